@@ -0,0 +1,296 @@
+//! Keeps query analysis stuff, e.g. to find out which tables a query reads and writes.
+
+use std::collections::BTreeSet;
+
+use select::{Select, SelectType, Column, DistinctType, SortKey};
+use insert::{Insert, Returning as InsertReturning};
+use update::{Update, Returning as UpdateReturning};
+use merge::{Merge, Source, Action};
+use visitor::{Visitor, Rewrite};
+
+/// Relations and columns referenced by a query.
+///
+/// Only things which are known structurally get here: tables and columns passed to the
+/// builders separately. Expressions written as strings (join and `WHERE` clauses, preformed
+/// `UPDATE` expressions) aren't parsed, and neither is SQL kept by a `Subquery`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct References<'a> {
+    /// Relations which are read: `FROM` and join targets, `UPDATE ... FROM` and its joins,
+    /// `MERGE ... USING`.
+    pub read_tables: BTreeSet<&'a str>,
+    /// Relations which are written: `INSERT`, `UPDATE` and `MERGE` targets.
+    pub written_tables: BTreeSet<&'a str>,
+    /// Columns which are read: `SELECT`, `DISTINCT ON`, `GROUP BY`, `ORDER BY`, `RETURNING`.
+    pub read_columns: BTreeSet<&'a str>,
+    /// Columns which are written: `INSERT` columns and structured `UPDATE` assignments,
+    /// including the ones of `MERGE` branches.
+    pub written_columns: BTreeSet<&'a str>
+}
+
+impl<'a> References<'a> {
+    /// Returns every referenced relation, no matter whether it's read or written.
+    pub fn tables(&self) -> BTreeSet<&'a str> {
+        self.read_tables.union(&self.written_tables).cloned().collect()
+    }
+
+    fn read_table(&mut self, table: &'a str) {
+        if let Some(table) = relation(table) {
+            self.read_tables.insert(table);
+        }
+    }
+
+    fn write_table(&mut self, table: &'a str) {
+        if let Some(table) = relation(table) {
+            self.written_tables.insert(table);
+        }
+    }
+
+    fn read_columns(&mut self, columns: &[&'a str]) {
+        self.read_columns.extend(columns.iter().cloned().filter(|x| is_identifier(x)));
+    }
+}
+
+impl<'a> Visitor<'a> for References<'a> {
+    fn visit_select(&mut self, query: &mut Select<'a>) {
+        self.read_table(query.get_from());
+        for join in query.get_joins() {
+            self.read_table(join.target);
+        }
+
+        match *query.get_select_type() {
+            SelectType::All => {},
+            SelectType::Specific(ref columns) => self.read_columns(columns),
+            SelectType::Extended(ref columns) => {
+                let columns = columns.iter()
+                    .filter_map(|x| match *x.expression() {
+                        Column::Simple(column) => Some(column),
+                        _ => None
+                    })
+                    .collect::<Vec<_>>();
+                self.read_columns(&columns);
+            }
+        }
+        if let DistinctType::Extended(ref columns) = *query.get_distinct() {
+            self.read_columns(columns);
+        }
+        let group_by = query.get_group_by().iter().flat_map(|x| x.columns()).collect::<Vec<_>>();
+        self.read_columns(&group_by);
+        let order_by = query.get_order_by().iter()
+            .filter_map(|x| match x.order_by {
+                SortKey::Simple(column) => Some(column),
+                _ => None
+            })
+            .collect::<Vec<_>>();
+        self.read_columns(&order_by);
+    }
+
+    fn visit_insert(&mut self, query: &mut Insert<'a>) {
+        self.write_table(query.get_table());
+        self.written_columns.extend(query.get_columns().iter().cloned().filter(|x| is_identifier(x)));
+
+        if let InsertReturning::Specified(ref columns) = *query.get_returning() {
+            self.read_columns(columns);
+        }
+    }
+
+    fn visit_update(&mut self, query: &mut Update<'a>) {
+        self.write_table(query.get_table());
+        for assignment in query.get_expressions() {
+            self.written_columns.extend(assignment.columns());
+        }
+        for (table, _) in query.get_from().items() {
+            self.read_table(table);
+        }
+        for join in query.get_joins() {
+            self.read_table(join.target);
+        }
+
+        if let UpdateReturning::Specified(ref columns) = *query.get_returning() {
+            self.read_columns(columns);
+        }
+    }
+
+    fn visit_merge(&mut self, query: &mut Merge<'a>) {
+        self.write_table(query.get_target());
+        if let Source::Table(table) = *query.get_source() {
+            self.read_table(table);
+        }
+
+        for branch in query.get_branches() {
+            match *branch.get_action() {
+                Action::Update(ref assignments) => {
+                    for assignment in assignments {
+                        self.written_columns.extend(assignment.columns());
+                    }
+                },
+                Action::Insert { ref columns, .. } => {
+                    self.written_columns.extend(columns.iter().cloned().filter(|x| is_identifier(x)));
+                },
+                Action::DoNothing | Action::Delete | Action::InsertDefault => {}
+            }
+        }
+    }
+}
+
+/// Collects relations and columns referenced by the query and queries nested into it.
+///
+/// # Example
+///
+/// ```
+/// use lithium::{Select, Insert};
+/// use lithium::analysis::references;
+///
+/// let select = Select::from("foo").columns(&["a", "COUNT(*)"]).join("bar", "bar.id = foo.id");
+/// let insert = Insert::into("bazz").columns("a").query(select);
+/// let refs = references(&insert);
+///
+/// assert_eq!(refs.read_tables.into_iter().collect::<Vec<_>>(), vec!["bar", "foo"]);
+/// assert_eq!(refs.written_tables.into_iter().collect::<Vec<_>>(), vec!["bazz"]);
+/// assert_eq!(refs.read_columns.into_iter().collect::<Vec<_>>(), vec!["a"]);
+/// ```
+pub fn references<'a, Q: Rewrite<'a> + Clone>(query: &Q) -> References<'a> {
+    let mut rv = References::default();
+    query.clone().walk(&mut rv);
+    rv
+}
+
+const AGGREGATES: [&str; 12] = [
+    "COUNT", "SUM", "AVG", "MIN", "MAX", "ARRAY_AGG", "STRING_AGG", "JSON_AGG", "JSONB_AGG",
+    "BOOL_AND", "BOOL_OR", "GROUP_CONCAT"
+];
+
+/// Looks for calls of well-known aggregate functions in an expression written as a string.
+///
+/// # Example
+///
+/// ```
+/// use lithium::analysis::has_aggregate;
+///
+/// assert!(has_aggregate("id, COUNT (*)"));
+/// assert!(!has_aggregate("account(id), summary"));
+/// ```
+pub fn has_aggregate(expression: &str) -> bool {
+    let expression = expression.to_uppercase();
+    AGGREGATES.iter().any(|name| {
+        expression.match_indices(name).any(|(position, _)| {
+            let before = expression[..position].chars().next_back();
+            let after = expression[position + name.len()..].trim_start().chars().next();
+            !before.is_some_and(|x| x.is_alphanumeric() || x == '_') && after == Some('(')
+        })
+    })
+}
+
+/// Drops an alias (`foo AS f`, `foo f`) and skips rendered subqueries.
+fn relation(table: &str) -> Option<&str> {
+    let table = table.trim();
+    if table.starts_with('(') {
+        return None;
+    }
+    table.split_whitespace().next()
+}
+
+fn is_identifier(expression: &str) -> bool {
+    !expression.is_empty() && expression.chars().all(|x| x.is_alphanumeric() || x == '_' || x == '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{references, relation, is_identifier, has_aggregate};
+    use select::{Select, Ordering, UnionMode, Union};
+    use update::Update;
+    use merge::{Merge, When};
+
+    #[test]
+    fn test_relation() {
+        assert_eq!(relation("foo"), Some("foo"));
+        assert_eq!(relation("public.foo AS f"), Some("public.foo"));
+        assert_eq!(relation("(SELECT * FROM foo) AS f"), None);
+    }
+
+    #[test]
+    fn test_has_aggregate() {
+        assert!(has_aggregate("max(a) AS m"));
+        assert!(has_aggregate("string_agg(name, ',')"));
+        assert!(!has_aggregate("summary, maximum"));
+    }
+
+    #[test]
+    fn test_is_identifier() {
+        assert!(is_identifier("foo.bar_1"));
+        assert!(!is_identifier("*"));
+        assert!(!is_identifier("COUNT(*)"));
+        assert!(!is_identifier("a AS b"));
+    }
+
+    #[test]
+    fn test_select() {
+        let subquery = Select::from("hidden").as_subquery().with_alias("h");
+        let query = Select::from("foo AS f")
+            .columns(&["f.a", "b"])
+            .distinct_on("c")
+            .left_join("bar", "bar.id = f.id")
+            .join(&subquery, "h.id = f.id")
+            .group_by("d")
+            .order_by("e", Ordering::Ascending);
+
+        let refs = references(&query);
+        assert_eq!(refs.read_tables.into_iter().collect::<Vec<_>>(), vec!["bar", "foo"]);
+        assert!(refs.written_tables.is_empty());
+        assert_eq!(refs.read_columns.into_iter().collect::<Vec<_>>(), vec!["b", "c", "d", "e", "f.a"]);
+    }
+
+    #[test]
+    fn test_aliases() {
+        let query = Select::from_as("foo", "f").column_as("f.a", "x").join_as("bar", "b", "b.id = f.id");
+        let refs = references(&query);
+        assert_eq!(refs.read_tables.into_iter().collect::<Vec<_>>(), vec!["bar", "foo"]);
+        assert_eq!(refs.read_columns.into_iter().collect::<Vec<_>>(), vec!["f.a"]);
+    }
+
+    #[test]
+    fn test_update() {
+        let query = Update::new("foo").set("a = bar.a").from("bar").returning(&["a", "b"]);
+
+        let refs = references(&query);
+        assert_eq!(refs.read_tables.into_iter().collect::<Vec<_>>(), vec!["bar"]);
+        assert_eq!(refs.written_tables.into_iter().collect::<Vec<_>>(), vec!["foo"]);
+        assert_eq!(refs.read_columns.into_iter().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert!(refs.written_columns.is_empty());
+
+        let query = query.set_value("c", "bar.c")
+            .set_default("d")
+            .set_row(&["e", "f"], Select::from("bazz").columns(&["e", "f"]))
+            .join("fizz", "fizz.id = bar.id");
+        let refs = references(&query);
+        assert_eq!(refs.written_columns.into_iter().collect::<Vec<_>>(), vec!["c", "d", "e", "f"]);
+        assert_eq!(refs.read_tables.into_iter().collect::<Vec<_>>(), vec!["bar", "bazz", "fizz"]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let query = Merge::into_as("foo", "t")
+            .using_as("bar", "s", "t.id = s.id")
+            .when(When::matched().set("a = s.a").set_value("b", "s.b"))
+            .when(When::not_matched().insert(&["id", "c"], vec!["s.id", "s.c"]));
+
+        let refs = references(&query);
+        assert_eq!(refs.read_tables.into_iter().collect::<Vec<_>>(), vec!["bar"]);
+        assert_eq!(refs.written_tables.into_iter().collect::<Vec<_>>(), vec!["foo"]);
+        assert_eq!(refs.written_columns.into_iter().collect::<Vec<_>>(), vec!["b", "c", "id"]);
+
+        let query = Merge::into("foo")
+            .using_query(Select::from("bar").join("bazz", "bazz.id = bar.id"), "s", "foo.id = s.id")
+            .when(When::matched().delete());
+        let refs = references(&query);
+        assert_eq!(refs.read_tables.into_iter().collect::<Vec<_>>(), vec!["bar", "bazz"]);
+        assert_eq!(refs.written_tables.into_iter().collect::<Vec<_>>(), vec!["foo"]);
+        assert!(refs.written_columns.is_empty());
+    }
+
+    #[test]
+    fn test_union() {
+        let union = Union::new(UnionMode::Simple, Select::from("foo"), Select::from("bar"));
+        let refs = references(&union);
+        assert_eq!(refs.tables().into_iter().collect::<Vec<_>>(), vec!["bar", "foo"]);
+    }
+}
@@ -0,0 +1,212 @@
+//! Keeps `CASE` expression stuff.
+
+use common::ToSQL;
+use select::{Column, IntoColumn, SortKey, IntoSortKey};
+use visitor::{Visitor, Rewrite};
+use where_cl::{WhereType, IntoWhereType};
+
+/// Represents `CASE` expression.
+///
+/// Conditions of the searched form (`Case::new`) accept anything `Where` accepts, while
+/// the simple form (`Case::simple`) compares its operand with values given to `when`.
+/// Results can be columns, literals or placeholders of bound parameters.
+///
+/// # Examples
+///
+/// ```
+/// use lithium::{ToSQL, Select, Where};
+/// use lithium::case::Case;
+///
+/// let status = Case::new()
+///     .when(Where::is_null("deleted_at"), "'active'")
+///     .when("deleted_at > now()", "'scheduled'")
+///     .otherwise("'deleted'")
+///     .alias("status");
+/// let query = Select::from("users").columns("id").column(status);
+/// let expected = {
+///     "SELECT id, CASE WHEN deleted_at IS NULL THEN 'active' \
+///     WHEN deleted_at > now() THEN 'scheduled' \
+///     ELSE 'deleted' END AS status FROM users".to_string()
+/// };
+/// assert_eq!(query.to_sql(), expected);
+/// ```
+///
+/// ```
+/// use lithium::{ToSQL, Update};
+/// use lithium::case::Case;
+///
+/// let priority = Case::simple("kind").when("'bug'", "1").when("'feature'", "2").otherwise("priority");
+/// let query = Update::new("issues").set_value("priority", priority);
+/// let expected = {
+///     "UPDATE issues SET priority = \
+///     CASE kind WHEN 'bug' THEN 1 WHEN 'feature' THEN 2 ELSE priority END".to_string()
+/// };
+/// assert_eq!(query.to_sql(), expected);
+/// ```
+#[derive(Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub struct Case<'a> {
+    operand: Option<Column<'a>>,
+    branches: Vec<(WhereType<'a>, Column<'a>)>,
+    otherwise: Option<Column<'a>>,
+    alias: Option<&'a str>
+}
+
+impl<'a> Case<'a> {
+    /// Method to start with the searched form: `CASE WHEN condition THEN ...`.
+    pub fn new() -> Self {
+        Case {
+            operand: None,
+            branches: vec![],
+            otherwise: None,
+            alias: None
+        }
+    }
+
+    /// Method to start with the simple form: `CASE operand WHEN value THEN ...`.
+    pub fn simple<T: IntoColumn<'a>>(operand: T) -> Self {
+        let mut rv = Self::new();
+        rv.operand = Some(operand.into_column());
+        rv
+    }
+
+    /// Adds `WHEN ... THEN ...` branch. Receives a condition for the searched form and
+    /// a value for the simple one.
+    pub fn when<C: IntoWhereType<'a>, R: IntoColumn<'a>>(mut self, condition: C, result: R) -> Self {
+        self.branches.push((condition.into_where_type(), result.into_column()));
+        self
+    }
+
+    /// Specifies `ELSE` result. `NULL` is returned by the database when there is none.
+    pub fn otherwise<T: IntoColumn<'a>>(mut self, result: T) -> Self {
+        self.otherwise = Some(result.into_column());
+        self
+    }
+
+    /// Specifies alias, which is used when the expression is a column.
+    pub fn alias(mut self, alias: &'a str) -> Self {
+        self.alias = Some(alias);
+        self
+    }
+
+    /// Returns the alias.
+    pub fn get_alias(&self) -> Option<&'a str> {
+        self.alias
+    }
+}
+
+impl<'a> ToSQL for Case<'a> {
+    fn to_sql(&self) -> String {
+        if self.branches.is_empty() {
+            // `CASE END` is not valid SQL
+            return match self.otherwise {
+                Some(ref result) => result.to_sql(),
+                None => "NULL".to_string()
+            };
+        }
+
+        let mut rv = String::new();
+        rv.push_str("CASE");
+        if let Some(ref operand) = self.operand {
+            rv.push(' ');
+            rv.push_str(&operand.to_sql());
+        }
+
+        for (condition, result) in &self.branches {
+            rv.push_str(&format!(" WHEN {} THEN {}", condition.to_sql(), result.to_sql()));
+        }
+
+        if let Some(ref result) = self.otherwise {
+            rv.push(' ');
+            rv.push_str("ELSE");
+            rv.push(' ');
+            rv.push_str(&result.to_sql());
+        }
+
+        rv.push(' ');
+        rv.push_str("END");
+        rv
+    }
+}
+
+impl<'a> IntoColumn<'a> for Case<'a> {
+    fn into_column(self) -> Column<'a> {
+        match self.alias {
+            Some(alias) => Column::Case(Box::new(self)).alias(alias),
+            None => Column::Case(Box::new(self))
+        }
+    }
+}
+
+impl<'a> IntoSortKey<'a> for Case<'a> {
+    fn into_sort_key(self) -> SortKey<'a> {
+        SortKey::Case(Box::new(self))
+    }
+}
+
+impl<'a> IntoWhereType<'a> for Case<'a> {
+    fn into_where_type(self) -> WhereType<'a> {
+        WhereType::Owned(self.to_sql())
+    }
+}
+
+impl<'a> Rewrite<'a> for Case<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        if let Some(ref mut operand) = self.operand {
+            operand.walk(visitor);
+        }
+        for (condition, result) in &mut self.branches {
+            condition.walk(visitor);
+            result.walk(visitor);
+        }
+        if let Some(ref mut result) = self.otherwise {
+            result.walk(visitor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Case;
+    use common::ToSQL;
+    use select::{Select, Ordering};
+    use where_cl::Where;
+    use params::{Parameters, Placeholder};
+
+    #[test]
+    fn test_searched() {
+        let case = Case::new()
+            .when(Where::with_and().expr("a > 1").expr("b < 2"), "x")
+            .when(Where::in_list("c", &["1", "2"]), "y");
+        assert_eq!(case.to_sql(), "CASE WHEN (a > 1 AND b < 2) THEN x WHEN c IN (1, 2) THEN y END");
+    }
+
+    #[test]
+    fn test_parameters() {
+        let mut params = Parameters::new(Placeholder::Numbered);
+        let case = Case::simple("status")
+            .when(params.push("new"), params.push(1))
+            .otherwise(params.push(0));
+        assert_eq!(case.to_sql(), "CASE status WHEN $1 THEN $2 ELSE $3 END");
+        assert_eq!(params.len(), 3);
+    }
+
+    #[test]
+    fn test_without_branches() {
+        assert_eq!(Case::new().to_sql(), "NULL");
+        assert_eq!(Case::new().otherwise("0").to_sql(), "0");
+    }
+
+    #[test]
+    fn test_order_by_and_filter() {
+        let query = Select::from("tasks")
+            .filter(Case::new().when("kind = 'a'", "x > 1").otherwise("TRUE"))
+            .order_by(Case::simple("priority").when("'high'", "1").otherwise("2"), Ordering::Ascending);
+        let expected = {
+            "SELECT * FROM tasks \
+            WHERE CASE WHEN kind = 'a' THEN x > 1 ELSE TRUE END \
+            ORDER BY CASE priority WHEN 'high' THEN 1 ELSE 2 END ASC"
+        };
+        assert_eq!(query.to_sql(), expected);
+    }
+}
@@ -1,13 +1,26 @@
 //! Keeps stuff (mostly traits) that is used (or is going to be) across different queries.
 
+use dialect::Dialect;
+
 pub trait ToSQL {
     fn to_sql(&self) -> String;
+
+    /// Generates SQL for the given dialect. Falls back to `to_sql` for things which
+    /// look the same in every dialect.
+    fn to_sql_for(&self, _dialect: Dialect) -> String {
+        self.to_sql()
+    }
 }
 
 /// Is used to build up methods which can receive either `&str` or `&Subquery`
 /// in a convenient way. You can find examples in some of `Select`'s methods.
 pub trait AsStr<'a> {
     fn as_str(&self) -> &'a str;
+
+    /// Returns an alias which comes along with the target, e.g. one of a `Subquery`.
+    fn alias(&self) -> Option<&'a str> {
+        None
+    }
 }
 
 impl<'a> AsStr<'a> for &'a str {
@@ -20,6 +33,10 @@ impl<'a> AsStr<'a> for &'a Subquery<'a> {
     fn as_str(&self) -> &'a str {
         &self.query
     }
+
+    fn alias(&self) -> Option<&'a str> {
+        self.alias
+    }
 }
 
 /// Is used to build up methods which can receive either `&str` or `&[&str; N]`
@@ -36,7 +53,7 @@ impl<'a> Pusheable<'a> for &'a str {
 
 impl<'a> Pusheable<'a> for &'a Subquery<'a> {
     fn push_to(&self, destination: &mut Vec<&'a str>) {
-        destination.push(self.as_str())
+        destination.push(&self.rendered)
     }
 }
 
@@ -61,45 +78,87 @@ pusheable_impls! {
 
 
 /// Struct that is used to keep result from `to_sql` of some query.
-/// If you use `with_alias` - keep in mind that it's changing content of
-/// `query` in **irreversible** way.  
-/// We do this because we need `&str` to have a nice
+/// The alias is kept apart from `query`, so it can be read with `get_alias` and replaced
+/// with another call of `with_alias`. Builders which receive a subquery as a table
+/// (`Select::from`, joins, `Update::from`) take the alias along with it.
+/// We keep SQL as `String` because we need `&str` to have a nice
 /// way of using subqueries and avoid forcing users to use `String` when they don't
 /// really need to.
+///
+/// # Example
+///
+/// ```
+/// use lithium::{ToSQL, Select};
+///
+/// let subquery = Select::from("foo").as_subquery().with_alias("f");
+/// assert_eq!(subquery.query, "(SELECT * FROM foo)");
+/// assert_eq!(subquery.get_alias(), Some("f"));
+/// assert_eq!(subquery.to_sql(), "(SELECT * FROM foo) AS f");
+///
+/// let subquery = subquery.with_alias("g");
+/// assert_eq!(Select::from(&subquery).to_sql(), "SELECT * FROM (SELECT * FROM foo) AS g");
+/// ```
 #[derive(Clone)]
 pub struct Subquery<'a> {
-    /// Keeps generated SQL
+    /// Keeps generated SQL in parentheses, without the alias.
     pub query: String,
-    alias: Option<&'a str> // FIXME: do we need this?
+    alias: Option<&'a str>,
+    /// Keeps `query` along with the alias for methods receiving `&str`, e.g. `Select::columns`.
+    rendered: String
 }
 
 impl<'a> Subquery<'a> {
     pub fn new(query: String) -> Self {
+        let query = format!("({})", query);
         Subquery {
-            query: format!("({})", query),
+            rendered: query.clone(),
+            query: query,
             alias: None
         }
     }
 
-    /// Appends `AS {alias}` to the `query` field
+    /// Specifies the alias, replacing the previous one.
     pub fn with_alias(mut self, alias: &'a str) -> Self {
         self.alias = Some(alias);
-        self.query.push_str(&format!(" AS {}", alias));
+        self.rendered = self.to_sql();
         self
     }
+
+    /// Returns the alias.
+    pub fn get_alias(&self) -> Option<&'a str> {
+        self.alias
+    }
+}
+
+impl<'a> ToSQL for Subquery<'a> {
+    fn to_sql(&self) -> String {
+        match self.alias {
+            Some(alias) => format!("{} AS {}", self.query, alias),
+            None => self.query.clone()
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Subquery;
+    use super::{Subquery, ToSQL, Pusheable};
 
+    #[test]
     fn test_subquery() {
         let subquery = Subquery::new("blah".to_string());
         assert_eq!(subquery.query, "(blah)".to_string());
+        assert_eq!(subquery.to_sql(), "(blah)".to_string());
     }
 
+    #[test]
     fn test_subquery_with_alias() {
         let subquery = Subquery::new("blah".to_string()).with_alias("foo");
-        assert_eq!(subquery.query, "(blah) AS foo".to_string());
+        assert_eq!(subquery.query, "(blah)".to_string());
+        assert_eq!(subquery.to_sql(), "(blah) AS foo".to_string());
+
+        let subquery = subquery.with_alias("bar");
+        let mut columns = vec![];
+        (&subquery).push_to(&mut columns);
+        assert_eq!(columns, vec!["(blah) AS bar"]);
     }
 }
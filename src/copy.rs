@@ -0,0 +1,455 @@
+//! Keeps `COPY` (PostgreSQL) related stuff: the statement itself and `Encoder`, which turns rows
+//! into data sent after `COPY ... FROM STDIN`.
+//!
+//! # Example
+//!
+//! ```
+//! use lithium::copy::{Copy, Format};
+//! use lithium::params::Value;
+//!
+//! let copy = Copy::from_stdin("users").columns(&["id", "name"]).format(Format::Csv).header();
+//! assert_eq!(copy.to_sql(), "COPY users (id, name) FROM STDIN WITH (FORMAT csv, HEADER)");
+//!
+//! let encoder = copy.encoder().unwrap();
+//! let mut data = encoder.header(copy.get_columns());
+//! data.push_str(&encoder.row(&[Value::Int(1), Value::from("Smith, John")]));
+//! data.push_str(&encoder.row(&[Value::Int(2), Value::Null]));
+//! assert_eq!(data, "id,name\n1,\"Smith, John\"\n2,\n");
+//! ```
+
+use common::{ToSQL, Pusheable};
+use params::Value;
+use select::Select;
+use visitor::{Visitor, Rewrite};
+
+/// Format of `COPY` data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Format {
+    /// Tab-separated values with backslash escapes, the default one.
+    Text,
+    Csv,
+    /// Binary format of PostgreSQL, `Encoder` doesn't support it.
+    Binary
+}
+
+impl Format {
+    pub fn to_sql(&self) -> &str {
+        match *self {
+            Format::Text => "text",
+            Format::Csv => "csv",
+            Format::Binary => "binary"
+        }
+    }
+}
+
+/// Where rows are copied from or to.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Target<'a> {
+    /// Table with optional columns, which can be copied both ways.
+    Table {
+        table: &'a str,
+        columns: Vec<&'a str>
+    },
+    /// Results of a query, which can only be copied `TO STDOUT`.
+    Query(Box<Select<'a>>)
+}
+
+/// Direction of `COPY`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Direction {
+    FromStdin,
+    ToStdout
+}
+
+/// Represents `COPY` statement.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub struct Copy<'a> {
+    target: Target<'a>,
+    direction: Direction,
+    format: Option<Format>,
+    header: bool,
+    delimiter: Option<char>,
+    null: Option<&'a str>
+}
+
+impl<'a> Copy<'a> {
+    fn new(target: Target<'a>, direction: Direction) -> Self {
+        Copy {
+            target: target,
+            direction: direction,
+            format: None,
+            header: false,
+            delimiter: None,
+            null: None
+        }
+    }
+
+    /// Method to start with loading rows into a table.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::copy::Copy;
+    /// let copy = Copy::from_stdin("test_table").columns("foo").columns(&["bar"]);
+    /// assert_eq!(copy.to_sql(), "COPY test_table (foo, bar) FROM STDIN".to_string());
+    /// ```
+    pub fn from_stdin(table: &'a str) -> Self {
+        Self::new(Target::Table { table: table, columns: vec![] }, Direction::FromStdin)
+    }
+
+    /// Method to start with dumping rows of a table.
+    pub fn to_stdout(table: &'a str) -> Self {
+        Self::new(Target::Table { table: table, columns: vec![] }, Direction::ToStdout)
+    }
+
+    /// Method to start with dumping results of a query.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Select;
+    /// use lithium::copy::{Copy, Format};
+    /// let copy = Copy::query(Select::from("foo").columns("a").filter("b > 1")).format(Format::Csv).delimiter(';');
+    /// let expected = "COPY (SELECT a FROM foo WHERE b > 1) TO STDOUT WITH (FORMAT csv, DELIMITER ';')".to_string();
+    /// assert_eq!(copy.to_sql(), expected);
+    /// ```
+    pub fn query(query: Select<'a>) -> Self {
+        Self::new(Target::Query(Box::new(query)), Direction::ToStdout)
+    }
+
+    /// Specifies columns of the table. Does nothing when results of a query are copied.
+    pub fn columns<T: Pusheable<'a>>(mut self, input_columns: T) -> Self {
+        if let Target::Table { ref mut columns, .. } = self.target {
+            input_columns.push_to(columns);
+        }
+        self
+    }
+
+    /// Specifies `FORMAT` option.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Specifies `HEADER` option: the first line of CSV keeps names of the columns.
+    pub fn header(mut self) -> Self {
+        self.header = true;
+        self
+    }
+
+    /// Specifies `DELIMITER` option. PostgreSQL doesn't accept a newline, a carriage return
+    /// and, in text format, a backslash, a dot, letters or digits.
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Specifies `NULL` option, which is a string representing `NULL`.
+    pub fn null(mut self, null: &'a str) -> Self {
+        self.null = Some(null);
+        self
+    }
+
+    /// Returns the table or the query.
+    pub fn get_target(&self) -> &Target<'a> {
+        &self.target
+    }
+
+    /// Returns columns of the table, which is empty for a query.
+    pub fn get_columns(&self) -> &[&'a str] {
+        match self.target {
+            Target::Table { ref columns, .. } => columns,
+            Target::Query(_) => &[]
+        }
+    }
+
+    pub fn get_direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns `FORMAT` option, which is `Text` when it's not specified.
+    pub fn get_format(&self) -> Format {
+        self.format.unwrap_or(Format::Text)
+    }
+
+    /// Returns an encoder of data in the format of the statement, which is `None` for `Binary`.
+    pub fn encoder(&self) -> Option<Encoder> {
+        let mut rv = Encoder::new(self.get_format())?;
+        if let Some(delimiter) = self.delimiter {
+            rv = rv.delimiter(delimiter);
+        }
+        if let Some(null) = self.null {
+            rv = rv.null(null);
+        }
+        Some(rv)
+    }
+
+    fn options(&self) -> Vec<String> {
+        let mut rv = vec![];
+        if let Some(format) = self.format {
+            rv.push(format!("FORMAT {}", format.to_sql()));
+        }
+        if self.header {
+            rv.push("HEADER".to_string());
+        }
+        if let Some(delimiter) = self.delimiter {
+            rv.push(format!("DELIMITER {}", quote(&delimiter.to_string())));
+        }
+        if let Some(null) = self.null {
+            rv.push(format!("NULL {}", quote(null)));
+        }
+        rv
+    }
+
+    /// Generates SQL.
+    pub fn to_sql(&self) -> String {
+        let mut rv = String::new();
+        rv.push_str("COPY");
+        rv.push(' ');
+
+        match self.target {
+            Target::Table { table, ref columns } => {
+                rv.push_str(table);
+                if !columns.is_empty() {
+                    rv.push(' ');
+                    rv.push('(');
+                    rv.push_str(&columns.join(", "));
+                    rv.push(')');
+                }
+            },
+            Target::Query(ref query) => {
+                rv.push('(');
+                rv.push_str(&query.to_sql());
+                rv.push(')');
+            }
+        }
+
+        rv.push(' ');
+        match self.direction {
+            Direction::FromStdin => rv.push_str("FROM STDIN"),
+            Direction::ToStdout => rv.push_str("TO STDOUT")
+        }
+
+        let options = self.options();
+        if !options.is_empty() {
+            rv.push(' ');
+            rv.push_str(&format!("WITH ({})", options.join(", ")));
+        }
+        rv
+    }
+}
+
+impl<'a> ToSQL for Copy<'a> {
+    fn to_sql(&self) -> String {
+        Copy::to_sql(self)
+    }
+}
+
+impl<'a> Rewrite<'a> for Copy<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        match self.target {
+            Target::Table { ref mut table, .. } => visitor.visit_table(table),
+            Target::Query(ref mut query) => query.walk(visitor)
+        }
+    }
+}
+
+/// Quotes a string literal.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Encodes rows into `COPY` data in text or CSV format. Every row ends with a newline.
+///
+/// # Example
+///
+/// ```
+/// use lithium::copy::{Encoder, Format};
+/// use lithium::params::Value;
+///
+/// let encoder = Encoder::new(Format::Text).unwrap();
+/// let row = encoder.row(&[Value::Int(1), Value::from("a\tb\\c"), Value::Null, Value::Bool(true)]);
+/// assert_eq!(row, "1\ta\\tb\\\\c\t\\N\tt\n");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Encoder {
+    format: Format,
+    delimiter: char,
+    null: String
+}
+
+impl Encoder {
+    /// Creates an encoder with defaults of the format, which is `None` for `Binary`.
+    pub fn new(format: Format) -> Option<Self> {
+        match format {
+            Format::Text => Some(Encoder { format: format, delimiter: '\t', null: "\\N".to_string() }),
+            Format::Csv => Some(Encoder { format: format, delimiter: ',', null: String::new() }),
+            Format::Binary => None
+        }
+    }
+
+    /// Overrides the delimiter, which has to match `DELIMITER` option, see `Copy::delimiter`
+    /// for the characters PostgreSQL doesn't accept.
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Overrides the representation of `NULL`, which has to match `NULL` option.
+    pub fn null(mut self, null: &str) -> Self {
+        self.null = null.to_string();
+        self
+    }
+
+    /// Encodes the header line, which is expected with `HEADER` option.
+    pub fn header(&self, columns: &[&str]) -> String {
+        let columns = columns.iter().map(|x| Value::from(*x)).collect::<Vec<_>>();
+        self.row(&columns)
+    }
+
+    /// Encodes a row.
+    pub fn row(&self, row: &[Value]) -> String {
+        let mut rv = String::new();
+        for (i, value) in row.iter().enumerate() {
+            if i > 0 {
+                rv.push(self.delimiter);
+            }
+            // every value goes through escaping, since a delimiter can be e.g. `-` of negative numbers
+            let value = match *value {
+                Value::Null => {
+                    rv.push_str(&self.null);
+                    continue;
+                },
+                Value::Bool(value) => if value { "t" } else { "f" }.to_string(),
+                Value::Float(value) if value.is_infinite() => {
+                    if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+                },
+                Value::Text(ref value) => value.clone(),
+                ref value => value.to_string()
+            };
+            rv.push_str(&self.text(&value));
+        }
+        rv.push('\n');
+        rv
+    }
+
+    fn text(&self, value: &str) -> String {
+        match self.format {
+            Format::Csv => {
+                // empty strings and ones looking like `NULL` are quoted to tell them apart
+                let is_quoted = value.is_empty() || value == self.null || value == "\\." ||
+                    value.chars().any(|x| x == self.delimiter || x == '"' || x == '\n' || x == '\r');
+                if is_quoted {
+                    format!("\"{}\"", value.replace('"', "\"\""))
+                } else {
+                    value.to_string()
+                }
+            },
+            _ => {
+                let mut rv = String::new();
+                for x in value.chars() {
+                    match x {
+                        '\\' => rv.push_str("\\\\"),
+                        '\n' => rv.push_str("\\n"),
+                        '\r' => rv.push_str("\\r"),
+                        '\t' => rv.push_str("\\t"),
+                        x if x == self.delimiter => {
+                            rv.push('\\');
+                            rv.push(x);
+                        },
+                        x => rv.push(x)
+                    }
+                }
+                // NULL is matched before backslashes are processed, so one of them makes
+                // the value differ from it; letters and digits of escape sequences are octal
+                if !rv.is_empty() && rv == self.null {
+                    let first = rv.remove(0);
+                    let escaped = if "bfnrtvx01234567".contains(first) {
+                        format!("\\{:03o}", first as u32)
+                    } else {
+                        format!("\\{}", first)
+                    };
+                    rv.insert_str(0, &escaped);
+                }
+                rv
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Copy, Format, Encoder, Target, Direction};
+    use params::Value;
+    use select::Select;
+    use visitor::{Visitor, rewrite};
+
+    #[test]
+    fn test_statements() {
+        let copy = Copy::to_stdout("test_table").columns(&["a", "b"]);
+        assert_eq!(copy.to_sql(), "COPY test_table (a, b) TO STDOUT");
+        assert!(copy.get_direction() == Direction::ToStdout);
+        assert_eq!(copy.get_format(), Format::Text);
+
+        let copy = Copy::from_stdin("test_table").format(Format::Text).delimiter('\'').null("");
+        assert_eq!(copy.to_sql(), "COPY test_table FROM STDIN WITH (FORMAT text, DELIMITER '''', NULL '')");
+
+        let copy = Copy::query(Select::from("foo")).columns("a").format(Format::Binary);
+        assert_eq!(copy.to_sql(), "COPY (SELECT * FROM foo) TO STDOUT WITH (FORMAT binary)");
+        assert!(copy.get_columns().is_empty());
+        assert!(*copy.get_target() == Target::Query(Box::new(Select::from("foo"))));
+    }
+
+    #[test]
+    fn test_text() {
+        let encoder = Copy::from_stdin("foo").delimiter('|').encoder().unwrap();
+        let row = encoder.row(&[Value::from("a|b\nc"), Value::Float(1.5), Value::Float(-1.0 / 0.0), Value::Null]);
+        assert_eq!(row, "a\\|b\\nc|1.5|-Infinity|\\N\n");
+        assert_eq!(encoder.row(&[]), "\n");
+
+        let encoder = encoder.delimiter('-').null("NULL");
+        let row = encoder.row(&[Value::from("NULL"), Value::Null, Value::Float(-1.5), Value::from("NULLS")]);
+        assert_eq!(row, "\\NULL-NULL-\\-1.5-NULLS\n");
+        assert_eq!(encoder.null("t").row(&[Value::Bool(true), Value::from("t")]), "\\164-\\164\n");
+    }
+
+    #[test]
+    fn test_csv() {
+        let encoder = Encoder::new(Format::Csv).unwrap();
+        let row = encoder.row(&[Value::from(""), Value::Null, Value::from("say \"hi\""), Value::Bool(false)]);
+        assert_eq!(row, "\"\",,\"say \"\"hi\"\"\",f\n");
+        assert_eq!(encoder.row(&[Value::from("\\."), Value::from("a\r\nb")]), "\"\\.\",\"a\r\nb\"\n");
+
+        let encoder = encoder.delimiter(';').null("NULL");
+        assert_eq!(encoder.row(&[Value::from("a,b;c"), Value::from("NULL"), Value::Null]), "\"a,b;c\";\"NULL\";NULL\n");
+        assert_eq!(encoder.header(&["id", "name"]), "id;name\n");
+
+        let encoder = encoder.delimiter('-').null("1");
+        assert_eq!(encoder.row(&[Value::Int(-1), Value::Int(1), Value::Null]), "\"-1\"-\"1\"-1\n");
+    }
+
+    #[test]
+    fn test_binary() {
+        assert!(Copy::from_stdin("foo").format(Format::Binary).encoder().is_none());
+        assert!(Encoder::new(Format::Binary).is_none());
+    }
+
+    struct Sharding;
+
+    impl<'a> Visitor<'a> for Sharding {
+        fn visit_table(&mut self, table: &mut &'a str) {
+            *table = "foo_07";
+        }
+    }
+
+    #[test]
+    fn test_rewrite() {
+        let mut copy = Copy::query(Select::from("foo"));
+        rewrite(&mut copy, &mut Sharding);
+        assert_eq!(copy.to_sql(), "COPY (SELECT * FROM foo_07) TO STDOUT");
+    }
+}
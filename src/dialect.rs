@@ -0,0 +1,54 @@
+//! Keeps SQL dialect stuff.
+//!
+//! Queries are rendered for PostgreSQL by `ToSQL::to_sql`. Use `ToSQL::to_sql_for` to get SQL
+//! for another database; parts which don't differ between databases are rendered the same way.
+
+use params::Placeholder;
+
+/// Database SQL is generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Dialect {
+    #[default]
+    PostgreSQL,
+    MySQL,
+    SQLite
+}
+
+impl Dialect {
+    /// Style of placeholders used by the database.
+    pub fn placeholder(&self) -> Placeholder {
+        match *self {
+            Dialect::PostgreSQL => Placeholder::Numbered,
+            Dialect::MySQL | Dialect::SQLite => Placeholder::Question
+        }
+    }
+
+    /// The maximum number of bound parameters in a statement. SQLite before 3.32.0 allows
+    /// only 999 of them, which has to be configured explicitly, e.g. with `Batches::max_parameters`.
+    pub fn max_parameters(&self) -> usize {
+        match *self {
+            Dialect::PostgreSQL | Dialect::MySQL => 65535,
+            Dialect::SQLite => 32766
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dialect;
+    use params::Placeholder;
+
+    #[test]
+    fn test_placeholder() {
+        assert_eq!(Dialect::default().placeholder(), Placeholder::Numbered);
+        assert_eq!(Dialect::MySQL.placeholder(), Placeholder::Question);
+        assert_eq!(Dialect::SQLite.placeholder(), Placeholder::Question);
+    }
+
+    #[test]
+    fn test_max_parameters() {
+        assert_eq!(Dialect::PostgreSQL.max_parameters(), 65535);
+        assert_eq!(Dialect::SQLite.max_parameters(), 32766);
+    }
+}
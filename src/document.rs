@@ -0,0 +1,331 @@
+//! Keeps `Document`: an owned copy of serialized data which queries can be deserialized from.
+//!
+//! Queries borrow their strings, so deserializing them straight from the input works only when
+//! no string has to be unescaped (e.g. `"\"order\""` in JSON can't be borrowed). `Document` reads
+//! the input into owned, unescaped strings first and then lends them to the query.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate lithium;
+//! # extern crate serde_json;
+//! # fn main() {
+//! use lithium::{Select, ToSQL};
+//! use lithium::document::Document;
+//!
+//! let json = serde_json::to_string(&Select::from("\"order\"")).unwrap();
+//! let document: Document = serde_json::from_str(&json).unwrap();
+//! let query: Select = document.decode().unwrap();
+//! assert_eq!(query.to_sql(), "SELECT * FROM \"order\"");
+//! # }
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::slice;
+
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess,
+                VariantAccess, Visitor};
+
+/// Owned serialized data, see the module documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    root: Node
+}
+
+impl Document {
+    /// Deserializes a query (or anything else) borrowing strings from the document.
+    pub fn decode<'a, T: Deserialize<'a>>(&'a self) -> Result<T, DocumentError> {
+        T::deserialize(&self.root)
+    }
+}
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Node::deserialize(deserializer).map(|root| Document { root: root })
+    }
+}
+
+/// Error of `Document::decode`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentError(String);
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for DocumentError {}
+
+impl de::Error for DocumentError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DocumentError(msg.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Null,
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    String(String),
+    Seq(Vec<Node>),
+    Map(Vec<(Node, Node)>)
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Node, E> {
+        Ok(Node::Bool(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Node, E> {
+        Ok(Node::I64(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Node, E> {
+        Ok(Node::U64(value))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Node, E> {
+        Ok(Node::F64(value))
+    }
+
+    fn visit_char<E: de::Error>(self, value: char) -> Result<Node, E> {
+        Ok(Node::String(value.to_string()))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Node, E> {
+        Ok(Node::String(value.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Node, E> {
+        Ok(Node::String(value))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Node, E> {
+        Ok(Node::Null)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Node, E> {
+        Ok(Node::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Node, D::Error> {
+        Node::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<Node, D::Error> {
+        Node::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+        let mut items = vec![];
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Node::Seq(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+        let mut entries = vec![];
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Node::Map(entries))
+    }
+}
+
+impl<'de> Deserializer<'de> for &'de Node {
+    type Error = DocumentError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocumentError> {
+        match *self {
+            Node::Null => visitor.visit_unit(),
+            Node::Bool(value) => visitor.visit_bool(value),
+            Node::U64(value) => visitor.visit_u64(value),
+            Node::I64(value) => visitor.visit_i64(value),
+            Node::F64(value) => visitor.visit_f64(value),
+            Node::String(ref value) => visitor.visit_borrowed_str(value),
+            Node::Seq(ref items) => visitor.visit_seq(Items(items.iter())),
+            Node::Map(ref entries) => visitor.visit_map(Entries { iter: entries.iter(), value: None })
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocumentError> {
+        match *self {
+            Node::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V)
+        -> Result<V::Value, DocumentError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V)
+        -> Result<V::Value, DocumentError> {
+        match *self {
+            Node::String(_) => visitor.visit_enum(Variant { name: self, value: None }),
+            Node::Map(ref entries) if entries.len() == 1 => {
+                let (name, value) = &entries[0];
+                visitor.visit_enum(Variant { name: name, value: Some(value) })
+            },
+            _ => Err(de::Error::custom("expected an enum variant"))
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct Items<'a>(slice::Iter<'a, Node>);
+
+impl<'a> SeqAccess<'a> for Items<'a> {
+    type Error = DocumentError;
+
+    fn next_element_seed<T: DeserializeSeed<'a>>(&mut self, seed: T) -> Result<Option<T::Value>, DocumentError> {
+        self.0.next().map(|item| seed.deserialize(item)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct Entries<'a> {
+    iter: slice::Iter<'a, (Node, Node)>,
+    value: Option<&'a Node>
+}
+
+impl<'a> MapAccess<'a> for Entries<'a> {
+    type Error = DocumentError;
+
+    fn next_key_seed<K: DeserializeSeed<'a>>(&mut self, seed: K) -> Result<Option<K::Value>, DocumentError> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key).map(Some)
+            },
+            None => Ok(None)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'a>>(&mut self, seed: V) -> Result<V::Value, DocumentError> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::custom("value is missing"))
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct Variant<'a> {
+    name: &'a Node,
+    value: Option<&'a Node>
+}
+
+impl<'a> EnumAccess<'a> for Variant<'a> {
+    type Error = DocumentError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'a>>(self, seed: V) -> Result<(V::Value, Self), DocumentError> {
+        seed.deserialize(self.name).map(|name| (name, self))
+    }
+}
+
+impl<'a> VariantAccess<'a> for Variant<'a> {
+    type Error = DocumentError;
+
+    fn unit_variant(self) -> Result<(), DocumentError> {
+        match self.value {
+            None | Some(&Node::Null) => Ok(()),
+            Some(_) => Err(de::Error::custom("expected a unit variant"))
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'a>>(self, seed: T) -> Result<T::Value, DocumentError> {
+        match self.value {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::custom("expected a newtype variant"))
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'a>>(self, _len: usize, visitor: V) -> Result<V::Value, DocumentError> {
+        match self.value {
+            Some(value) => value.deserialize_any(visitor),
+            None => Err(de::Error::custom("expected a tuple variant"))
+        }
+    }
+
+    fn struct_variant<V: Visitor<'a>>(self, _fields: &'static [&'static str], visitor: V)
+        -> Result<V::Value, DocumentError> {
+        match self.value {
+            Some(value) => value.deserialize_any(visitor),
+            None => Err(de::Error::custom("expected a struct variant"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Document;
+    use common::ToSQL;
+    use select::Select;
+    use insert::Insert;
+    use update::Update;
+
+    fn round_trip<'a, T: ::serde::Serialize>(query: &T, document: &'a mut Option<Document>) -> &'a Document {
+        let json = ::serde_json::to_string(query).unwrap();
+        *document = Some(::serde_json::from_str(&json).unwrap());
+        document.as_ref().unwrap()
+    }
+
+    #[test]
+    fn test_escaped_strings() {
+        let query = Select::from("\"order\"").columns(&["\"user id\""]).filter("name = 'a\nb'");
+        let json = ::serde_json::to_string(&query).unwrap();
+        assert!(::serde_json::from_str::<Select>(&json).is_err());
+
+        let mut document = None;
+        let restored: Select = round_trip(&query, &mut document).decode().unwrap();
+        assert_eq!(restored.to_sql(), query.to_sql());
+        assert_eq!(restored.to_sql(), "SELECT \"user id\" FROM \"order\" WHERE name = 'a\nb'");
+
+        let insert = Insert::into("\"order\"").columns(&["a"]).values(vec!["'\\x'"]);
+        let restored: Insert = round_trip(&insert, &mut document).decode().unwrap();
+        assert_eq!(restored.to_sql(), insert.to_sql());
+
+        let update = Update::new("\"order\"").set("a = 1");
+        let restored: Update = round_trip(&update, &mut document).decode().unwrap();
+        assert_eq!(restored.to_sql(), update.to_sql());
+    }
+
+    #[test]
+    fn test_errors() {
+        let document: Document = ::serde_json::from_str("[1, 2]").unwrap();
+        assert!(document.decode::<Select>().is_err());
+    }
+}
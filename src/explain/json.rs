@@ -0,0 +1,268 @@
+//! Minimal JSON reader for plans printed by `EXPLAIN (FORMAT JSON)`, so parsing them doesn't
+//! need extra dependencies.
+
+use std::char;
+
+/// The maximum nesting of arrays and objects, plans are far shallower.
+const MAX_DEPTH: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>)
+}
+
+impl Json {
+    /// Returns a value of the object's key.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref pairs) => pairs.iter().find(|x| x.0 == key).map(|x| &x.1),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref value) => Some(value),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Json::Number(value) => Some(value),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match *self {
+            Json::Array(ref values) => Some(values),
+            _ => None
+        }
+    }
+}
+
+/// Parses a document, returns the byte offset where it became invalid on failure.
+pub fn parse(input: &str) -> Result<Json, usize> {
+    let mut parser = Parser { input: input.as_bytes(), position: 0, depth: 0 };
+    let value = parser.value()?;
+    parser.whitespace();
+    if parser.position == parser.input.len() {
+        Ok(value)
+    } else {
+        Err(parser.position)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+    depth: usize
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).cloned()
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), usize> {
+        self.whitespace();
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.position)
+        }
+    }
+
+    fn literal(&mut self, literal: &str, value: Json) -> Result<Json, usize> {
+        if self.input[self.position..].starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(value)
+        } else {
+            Err(self.position)
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, usize> {
+        self.whitespace();
+        match self.peek() {
+            Some(b'{') => self.nested(Parser::object),
+            Some(b'[') => self.nested(Parser::array),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            _ => Err(self.position)
+        }
+    }
+
+    fn nested<F: FnOnce(&mut Self) -> Result<Json, usize>>(&mut self, parse: F) -> Result<Json, usize> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.position);
+        }
+        self.depth += 1;
+        let rv = parse(self);
+        self.depth -= 1;
+        rv
+    }
+
+    fn object(&mut self) -> Result<Json, usize> {
+        self.position += 1;
+        let mut pairs = vec![];
+        self.whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(pairs));
+        }
+        loop {
+            self.whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.position);
+            }
+            let key = self.string()?;
+            self.expect(b':')?;
+            pairs.push((key, self.value()?));
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(pairs));
+                },
+                _ => return Err(self.position)
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, usize> {
+        self.position += 1;
+        let mut values = vec![];
+        self.whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                },
+                _ => return Err(self.position)
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, usize> {
+        let start = self.position;
+        while let Some(b'-') | Some(b'+') | Some(b'.') | Some(b'e') | Some(b'E') | Some(b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+        ::std::str::from_utf8(&self.input[start..self.position]).ok()
+            .and_then(|x| x.parse().ok())
+            .map(Json::Number)
+            .ok_or(start)
+    }
+
+    fn hex(&mut self) -> Result<u32, usize> {
+        let digits = self.input.get(self.position..self.position + 4).ok_or(self.position)?;
+        let value = ::std::str::from_utf8(digits).ok()
+            .and_then(|x| u32::from_str_radix(x, 16).ok())
+            .ok_or(self.position)?;
+        self.position += 4;
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<String, usize> {
+        self.position += 1;
+        let mut bytes = vec![];
+        loop {
+            match self.peek() {
+                None => return Err(self.position),
+                Some(b'"') => {
+                    self.position += 1;
+                    return String::from_utf8(bytes).map_err(|_| self.position);
+                },
+                Some(b'\\') => {
+                    self.position += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            self.position += 1;
+                            let start = self.position;
+                            let mut code = self.hex()?;
+                            if (0xD800..0xDC00).contains(&code) && self.input[self.position..].starts_with(b"\\u") {
+                                self.position += 2;
+                                let low = self.hex()?;
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+                            let value = char::from_u32(code).ok_or(start)?;
+                            let mut buffer = [0; 4];
+                            bytes.extend_from_slice(value.encode_utf8(&mut buffer).as_bytes());
+                            continue;
+                        },
+                        _ => return Err(self.position)
+                    };
+                    self.position += 1;
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut buffer).as_bytes());
+                },
+                Some(byte) => {
+                    self.position += 1;
+                    bytes.push(byte);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Json};
+
+    #[test]
+    fn test_parse() {
+        let value = parse(r#" [{"a": 1.5e1, "b": [true, false, null], "c": "x\"\u00e9\ud83d\ude00"}, {}] "#).unwrap();
+        assert_eq!(value, Json::Array(vec![
+            Json::Object(vec![
+                ("a".to_string(), Json::Number(15.0)),
+                ("b".to_string(), Json::Array(vec![Json::Bool(true), Json::Bool(false), Json::Null])),
+                ("c".to_string(), Json::String("x\"é😀".to_string()))
+            ]),
+            Json::Object(vec![])
+        ]));
+        assert_eq!(parse("[1, ]"), Err(4));
+        assert_eq!(parse("{\"a\" 1}"), Err(5));
+        assert_eq!(parse("1 2"), Err(2));
+    }
+
+    #[test]
+    fn test_depth() {
+        let input = format!("{}{}", "[".repeat(256), "]".repeat(256));
+        assert!(parse(&input).is_ok());
+        assert_eq!(parse(&"[".repeat(200000)), Err(256));
+    }
+}
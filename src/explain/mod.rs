@@ -0,0 +1,161 @@
+//! Keeps `EXPLAIN` related stuff: `Explain` wrapping a query and `Plan` parsed from its output.
+//!
+//! # Example
+//!
+//! ```
+//! use lithium::{Select, ToSQL, Dialect};
+//! use lithium::explain::{Explain, Format};
+//!
+//! let query = Select::from("users").filter("active");
+//! let explain = Explain::new(&query).analyze().buffers().format(Format::Json);
+//! assert_eq!(explain.to_sql(), "EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON) SELECT * FROM users WHERE active");
+//! assert_eq!(explain.to_sql_for(Dialect::SQLite), "EXPLAIN QUERY PLAN SELECT * FROM users WHERE active");
+//! ```
+
+pub mod plan;
+mod json;
+
+use common::ToSQL;
+use dialect::Dialect;
+
+pub use self::plan::{Plan, PlanError};
+
+/// Output format of `EXPLAIN`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Format {
+    Text,
+    Json
+}
+
+/// Represents `EXPLAIN` of a query. Keeps a reference, so the query is rendered for the dialect
+/// `EXPLAIN` is rendered for.
+///
+/// PostgreSQL gets every option. MySQL gets `ANALYZE` or `FORMAT=JSON`, since versions before
+/// 8.3 reject them together and `ANALYZE` wins then. SQLite always gets
+/// `EXPLAIN QUERY PLAN`, which can be parsed with `Plan::from_sqlite_rows`. Keep in mind that
+/// `ANALYZE` executes the query.
+#[derive(Clone)]
+pub struct Explain<'q> {
+    query: &'q dyn ToSQL,
+    analyze: bool,
+    verbose: bool,
+    buffers: bool,
+    format: Option<Format>
+}
+
+impl<'q> Explain<'q> {
+    /// Method to start with.
+    pub fn new(query: &'q dyn ToSQL) -> Self {
+        Explain {
+            query: query,
+            analyze: false,
+            verbose: false,
+            buffers: false,
+            format: None
+        }
+    }
+
+    /// Adds `ANALYZE`, which executes the query to get actual rows and timings.
+    pub fn analyze(mut self) -> Self {
+        self.analyze = true;
+        self
+    }
+
+    /// Adds `VERBOSE` (PostgreSQL).
+    pub fn verbose(mut self) -> Self {
+        self.verbose = true;
+        self
+    }
+
+    /// Adds `BUFFERS` (PostgreSQL).
+    pub fn buffers(mut self) -> Self {
+        self.buffers = true;
+        self
+    }
+
+    /// Specifies the output format, `Format::Json` output of PostgreSQL can be parsed with
+    /// `Plan::from_json`.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Returns the output format.
+    pub fn get_format(&self) -> Option<Format> {
+        self.format
+    }
+}
+
+impl<'q> ToSQL for Explain<'q> {
+    fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
+        let query = self.query.to_sql_for(dialect);
+        match dialect {
+            Dialect::PostgreSQL => {
+                let mut options = vec![];
+                if self.analyze {
+                    options.push("ANALYZE");
+                }
+                if self.verbose {
+                    options.push("VERBOSE");
+                }
+                if self.buffers {
+                    options.push("BUFFERS");
+                }
+                match self.format {
+                    Some(Format::Text) => options.push("FORMAT TEXT"),
+                    Some(Format::Json) => options.push("FORMAT JSON"),
+                    None => {}
+                }
+                if options.is_empty() {
+                    format!("EXPLAIN {}", query)
+                } else {
+                    format!("EXPLAIN ({}) {}", options.join(", "), query)
+                }
+            },
+            Dialect::MySQL => {
+                let mut rv = String::new();
+                rv.push_str("EXPLAIN");
+                if self.analyze {
+                    rv.push_str(" ANALYZE");
+                } else if let Some(Format::Json) = self.format {
+                    rv.push_str(" FORMAT=JSON");
+                }
+                rv.push(' ');
+                rv.push_str(&query);
+                rv
+            },
+            Dialect::SQLite => format!("EXPLAIN QUERY PLAN {}", query)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Explain, Format};
+    use common::ToSQL;
+    use dialect::Dialect;
+    use select::Select;
+    use update::Update;
+
+    #[test]
+    fn test_explain() {
+        let query = Select::from("foo").filter("a = 1");
+        assert_eq!(Explain::new(&query).to_sql(), "EXPLAIN SELECT * FROM foo WHERE a = 1");
+        let explain = Explain::new(&query).verbose().format(Format::Text);
+        assert_eq!(explain.to_sql(), "EXPLAIN (VERBOSE, FORMAT TEXT) SELECT * FROM foo WHERE a = 1");
+        assert_eq!(explain.get_format(), Some(Format::Text));
+        assert_eq!(explain.to_sql_for(Dialect::MySQL), "EXPLAIN SELECT * FROM foo WHERE a = 1");
+
+        let update = Update::new("foo").set("a = 2");
+        let explain = Explain::new(&update).analyze().verbose().format(Format::Json);
+        assert_eq!(explain.to_sql_for(Dialect::MySQL), "EXPLAIN ANALYZE UPDATE foo SET a = 2");
+        let explain = Explain::new(&update).format(Format::Json);
+        assert_eq!(explain.to_sql_for(Dialect::MySQL), "EXPLAIN FORMAT=JSON UPDATE foo SET a = 2");
+        assert_eq!(explain.to_sql_for(Dialect::SQLite), "EXPLAIN QUERY PLAN UPDATE foo SET a = 2");
+    }
+}
@@ -0,0 +1,324 @@
+//! Keeps `Plan`, a typed tree of a query plan parsed from output of `EXPLAIN`.
+
+use std::error::Error;
+use std::fmt;
+
+use super::json::{self, Json};
+
+/// Error of parsing a plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanError {
+    /// Input isn't valid JSON, keeps the byte offset where it became invalid.
+    Json(usize),
+    /// A node doesn't have the required key, e.g. `Plan` or `Node Type`.
+    MissingKey(&'static str),
+    /// A row of `EXPLAIN QUERY PLAN` (SQLite) refers to a parent which doesn't exist.
+    UnknownParent {
+        id: i64,
+        parent: i64
+    }
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PlanError::Json(position) => write!(f, "invalid JSON at byte {}", position),
+            PlanError::MissingKey(key) => write!(f, "plan node doesn't have \"{}\"", key),
+            PlanError::UnknownParent { id, parent } => {
+                write!(f, "row {} refers to unknown parent {}", id, parent)
+            }
+        }
+    }
+}
+
+impl Error for PlanError {}
+
+/// Node of a query plan. Costs, rows and timings are only known for PostgreSQL, the latter two
+/// only with `ANALYZE`. Actual and removed rows are per loop, as PostgreSQL reports them.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Plan {
+    /// `Node Type` for PostgreSQL, e.g. `Seq Scan`; leading keyword of the row for SQLite,
+    /// e.g. `SCAN` or `SEARCH`, and the whole row when it's something else.
+    pub node_type: String,
+    pub relation: Option<String>,
+    pub alias: Option<String>,
+    pub index: Option<String>,
+    pub filter: Option<String>,
+    pub startup_cost: Option<f64>,
+    pub total_cost: Option<f64>,
+    pub rows: Option<f64>,
+    pub actual_rows: Option<f64>,
+    pub actual_loops: Option<f64>,
+    /// `Rows Removed by Filter`, rows read by the node which didn't pass `filter`.
+    pub rows_removed: Option<f64>,
+    pub actual_time: Option<f64>,
+    /// The row of `EXPLAIN QUERY PLAN` as is (SQLite).
+    pub detail: Option<String>,
+    pub children: Vec<Plan>
+}
+
+impl Plan {
+    /// Parses output of `EXPLAIN (FORMAT JSON)` (PostgreSQL) and returns the root node.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::explain::Plan;
+    ///
+    /// let output = r#"[{"Plan": {
+    ///     "Node Type": "Hash Join", "Total Cost": 350.5, "Plan Rows": 1000,
+    ///     "Plans": [
+    ///         {"Node Type": "Seq Scan", "Relation Name": "orders", "Alias": "o", "Plan Rows": 100000},
+    ///         {"Node Type": "Index Scan", "Relation Name": "users", "Index Name": "users_pkey", "Plan Rows": 10}
+    ///     ]
+    /// }}]"#;
+    /// let plan = Plan::from_json(output).unwrap();
+    /// assert_eq!(plan.node_type, "Hash Join");
+    /// assert_eq!(plan.children[1].index, Some("users_pkey".to_string()));
+    /// assert_eq!(plan.sequential_scans_over(10000.0)[0].relation, Some("orders".to_string()));
+    /// ```
+    pub fn from_json(input: &str) -> Result<Plan, PlanError> {
+        let document = json::parse(input).map_err(PlanError::Json)?;
+        let root = match document {
+            Json::Array(ref values) => values.first(),
+            ref value => Some(value)
+        };
+        root.and_then(|x| x.get("Plan"))
+            .ok_or(PlanError::MissingKey("Plan"))
+            .and_then(Plan::from_node)
+    }
+
+    fn from_node(node: &Json) -> Result<Plan, PlanError> {
+        let string = |key| node.get(key).and_then(Json::as_str).map(|x: &str| x.to_string());
+        let number = |key| node.get(key).and_then(Json::as_f64);
+        let children = match node.get("Plans").and_then(Json::as_array) {
+            Some(children) => children.iter().map(Plan::from_node).collect::<Result<Vec<_>, _>>()?,
+            None => vec![]
+        };
+        Ok(Plan {
+            node_type: string("Node Type").ok_or(PlanError::MissingKey("Node Type"))?,
+            relation: string("Relation Name"),
+            alias: string("Alias"),
+            index: string("Index Name"),
+            filter: string("Filter"),
+            startup_cost: number("Startup Cost"),
+            total_cost: number("Total Cost"),
+            rows: number("Plan Rows"),
+            actual_rows: number("Actual Rows"),
+            actual_loops: number("Actual Loops"),
+            rows_removed: number("Rows Removed by Filter"),
+            actual_time: number("Actual Total Time"),
+            detail: None,
+            children: children
+        })
+    }
+
+    /// Builds a plan from rows of `EXPLAIN QUERY PLAN` (SQLite): `id`, `parent` and `detail`
+    /// columns. Top level rows become children of the root with `QUERY PLAN` node type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::explain::Plan;
+    ///
+    /// let plan = Plan::from_sqlite_rows(vec![
+    ///     (3, 0, "SCAN orders AS o"),
+    ///     (5, 0, "SEARCH users USING INDEX users_id (id=?)"),
+    ///     (8, 0, "USE TEMP B-TREE FOR ORDER BY")
+    /// ]).unwrap();
+    /// assert_eq!(plan.children[0].node_type, "SCAN");
+    /// assert_eq!(plan.children[0].alias, Some("o".to_string()));
+    /// assert_eq!(plan.children[1].index, Some("users_id".to_string()));
+    /// assert_eq!(plan.children[2].node_type, "USE TEMP B-TREE FOR ORDER BY");
+    /// assert_eq!(plan.sequential_scans_on(&["orders"]).len(), 1);
+    /// ```
+    pub fn from_sqlite_rows<'r, I>(rows: I) -> Result<Plan, PlanError>
+        where I: IntoIterator<Item=(i64, i64, &'r str)>
+    {
+        let rows = rows.into_iter().collect::<Vec<_>>();
+        for &(id, parent, _) in &rows {
+            if parent != 0 && !rows.iter().any(|x| x.0 == parent) {
+                return Err(PlanError::UnknownParent { id: id, parent: parent });
+            }
+        }
+        Ok(Plan {
+            node_type: "QUERY PLAN".to_string(),
+            children: sqlite_children(&rows, 0),
+            ..Plan::default()
+        })
+    }
+
+    fn from_sqlite_detail(detail: &str) -> Plan {
+        let words = detail.split_whitespace().collect::<Vec<_>>();
+        let mut plan = Plan { detail: Some(detail.to_string()), ..Plan::default() };
+        match words.first() {
+            Some(&"SCAN") | Some(&"SEARCH") => {
+                plan.node_type = words[0].to_string();
+                let mut rest = &words[1..];
+                if rest.first() == Some(&"TABLE") {
+                    rest = &rest[1..];
+                }
+                match rest.first() {
+                    Some(&"CONSTANT") | Some(&"SUBQUERY") | None => {},
+                    Some(relation) => {
+                        plan.relation = Some(relation.to_string());
+                        rest = &rest[1..];
+                    }
+                }
+                if rest.first() == Some(&"AS") && rest.len() > 1 {
+                    plan.alias = Some(rest[1].to_string());
+                    rest = &rest[2..];
+                }
+                if rest.first() == Some(&"USING") {
+                    let using = rest[1..].join(" ");
+                    if using.starts_with("INTEGER PRIMARY KEY") {
+                        plan.index = Some("INTEGER PRIMARY KEY".to_string());
+                    } else if let Some(position) = rest.iter().position(|x| *x == "INDEX") {
+                        plan.index = rest.get(position + 1).map(|x| x.to_string());
+                    }
+                }
+            },
+            _ => plan.node_type = detail.to_string()
+        }
+        plan
+    }
+
+    /// Returns the node and all of its descendants, parents go before their children.
+    pub fn nodes(&self) -> Vec<&Plan> {
+        let mut rv = vec![self];
+        for child in &self.children {
+            rv.extend(child.nodes());
+        }
+        rv
+    }
+
+    /// Checks if the node reads the whole table: `Seq Scan` (PostgreSQL) or `SCAN` of a table
+    /// without an index (SQLite).
+    pub fn is_sequential_scan(&self) -> bool {
+        match self.node_type.as_str() {
+            "Seq Scan" => true,
+            "SCAN" => self.relation.is_some() && self.index.is_none(),
+            _ => false
+        }
+    }
+
+    /// Returns sequential scans of the tree.
+    pub fn sequential_scans(&self) -> Vec<&Plan> {
+        self.nodes().into_iter().filter(|x| x.is_sequential_scan()).collect()
+    }
+
+    /// Returns sequential scans of the given tables.
+    pub fn sequential_scans_on(&self, tables: &[&str]) -> Vec<&Plan> {
+        self.nodes().into_iter()
+            .filter(|x| x.is_sequential_scan())
+            .filter(|x| x.relation.as_ref().is_some_and(|relation| tables.contains(&relation.as_str())))
+            .collect()
+    }
+
+    /// Returns the number of rows read by the node in all loops: ones it produced along with ones
+    /// removed by the filter. Without `ANALYZE` only estimated rows it produces are known, which
+    /// is less than rows read when there is a filter. `None` for SQLite.
+    pub fn rows_read(&self) -> Option<f64> {
+        match self.actual_rows {
+            Some(rows) => Some((rows + self.rows_removed.unwrap_or(0.0)) * self.actual_loops.unwrap_or(1.0)),
+            None => self.rows
+        }
+    }
+
+    /// Returns sequential scans reading at least `rows` rows, see `rows_read`. Nodes without
+    /// rows (SQLite) are skipped, use `sequential_scans_on` with tables known to be large for them.
+    pub fn sequential_scans_over(&self, rows: f64) -> Vec<&Plan> {
+        self.nodes().into_iter()
+            .filter(|x| x.is_sequential_scan())
+            .filter(|x| x.rows_read().is_some_and(|found| found >= rows))
+            .collect()
+    }
+}
+
+fn sqlite_children(rows: &[(i64, i64, &str)], parent: i64) -> Vec<Plan> {
+    rows.iter()
+        .filter(|x| x.1 == parent && x.0 != parent)
+        .map(|&(id, _, detail)| {
+            let mut plan = Plan::from_sqlite_detail(detail);
+            plan.children = sqlite_children(rows, id);
+            plan
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Plan, PlanError};
+
+    #[test]
+    fn test_from_json() {
+        let output = r#"[
+          {
+            "Plan": {
+              "Node Type": "Limit",
+              "Startup Cost": 0.00,
+              "Total Cost": 0.35,
+              "Plan Rows": 10,
+              "Actual Total Time": 0.031,
+              "Actual Rows": 10,
+              "Actual Loops": 1,
+              "Plans": [
+                {
+                  "Node Type": "Seq Scan",
+                  "Parent Relationship": "Outer",
+                  "Relation Name": "users",
+                  "Alias": "u",
+                  "Filter": "(active IS TRUE)",
+                  "Plan Rows": 5000,
+                  "Actual Rows": 10,
+                  "Actual Loops": 2,
+                  "Rows Removed by Filter": 2490
+                }
+              ]
+            },
+            "Planning Time": 0.1,
+            "Execution Time": 0.05
+          }
+        ]"#;
+        let plan = Plan::from_json(output).unwrap();
+        assert_eq!(plan.total_cost, Some(0.35));
+        assert_eq!(plan.actual_time, Some(0.031));
+        assert_eq!(plan.nodes().len(), 2);
+        let scan = &plan.children[0];
+        assert_eq!(scan.filter, Some("(active IS TRUE)".to_string()));
+        assert_eq!(plan.sequential_scans(), vec![scan]);
+        assert_eq!(scan.rows_read(), Some(5000.0));
+        assert_eq!(plan.sequential_scans_over(5000.0), vec![scan]);
+        assert!(plan.sequential_scans_over(5001.0).is_empty());
+        assert_eq!(plan.rows_read(), Some(10.0));
+        assert_eq!(plan.sequential_scans_on(&["users"]), vec![scan]);
+    }
+
+    #[test]
+    fn test_json_errors() {
+        assert_eq!(Plan::from_json("[{\"Plan\": "), Err(PlanError::Json(10)));
+        assert_eq!(Plan::from_json("[]"), Err(PlanError::MissingKey("Plan")));
+        assert_eq!(Plan::from_json("{\"Plan\": {\"Plans\": []}}"), Err(PlanError::MissingKey("Node Type")));
+    }
+
+    #[test]
+    fn test_from_sqlite_rows() {
+        let plan = Plan::from_sqlite_rows(vec![
+            (2, 0, "CO-ROUTINE recent"),
+            (4, 2, "SCAN TABLE events USING COVERING INDEX events_created"),
+            (9, 0, "SCAN recent"),
+            (11, 0, "SEARCH users USING INTEGER PRIMARY KEY (rowid=?)"),
+            (15, 0, "SCAN CONSTANT ROW")
+        ]).unwrap();
+        assert_eq!(plan.nodes().len(), 6);
+        assert_eq!(plan.children[0].node_type, "CO-ROUTINE recent");
+        assert_eq!(plan.children[0].children[0].index, Some("events_created".to_string()));
+        assert_eq!(plan.children[2].index, Some("INTEGER PRIMARY KEY".to_string()));
+        assert_eq!(plan.children[3].relation, None);
+        assert_eq!(plan.sequential_scans().len(), 1);
+        assert_eq!(plan.sequential_scans()[0].relation, Some("recent".to_string()));
+
+        assert_eq!(Plan::from_sqlite_rows(vec![(3, 2, "SCAN foo")]), Err(PlanError::UnknownParent { id: 3, parent: 2 }));
+    }
+}
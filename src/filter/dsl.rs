@@ -0,0 +1,397 @@
+//! Keeps parser of search strings like `status:active age>=18 (name~"bob" OR email~"@corp")`.
+//!
+//! Grammar:
+//!
+//! - `field:value` or `field=value` - equality, `field!:value` or `field!=value` - inequality;
+//! - `field>value`, `field>=value`, `field<value`, `field<=value` - comparisons;
+//! - `field~value` - case-insensitive substring search;
+//! - terms separated by whitespace are joined with `AND`, `OR` and `NOT` (uppercase) and
+//!   parentheses work as usual;
+//! - values containing whitespace or special characters are written in double quotes,
+//!   `\"` and `\\` are escapes inside of them;
+//! - unquoted `null`, `true`, `false` and numbers are typed, everything else is text.
+
+use params::{Parameters, Value};
+use where_cl::{Where, WhereType, IntoWhereType};
+use super::{Fields, Comparison, FilterError};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+    Quoted(String),
+    Comparison(Comparison)
+}
+
+fn is_special(x: char) -> bool {
+    x.is_whitespace() || "()\":=!<>~".contains(x)
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, FilterError> {
+    let mut rv = vec![];
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((position, x)) = chars.next() {
+        let token = match x {
+            x if x.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ':' | '=' => Token::Comparison(Comparison::Eq),
+            '~' => Token::Comparison(Comparison::Contains),
+            '!' | '<' | '>' => {
+                let with_eq = match chars.peek() {
+                    Some(&(_, next)) => next == '=' || (x == '!' && next == ':'),
+                    None => false
+                };
+                if with_eq {
+                    chars.next();
+                }
+                Token::Comparison(match (x, with_eq) {
+                    ('!', true) => Comparison::NotEq,
+                    ('<', false) => Comparison::Lt,
+                    ('<', true) => Comparison::Le,
+                    ('>', false) => Comparison::Gt,
+                    ('>', true) => Comparison::Ge,
+                    _ => return Err(FilterError::Syntax(position, "expected `!:` or `!=`".to_string()))
+                })
+            },
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, x)) => value.push(x),
+                            None => return Err(FilterError::Syntax(input.len(), "unterminated string".to_string()))
+                        },
+                        Some((_, x)) => value.push(x),
+                        None => return Err(FilterError::Syntax(input.len(), "unterminated string".to_string()))
+                    }
+                }
+                Token::Quoted(value)
+            },
+            x => {
+                let mut value = x.to_string();
+                while let Some(&(_, next)) = chars.peek() {
+                    if is_special(next) {
+                        break;
+                    }
+                    value.push(next);
+                    chars.next();
+                }
+                Token::Word(value)
+            }
+        };
+        rv.push((position, token));
+    }
+    Ok(rv)
+}
+
+/// Types unquoted values. Only words starting with a digit (after an optional sign) are
+/// numbers, so `inf` or `NaN` stay text.
+fn word_value(word: &str) -> Value {
+    let is_numeric = word.trim_start_matches(['-', '+']).starts_with(|x: char| x.is_ascii_digit());
+    match word {
+        "null" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ if is_numeric => {
+            if let Ok(value) = word.parse::<i64>() {
+                Value::Int(value)
+            } else if let Ok(value) = word.parse::<f64>() {
+                Value::Float(value)
+            } else {
+                Value::Text(word.to_string())
+            }
+        },
+        _ => Value::Text(word.to_string())
+    }
+}
+
+/// Nesting of parentheses and `NOT`s deeper than this is a syntax error, so a crafted search
+/// string can't overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'f, 'a: 'f, 'p> {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    depth: usize,
+    end: usize,
+    fields: &'f Fields<'a>,
+    params: &'p mut Parameters
+}
+
+impl<'f, 'a, 'p> Parser<'f, 'a, 'p> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|x| &x.1)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.position).map(|x| x.0).unwrap_or(self.end)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) => word == keyword,
+            _ => false
+        }
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, FilterError> {
+        Err(FilterError::Syntax(self.offset(), message.to_string()))
+    }
+
+    fn or(&mut self) -> Result<WhereType<'a>, FilterError> {
+        let mut rv = Where::with_or().expr(self.and()?);
+        while self.is_keyword("OR") {
+            self.position += 1;
+            rv = rv.expr(self.and()?);
+        }
+        Ok(rv.into_where_type())
+    }
+
+    fn and(&mut self) -> Result<WhereType<'a>, FilterError> {
+        let mut rv = Where::with_and().expr(self.unary()?);
+        loop {
+            match self.peek() {
+                None | Some(&Token::Close) => break,
+                _ if self.is_keyword("OR") => break,
+                _ => {}
+            }
+            if self.is_keyword("AND") {
+                self.position += 1;
+            }
+            rv = rv.expr(self.unary()?);
+        }
+        Ok(rv.into_where_type())
+    }
+
+    /// Parses a nested expression, keeping track of the depth.
+    fn nested<F>(&mut self, parse: F) -> Result<WhereType<'a>, FilterError>
+        where F: FnOnce(&mut Self) -> Result<WhereType<'a>, FilterError>
+    {
+        if self.depth == MAX_DEPTH {
+            return self.error("too deep nesting");
+        }
+        self.depth += 1;
+        let rv = parse(self);
+        self.depth -= 1;
+        rv
+    }
+
+    fn unary(&mut self) -> Result<WhereType<'a>, FilterError> {
+        if self.is_keyword("NOT") {
+            let clause = self.nested(|x| {
+                x.position += 1;
+                x.unary()
+            })?;
+            return Ok(Where::not(clause).into_where_type());
+        }
+
+        match self.peek().cloned() {
+            Some(Token::Open) => {
+                let rv = self.nested(|x| {
+                    x.position += 1;
+                    x.or()
+                })?;
+                if self.peek() != Some(&Token::Close) {
+                    return self.error("expected `)`");
+                }
+                self.position += 1;
+                Ok(rv)
+            },
+            Some(Token::Word(name)) => {
+                self.position += 1;
+                let comparison = match self.peek() {
+                    Some(&Token::Comparison(comparison)) => comparison,
+                    _ => return self.error("expected comparison")
+                };
+                self.position += 1;
+                let value = match self.peek() {
+                    Some(Token::Word(word)) => word_value(word),
+                    Some(Token::Quoted(value)) => Value::Text(value.clone()),
+                    _ => return self.error("expected value")
+                };
+                self.position += 1;
+                self.fields.compile(&name, comparison, value, self.params)
+            },
+            None => self.error("unexpected end of input"),
+            _ => self.error("expected field")
+        }
+    }
+}
+
+/// Compiles a search string into a clause. Only fields and comparisons from `fields` are
+/// allowed, values are added to `params` when the whole string compiles. An empty string
+/// results in `TRUE`, which doesn't add anything to queries.
+///
+/// # Example
+///
+/// ```
+/// use lithium::{ToSQL, Select};
+/// use lithium::filter::{self, Fields, Comparison};
+/// use lithium::params::{Parameters, Placeholder, Value};
+///
+/// let fields = Fields::new()
+///     .field("status", "status", &[Comparison::Eq])
+///     .field("age", "age", Comparison::all())
+///     .field("name", "name", &[Comparison::Contains])
+///     .field("email", "email", &[Comparison::Contains]);
+/// let mut params = Parameters::new(Placeholder::Numbered);
+///
+/// let input = r#"status:active age>=18 (name~"bob" OR email~"@corp")"#;
+/// let clause = filter::parse(input, &fields, &mut params).unwrap();
+/// let query = Select::from("users").filter(clause);
+///
+/// let expected = {
+///     "SELECT * FROM users WHERE \
+///     (status = $1 AND age >= $2 AND (name ILIKE $3 OR email ILIKE $4))".to_string()
+/// };
+/// assert_eq!(query.to_sql(), expected);
+/// assert_eq!(params.into_values(), vec![
+///     Value::Text("active".to_string()),
+///     Value::Int(18),
+///     Value::Text("%bob%".to_string()),
+///     Value::Text("%@corp%".to_string()),
+/// ]);
+///
+/// assert!(filter::parse("password:secret", &fields, &mut Parameters::new(Placeholder::Numbered)).is_err());
+/// ```
+pub fn parse<'a>(input: &str, fields: &Fields<'a>, params: &mut Parameters)
+                 -> Result<WhereType<'a>, FilterError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Ok(WhereType::Constant(true));
+    }
+
+    // values are bound to a copy, so `params` isn't left half-filled on errors
+    let mut bound = params.clone();
+    let rv = {
+        let mut parser = Parser {
+            tokens: tokens,
+            position: 0,
+            depth: 0,
+            end: input.len(),
+            fields: fields,
+            params: &mut bound
+        };
+
+        let rv = parser.or()?;
+        if parser.position < parser.tokens.len() {
+            return parser.error("unexpected `)`");
+        }
+        rv
+    };
+    *params = bound;
+    Ok(rv.normalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, tokenize, word_value, Token};
+    use common::ToSQL;
+    use filter::{Fields, Comparison, FilterError};
+    use params::{Parameters, Placeholder, Value};
+
+    fn fields() -> Fields<'static> {
+        Fields::new()
+            .field("a", "t.a", Comparison::all())
+            .field("b", "t.b", Comparison::all())
+            .field("c", "t.c", &[Comparison::Eq])
+    }
+
+    fn compile(input: &str) -> Result<(String, Vec<Value>), FilterError> {
+        let fields = fields();
+        let mut params = Parameters::new(Placeholder::Numbered);
+        let clause = parse(input, &fields, &mut params)?;
+        Ok((clause.to_sql(), params.into_values()))
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize(r#"a!:1 (b<="x \"y\"")"#).unwrap();
+        assert_eq!(tokens, vec![
+            (0, Token::Word("a".to_string())),
+            (1, Token::Comparison(Comparison::NotEq)),
+            (3, Token::Word("1".to_string())),
+            (5, Token::Open),
+            (6, Token::Word("b".to_string())),
+            (7, Token::Comparison(Comparison::Le)),
+            (9, Token::Quoted("x \"y\"".to_string())),
+            (18, Token::Close),
+        ]);
+    }
+
+    #[test]
+    fn test_word_value() {
+        assert_eq!(word_value("null"), Value::Null);
+        assert_eq!(word_value("true"), Value::Bool(true));
+        assert_eq!(word_value("-12"), Value::Int(-12));
+        assert_eq!(word_value("1.5"), Value::Float(1.5));
+        assert_eq!(word_value("abc"), Value::Text("abc".to_string()));
+        assert_eq!(word_value("+2e3"), Value::Float(2000.0));
+        assert_eq!(word_value("inf"), Value::Text("inf".to_string()));
+        assert_eq!(word_value("-infinity"), Value::Text("-infinity".to_string()));
+        assert_eq!(word_value("NaN"), Value::Text("NaN".to_string()));
+    }
+
+    #[test]
+    fn test_single_term() {
+        assert_eq!(compile("a:1").unwrap(), ("t.a = $1".to_string(), vec![Value::Int(1)]));
+        assert_eq!(compile("a!=x").unwrap(), ("t.a <> $1".to_string(), vec![Value::Text("x".to_string())]));
+        assert_eq!(compile("a:null").unwrap(), ("t.a IS NULL".to_string(), vec![]));
+        assert_eq!(compile("a!:null").unwrap(), ("t.a IS NOT NULL".to_string(), vec![]));
+        assert_eq!(compile("a:\"null\"").unwrap().0, "t.a = $1".to_string());
+    }
+
+    #[test]
+    fn test_operators() {
+        let (sql, params) = compile("a>1 AND b<2 OR NOT (c:3 b>=4)").unwrap();
+        assert_eq!(sql, "((t.a > $1 AND t.b < $2) OR NOT (t.c = $3 AND t.b >= $4))");
+        assert_eq!(params, vec![Value::Int(1), Value::Int(2), Value::Int(3), Value::Int(4)]);
+
+        assert_eq!(compile("NOT a:1").unwrap().0, "NOT (t.a = $1)");
+        assert_eq!(compile("((a:1))").unwrap().0, "t.a = $1");
+        assert_eq!(compile("  ").unwrap().0, "TRUE");
+    }
+
+    #[test]
+    fn test_allow_list() {
+        assert_eq!(compile("d:1"), Err(FilterError::UnknownField("d".to_string())));
+        assert_eq!(compile("c>1"), Err(FilterError::Comparison("c".to_string(), Comparison::Gt)));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(compile("a"), Err(FilterError::Syntax(1, "expected comparison".to_string())));
+        assert_eq!(compile("a:"), Err(FilterError::Syntax(2, "expected value".to_string())));
+        assert_eq!(compile("(a:1"), Err(FilterError::Syntax(4, "expected `)`".to_string())));
+        assert_eq!(compile("a:1)"), Err(FilterError::Syntax(3, "unexpected `)`".to_string())));
+        assert_eq!(compile("a:\"x"), Err(FilterError::Syntax(4, "unterminated string".to_string())));
+        assert_eq!(compile("a!1"), Err(FilterError::Syntax(1, "expected `!:` or `!=`".to_string())));
+        assert_eq!(compile("OR a:1"), Err(FilterError::Syntax(3, "expected comparison".to_string())));
+    }
+
+    #[test]
+    fn test_nesting() {
+        let input = format!("{}a:1{}", "(".repeat(64), ")".repeat(64));
+        assert_eq!(compile(&input).unwrap().0, "t.a = $1");
+        let input = format!("{}a:1{}", "(".repeat(65), ")".repeat(65));
+        assert_eq!(compile(&input), Err(FilterError::Syntax(64, "too deep nesting".to_string())));
+        assert!(compile(&"(".repeat(200000)).is_err());
+        assert!(compile(&"NOT ".repeat(200000)).is_err());
+    }
+
+    #[test]
+    fn test_params_on_error() {
+        let fields = fields();
+        let mut params = Parameters::new(Placeholder::Numbered);
+        params.push(0);
+        assert!(parse("a:1 b:2 d:3", &fields, &mut params).is_err());
+        assert_eq!(params.values(), &[Value::Int(0)]);
+        let clause = parse("a:1", &fields, &mut params).unwrap();
+        assert_eq!(clause.to_sql(), "t.a = $2");
+        assert_eq!(params.values(), &[Value::Int(0), Value::Int(1)]);
+    }
+}
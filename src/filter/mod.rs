@@ -0,0 +1,227 @@
+//! Keeps stuff for building `WHERE` clauses out of user input, e.g. search strings.
+//!
+//! There are two kinds of input: search strings handled by `parse` and structured `Filter`s,
+//! which can be deserialized from JSON with `serde` feature. User input never gets into SQL
+//! as it is: fields are looked up in an allow-list of `Fields` and values are collected
+//! into `Parameters`.
+
+pub mod dsl;
+pub mod structured;
+
+use std::error::Error;
+use std::fmt;
+
+use params::{Parameters, Placeholder, Value};
+use where_cl::{Where, WhereType, IntoWhereType};
+
+pub use self::dsl::parse;
+pub use self::structured::Filter;
+
+/// Comparison which can be used for a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum Comparison {
+    Eq,
+    #[cfg_attr(feature = "serde", serde(rename = "ne", alias = "not_eq"))]
+    NotEq,
+    Lt,
+    #[cfg_attr(feature = "serde", serde(rename = "lte", alias = "le"))]
+    Le,
+    Gt,
+    #[cfg_attr(feature = "serde", serde(rename = "gte", alias = "ge"))]
+    Ge,
+    /// Case-insensitive substring search, results in `ILIKE '%...%'` for PostgreSQL
+    /// and in `LOWER(...) LIKE LOWER('%...%')` for MySQL and SQLite, which have no `ILIKE`.
+    Contains
+}
+
+impl Comparison {
+    /// Returns every comparison.
+    pub fn all() -> &'static [Comparison] {
+        &[Comparison::Eq, Comparison::NotEq, Comparison::Lt, Comparison::Le,
+          Comparison::Gt, Comparison::Ge, Comparison::Contains]
+    }
+
+    pub fn to_sql(&self) -> &str {
+        match *self {
+            Comparison::Eq => "=",
+            Comparison::NotEq => "<>",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+            Comparison::Contains => "ILIKE"
+        }
+    }
+
+    /// Builds clause comparing `column` with `value`. The value is added to `params`,
+    /// `NULL` results in `IS [NOT] NULL` for `Eq` and `NotEq`. The dialect of `Contains` is
+    /// told by the placeholders: numbered ones mean PostgreSQL.
+    ///
+    /// Outside PostgreSQL wildcards are escaped with `!`, since a backslash can't be
+    /// written the same way in MySQL and SQLite literals.
+    fn compile<'a>(&self, column: &'a str, value: Value, params: &mut Parameters) -> WhereType<'a> {
+        match (*self, value) {
+            (Comparison::Eq, Value::Null) => Where::is_null(column).into_where_type(),
+            (Comparison::NotEq, Value::Null) => Where::is_not_null(column).into_where_type(),
+            (Comparison::Contains, value) => match params.placeholder {
+                Placeholder::Numbered => {
+                    let pattern = format!("%{}%", escape_like(&value.to_string(), '\\'));
+                    format!("{} {} {}", column, self.to_sql(), params.push(pattern)).into_where_type()
+                },
+                Placeholder::Question => {
+                    let pattern = format!("%{}%", escape_like(&value.to_string(), '!'));
+                    format!("LOWER({}) LIKE LOWER({}) ESCAPE '!'", column, params.push(pattern)).into_where_type()
+                }
+            },
+            (comparison, value) => {
+                format!("{} {} {}", column, comparison.to_sql(), params.push(value)).into_where_type()
+            }
+        }
+    }
+}
+
+/// Escapes wildcards of `LIKE` patterns with `escape` character.
+fn escape_like(value: &str, escape: char) -> String {
+    let mut rv = String::new();
+    for x in value.chars() {
+        if x == escape || x == '%' || x == '_' {
+            rv.push(escape);
+        }
+        rv.push(x);
+    }
+    rv
+}
+
+/// Field which users are allowed to filter by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field<'a> {
+    /// Name used in user input.
+    pub name: &'a str,
+    /// Column or expression used in SQL.
+    pub column: &'a str,
+    pub comparisons: Vec<Comparison>
+}
+
+/// Allow-list of fields and comparisons.
+///
+/// # Example
+///
+/// ```
+/// use lithium::filter::{Fields, Comparison};
+///
+/// let fields = Fields::new()
+///     .field("status", "users.status", &[Comparison::Eq, Comparison::NotEq])
+///     .field("age", "users.age", Comparison::all());
+/// assert_eq!(fields.get("status").unwrap().column, "users.status");
+/// assert!(fields.get("password").is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Fields<'a> {
+    fields: Vec<Field<'a>>
+}
+
+impl<'a> Fields<'a> {
+    /// Method to start with.
+    pub fn new() -> Self {
+        Fields {
+            fields: vec![]
+        }
+    }
+
+    /// Allows filtering by `name` with the given comparisons.
+    pub fn field(mut self, name: &'a str, column: &'a str, comparisons: &[Comparison]) -> Self {
+        self.fields.push(Field {
+            name: name,
+            column: column,
+            comparisons: comparisons.to_vec()
+        });
+        self
+    }
+
+    /// Looks up a field by name.
+    pub fn get(&self, name: &str) -> Option<&Field<'a>> {
+        self.fields.iter().find(|x| x.name == name)
+    }
+
+    /// Builds clause for `name`, checking that the field and comparison are allowed.
+    fn compile(&self, name: &str, comparison: Comparison, value: Value, params: &mut Parameters)
+               -> Result<WhereType<'a>, FilterError> {
+        let field = self.get(name).ok_or_else(|| FilterError::UnknownField(name.to_string()))?;
+        if !field.comparisons.contains(&comparison) {
+            return Err(FilterError::Comparison(name.to_string(), comparison));
+        }
+        Ok(comparison.compile(field.column, value, params))
+    }
+}
+
+/// Error returned when user input can't be turned into a clause.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterError {
+    /// Field is not in the allow-list.
+    UnknownField(String),
+    /// Comparison is not allowed for the field.
+    Comparison(String, Comparison),
+    /// Input is malformed. Keeps a position in the input (in bytes) and a description.
+    Syntax(usize, String)
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FilterError::UnknownField(ref name) => write!(f, "unknown field `{}`", name),
+            FilterError::Comparison(ref name, ref comparison) => {
+                write!(f, "comparison `{}` is not allowed for field `{}`", comparison.to_sql(), name)
+            },
+            FilterError::Syntax(position, ref message) => write!(f, "{} at {}", message, position)
+        }
+    }
+}
+
+impl Error for FilterError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fields, Comparison, FilterError, escape_like};
+    use common::ToSQL;
+    use params::{Parameters, Placeholder, Value};
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("50%_a\\b", '\\'), "50\\%\\_a\\\\b");
+        assert_eq!(escape_like("50%_a!b\\", '!'), "50!%!_a!!b\\");
+    }
+
+    #[test]
+    fn test_compile() {
+        let fields = Fields::new()
+            .field("name", "users.name", &[Comparison::Eq, Comparison::Contains])
+            .field("age", "age", &[Comparison::Ge]);
+        let mut params = Parameters::new(Placeholder::Numbered);
+
+        let clause = fields.compile("name", Comparison::Contains, "bo_b".into(), &mut params);
+        assert_eq!(clause.unwrap().to_sql(), "users.name ILIKE $1");
+        let clause = fields.compile("name", Comparison::Eq, Value::Null, &mut params);
+        assert_eq!(clause.unwrap().to_sql(), "users.name IS NULL");
+        let clause = fields.compile("age", Comparison::Ge, 18.into(), &mut params);
+        assert_eq!(clause.unwrap().to_sql(), "age >= $2");
+        assert_eq!(params.values(), &[Value::Text("%bo\\_b%".to_string()), Value::Int(18)]);
+
+        let clause = fields.compile("age", Comparison::Lt, 18.into(), &mut params);
+        assert!(clause.is_err());
+        assert_eq!(clause.err(), Some(FilterError::Comparison("age".to_string(), Comparison::Lt)));
+        let clause = fields.compile("password", Comparison::Eq, 18.into(), &mut params);
+        assert_eq!(clause.err(), Some(FilterError::UnknownField("password".to_string())));
+        assert_eq!(params.len(), 2);
+    }
+
+    #[test]
+    fn test_contains() {
+        let fields = Fields::new().field("name", "users.name", &[Comparison::Contains]);
+        let mut params = Parameters::new(Placeholder::Question);
+
+        let clause = fields.compile("name", Comparison::Contains, "50%!".into(), &mut params);
+        assert_eq!(clause.unwrap().to_sql(), "LOWER(users.name) LIKE LOWER(?) ESCAPE '!'");
+        assert_eq!(params.values(), &[Value::Text("%50!%!!%".to_string())]);
+    }
+}
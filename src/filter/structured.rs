@@ -0,0 +1,380 @@
+//! Keeps structured filters, which are usually received by HTTP APIs as JSON objects like
+//! `{"and": [{"field": "status", "op": "eq", "value": "active"}, {"not": {...}}]}`.
+//!
+//! With `serde` feature `Filter` implements `Deserialize` and `Serialize`, so it can be read
+//! from a request and written back, e.g. to keep it in a saved search. A clause compiled
+//! from a filter can be turned back into it with `Filter::from_where`.
+
+use params::{Parameters, Placeholder, Value};
+use where_cl::{Where, WhereType, Predicate, Operator, IntoWhereType};
+use super::{Fields, Field, Comparison, FilterError};
+
+/// Tree of conditions combined with `and`, `or` and `not`.
+///
+/// # Example
+///
+/// ```
+/// use lithium::{ToSQL, Select};
+/// use lithium::filter::{Filter, Fields, Comparison};
+/// use lithium::params::{Parameters, Placeholder, Value};
+///
+/// let fields = Fields::new()
+///     .field("status", "status", &[Comparison::Eq])
+///     .field("age", "age", Comparison::all());
+/// let mut params = Parameters::new(Placeholder::Numbered);
+///
+/// let filter = Filter::and(vec![
+///     Filter::condition("status", Comparison::Eq, "active"),
+///     Filter::negate(Filter::condition("age", Comparison::Lt, 18)),
+/// ]);
+/// let query = Select::from("users").filter(filter.compile(&fields, &mut params).unwrap());
+///
+/// assert_eq!(query.to_sql(), "SELECT * FROM users WHERE (status = $1 AND NOT (age < $2))");
+/// assert_eq!(params.into_values(), vec![Value::Text("active".to_string()), Value::Int(18)]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(untagged, deny_unknown_fields))]
+pub enum Filter {
+    And {
+        and: Vec<Filter>
+    },
+    Or {
+        or: Vec<Filter>
+    },
+    Not {
+        not: Box<Filter>
+    },
+    /// Comparison of a field with a value. A missing value means `null`.
+    Condition {
+        field: String,
+        op: Comparison,
+        #[cfg_attr(feature = "serde", serde(default))]
+        value: Value
+    }
+}
+
+impl Filter {
+    /// Joins filters with `AND`. An empty list matches everything.
+    pub fn and(filters: Vec<Filter>) -> Self {
+        Filter::And { and: filters }
+    }
+
+    /// Joins filters with `OR`. An empty list matches nothing.
+    pub fn or(filters: Vec<Filter>) -> Self {
+        Filter::Or { or: filters }
+    }
+
+    /// Negates the filter.
+    pub fn negate(filter: Filter) -> Self {
+        Filter::Not { not: Box::new(filter) }
+    }
+
+    /// Compares `field` with `value`.
+    pub fn condition<S: Into<String>, V: Into<Value>>(field: S, op: Comparison, value: V) -> Self {
+        Filter::Condition {
+            field: field.into(),
+            op: op,
+            value: value.into()
+        }
+    }
+
+    /// Compiles the filter into a clause. Only fields and comparisons from `fields` are
+    /// allowed, values are added to `params`. The result is normalized, so `NOT` is applied
+    /// to comparisons where possible and empty groups turn into constants. On errors `params`
+    /// are left as they were.
+    pub fn compile<'a>(&self, fields: &Fields<'a>, params: &mut Parameters)
+                       -> Result<WhereType<'a>, FilterError> {
+        // values are bound to a copy, so `params` isn't left half-filled on errors
+        let mut bound = params.clone();
+        let rv = self.build(fields, &mut bound)?;
+        *params = bound;
+        Ok(rv.normalize())
+    }
+
+    /// Turns a clause built by `compile` back into a filter, looking up fields by columns
+    /// and values by placeholders. Question marks are matched with `params` in their order.
+    /// Returns `None` for anything `compile` doesn't produce, e.g. raw SQL expressions.
+    ///
+    /// The result is equal to the compiled filter up to normalization: `NOT` may be applied
+    /// to comparisons, groups of one filter are unwrapped and `contains` values become strings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::filter::{Filter, Fields, Comparison};
+    /// use lithium::params::{Parameters, Placeholder};
+    ///
+    /// let fields = Fields::new().field("age", "users.age", Comparison::all());
+    /// let mut params = Parameters::new(Placeholder::Numbered);
+    ///
+    /// let filter = Filter::negate(Filter::condition("age", Comparison::Lt, 18));
+    /// let clause = filter.compile(&fields, &mut params).unwrap();
+    /// assert_eq!(Filter::from_where(&clause, &fields, &params), Some(filter));
+    /// ```
+    pub fn from_where(clause: &WhereType, fields: &Fields, params: &Parameters) -> Option<Filter> {
+        Lookup { params: params, next: 0 }.restore(clause, fields)
+    }
+
+    fn build<'a>(&self, fields: &Fields<'a>, params: &mut Parameters)
+                 -> Result<WhereType<'a>, FilterError> {
+        let (mut rv, filters) = match *self {
+            Filter::And { ref and } => (Where::with_and(), and),
+            Filter::Or { ref or } => (Where::with_or(), or),
+            Filter::Not { ref not } => return Ok(Where::not(not.build(fields, params)?).into_where_type()),
+            Filter::Condition { ref field, op, ref value } => {
+                return fields.compile(field, op, value.clone(), params);
+            }
+        };
+        for filter in filters {
+            rv = rv.expr(filter.build(fields, params)?);
+        }
+        Ok(rv.into_where_type())
+    }
+}
+
+/// Finds values of placeholders for `Filter::from_where`.
+struct Lookup<'p> {
+    params: &'p Parameters,
+    next: usize
+}
+
+impl<'p> Lookup<'p> {
+    fn restore(&mut self, clause: &WhereType, fields: &Fields) -> Option<Filter> {
+        match *clause {
+            WhereType::Owned(ref clause) => fields.fields.iter().filter_map(|x| self.comparison(clause, x)).next(),
+            WhereType::Predicate(Predicate::Not(ref clause)) => self.restore(clause, fields).map(Filter::negate),
+            WhereType::Predicate(Predicate::IsNull { expression, negated }) => {
+                let (op, opposite) = if negated {
+                    (Comparison::NotEq, Comparison::Eq)
+                } else {
+                    (Comparison::Eq, Comparison::NotEq)
+                };
+                let field = fields.fields.iter().find(|x| x.column == expression && x.comparisons.contains(&op));
+                match field {
+                    Some(field) => Some(Filter::condition(field.name, op, Value::Null)),
+                    None => {
+                        let field = fields.fields.iter().find(|x| x.column == expression && x.comparisons.contains(&opposite))?;
+                        Some(Filter::negate(Filter::condition(field.name, opposite, Value::Null)))
+                    }
+                }
+            },
+            WhereType::Extended(ref clause) => {
+                let filters = clause.get_expressions().iter()
+                    .map(|x| self.restore(x, fields))
+                    .collect::<Option<Vec<_>>>()?;
+                match clause.operator {
+                    Operator::And => Some(Filter::and(filters)),
+                    Operator::Or => Some(Filter::or(filters))
+                }
+            },
+            WhereType::Constant(true) => Some(Filter::and(vec![])),
+            WhereType::Constant(false) => Some(Filter::or(vec![])),
+            _ => None
+        }
+    }
+
+    /// Matches a comparison generated by `Comparison::compile` for `field`.
+    fn comparison(&mut self, clause: &str, field: &Field) -> Option<Filter> {
+        if self.params.placeholder == Placeholder::Question && field.comparisons.contains(&Comparison::Contains) {
+            let placeholder = clause.strip_prefix("LOWER(")
+                .and_then(|x| x.strip_prefix(field.column))
+                .and_then(|x| x.strip_prefix(") LIKE LOWER("))
+                .and_then(|x| x.strip_suffix(") ESCAPE '!'"));
+            if let Some(placeholder) = placeholder {
+                let value = unescape_like(self.value(placeholder)?, '!')?;
+                return Some(Filter::condition(field.name, Comparison::Contains, value));
+            }
+        }
+
+        let rest = clause.strip_prefix(field.column)?.strip_prefix(' ')?;
+        for &op in &field.comparisons {
+            let placeholder = match rest.strip_prefix(op.to_sql()).and_then(|x| x.strip_prefix(' ')) {
+                Some(placeholder) => placeholder,
+                None => continue
+            };
+            let value = self.value(placeholder)?;
+            return match op {
+                Comparison::Contains => unescape_like(value, '\\').map(|x| Filter::condition(field.name, op, x)),
+                _ => Some(Filter::condition(field.name, op, value))
+            };
+        }
+        None
+    }
+
+    fn value(&mut self, placeholder: &str) -> Option<Value> {
+        match self.params.placeholder {
+            Placeholder::Numbered => self.params.get(placeholder).cloned(),
+            Placeholder::Question if placeholder == "?" => {
+                self.next += 1;
+                self.params.values().get(self.next - 1).cloned()
+            },
+            Placeholder::Question => None
+        }
+    }
+}
+
+/// Reverses `escape_like` for a `%...%` pattern.
+fn unescape_like(value: Value, escape: char) -> Option<String> {
+    let pattern = match value {
+        Value::Text(pattern) => pattern,
+        _ => return None
+    };
+    let mut rv = String::new();
+    let mut chars = pattern.strip_prefix('%')?.strip_suffix('%')?.chars();
+    while let Some(x) = chars.next() {
+        rv.push(if x == escape { chars.next()? } else { x });
+    }
+    Some(rv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Filter;
+    use common::ToSQL;
+    use filter::{Fields, Comparison, FilterError};
+    use params::{Parameters, Placeholder, Value};
+    use where_cl::WhereType;
+
+    fn fields() -> Fields<'static> {
+        Fields::new()
+            .field("a", "t.a", Comparison::all())
+            .field("b", "t.b", &[Comparison::Eq])
+    }
+
+    fn compile(filter: &Filter) -> Result<(String, Vec<Value>), FilterError> {
+        let fields = fields();
+        let mut params = Parameters::new(Placeholder::Question);
+        let clause = filter.compile(&fields, &mut params)?;
+        Ok((clause.to_sql(), params.into_values()))
+    }
+
+    #[test]
+    fn test_compile() {
+        let filter = Filter::or(vec![
+            Filter::condition("a", Comparison::Contains, "x"),
+            Filter::and(vec![Filter::condition("b", Comparison::Eq, Value::Null)]),
+            Filter::negate(Filter::condition("a", Comparison::Ge, 1.5)),
+        ]);
+        let (sql, params) = compile(&filter).unwrap();
+        assert_eq!(sql, "(LOWER(t.a) LIKE LOWER(?) ESCAPE '!' OR t.b IS NULL OR NOT (t.a >= ?))");
+        assert_eq!(params, vec![Value::Text("%x%".to_string()), Value::Float(1.5)]);
+    }
+
+    #[test]
+    fn test_empty_groups() {
+        assert_eq!(compile(&Filter::and(vec![])).unwrap().0, "TRUE");
+        assert_eq!(compile(&Filter::or(vec![])).unwrap().0, "FALSE");
+        assert_eq!(compile(&Filter::negate(Filter::or(vec![]))).unwrap().0, "TRUE");
+    }
+
+    #[test]
+    fn test_allow_list() {
+        let filter = Filter::and(vec![Filter::condition("c", Comparison::Eq, 1)]);
+        assert_eq!(compile(&filter), Err(FilterError::UnknownField("c".to_string())));
+        let filter = Filter::condition("b", Comparison::Lt, 1);
+        assert_eq!(compile(&filter), Err(FilterError::Comparison("b".to_string(), Comparison::Lt)));
+    }
+
+    #[test]
+    fn test_params_on_errors() {
+        let mut params = Parameters::new(Placeholder::Numbered);
+        params.push(0);
+        let filter = Filter::and(vec![
+            Filter::condition("a", Comparison::Eq, 1),
+            Filter::condition("c", Comparison::Eq, 2),
+        ]);
+        assert!(filter.compile(&fields(), &mut params).is_err());
+        assert_eq!(params.values(), &[Value::Int(0)]);
+
+        let clause = Filter::condition("a", Comparison::Eq, 1).compile(&fields(), &mut params);
+        assert_eq!(clause.unwrap().to_sql(), "t.a = $2");
+    }
+
+    #[test]
+    fn test_from_where() {
+        let filter = Filter::or(vec![
+            Filter::condition("a", Comparison::Contains, "50%_!\\"),
+            Filter::and(vec![
+                Filter::condition("b", Comparison::Eq, Value::Null),
+                Filter::negate(Filter::condition("b", Comparison::Eq, Value::Null)),
+                Filter::condition("a", Comparison::Le, 2.5),
+            ]),
+            Filter::negate(Filter::condition("b", Comparison::Eq, "x")),
+            Filter::or(vec![]),
+        ]);
+        let expected = Filter::or(vec![
+            Filter::condition("a", Comparison::Contains, "50%_!\\"),
+            Filter::and(vec![
+                Filter::condition("b", Comparison::Eq, Value::Null),
+                Filter::negate(Filter::condition("b", Comparison::Eq, Value::Null)),
+                Filter::condition("a", Comparison::Le, 2.5),
+            ]),
+            Filter::negate(Filter::condition("b", Comparison::Eq, "x")),
+        ]);
+        for &placeholder in &[Placeholder::Numbered, Placeholder::Question] {
+            let mut params = Parameters::new(placeholder);
+            let clause = filter.compile(&fields(), &mut params).unwrap();
+            assert_eq!(Filter::from_where(&clause, &fields(), &params), Some(expected.clone()));
+        }
+
+        let mut params = Parameters::new(Placeholder::Numbered).offset(3);
+        let clause = Filter::condition("a", Comparison::NotEq, Value::Null).compile(&fields(), &mut params).unwrap();
+        let expected = Filter::condition("a", Comparison::NotEq, Value::Null);
+        assert_eq!(Filter::from_where(&clause, &fields(), &params), Some(expected));
+        let clause = Filter::condition("a", Comparison::Gt, 1).compile(&fields(), &mut params).unwrap();
+        let expected = Filter::condition("a", Comparison::Gt, 1);
+        assert_eq!(Filter::from_where(&clause, &fields(), &params), Some(expected));
+
+        let params = Parameters::new(Placeholder::Numbered);
+        assert_eq!(Filter::from_where(&WhereType::Simple("t.a = 1"), &fields(), &params), None);
+        assert_eq!(Filter::from_where(&WhereType::Owned("t.a = $1".to_string()), &fields(), &params), None);
+        assert_eq!(Filter::from_where(&WhereType::Owned("t.c = $1".to_string()), &fields(), &params), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        let input = r#"{"and": [
+            {"field": "a", "op": "gte", "value": 1},
+            {"not": {"field": "b", "op": "eq"}},
+            {"or": [{"field": "a", "op": "ne", "value": "x"}, {"field": "a", "op": "lt", "value": true}]}
+        ]}"#;
+        let filter: Filter = ::serde_json::from_str(input).unwrap();
+        assert_eq!(filter, Filter::and(vec![
+            Filter::condition("a", Comparison::Ge, 1),
+            Filter::negate(Filter::condition("b", Comparison::Eq, Value::Null)),
+            Filter::or(vec![
+                Filter::condition("a", Comparison::NotEq, "x"),
+                Filter::condition("a", Comparison::Lt, true),
+            ]),
+        ]));
+
+        let (sql, params) = compile(&filter).unwrap();
+        assert_eq!(sql, "(t.a >= ? AND t.b IS NOT NULL AND (t.a <> ? OR t.a < ?))");
+        assert_eq!(params, vec![Value::Int(1), Value::Text("x".to_string()), Value::Bool(true)]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_errors() {
+        assert!(::serde_json::from_str::<Filter>(r#"{"field": "a", "op": "like", "value": 1}"#).is_err());
+        assert!(::serde_json::from_str::<Filter>(r#"{"and": [], "or": []}"#).is_err());
+        assert!(::serde_json::from_str::<Filter>(r#"{"field": "a", "value": 1}"#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_round_trip() {
+        let filter = Filter::or(vec![
+            Filter::condition("a", Comparison::Le, 2.5),
+            Filter::negate(Filter::condition("b", Comparison::Contains, "y")),
+        ]);
+        let json = ::serde_json::to_string(&filter).unwrap();
+        let expected = {
+            r#"{"or":[{"field":"a","op":"lte","value":2.5},"#.to_string() +
+            r#"{"not":{"field":"b","op":"contains","value":"y"}}]}"#
+        };
+        assert_eq!(json, expected);
+        assert_eq!(::serde_json::from_str::<Filter>(&json).unwrap(), filter);
+    }
+}
@@ -0,0 +1,289 @@
+//! Keeps function call stuff, mostly aggregates like `COUNT` or `string_agg`.
+//!
+//! `Function` renders the call itself with `to_sql`, so it can be put into `Select::having`
+//! (via `Function::compare`) or `Select::order_by`. When used as a column with
+//! `Select::column` it also gets its alias.
+
+use common::ToSQL;
+use select::{OrderBy, Ordering, Column, IntoColumn, SortKey, IntoSortKey};
+use visitor::{Visitor, Rewrite};
+use where_cl::{WhereType, IntoWhereType, conjunction};
+
+/// Represents a function call.
+///
+/// `FILTER (WHERE ...)` is supported by PostgreSQL and SQLite, `WITHIN GROUP` is used by
+/// ordered-set aggregates of PostgreSQL (e.g. `percentile_cont`).
+///
+/// # Example
+///
+/// ```
+/// use lithium::{ToSQL, Select, Where};
+/// use lithium::function::Function;
+/// use lithium::select::Ordering;
+///
+/// let tags = Function::string_agg("tag", "', '")
+///     .distinct()
+///     .order_by("tag", Ordering::Ascending)
+///     .alias("tags");
+/// let active = Function::count_all().filter("active").alias("active");
+/// let query = Select::from("posts")
+///     .columns("author_id")
+///     .column(tags)
+///     .column(active)
+///     .group_by("author_id")
+///     .having(Function::count_all().compare(">", "10"))
+///     .order_by(Function::max("created"), Ordering::Descending);
+///
+/// let expected = {
+///     "SELECT author_id, string_agg(DISTINCT tag, ', ' ORDER BY tag ASC) AS tags, \
+///     COUNT(*) FILTER (WHERE active) AS active \
+///     FROM posts \
+///     GROUP BY author_id \
+///     HAVING COUNT(*) > 10 \
+///     ORDER BY MAX(created) DESC".to_string()
+/// };
+/// assert_eq!(query.to_sql(), expected);
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub struct Function<'a> {
+    name: &'a str,
+    arguments: Vec<Column<'a>>,
+    distinct: bool,
+    order_by: Vec<OrderBy<'a>>,
+    within_group: Vec<OrderBy<'a>>,
+    filter: Vec<WhereType<'a>>,
+    alias: Option<&'a str>
+}
+
+impl<'a> Function<'a> {
+    /// Method to start with.
+    pub fn new(name: &'a str) -> Self {
+        Function {
+            name: name,
+            arguments: vec![],
+            distinct: false,
+            order_by: vec![],
+            within_group: vec![],
+            filter: vec![],
+            alias: None
+        }
+    }
+
+    /// `COUNT(expression)`.
+    pub fn count<T: IntoColumn<'a>>(expression: T) -> Self {
+        Self::new("COUNT").arg(expression)
+    }
+
+    /// `COUNT(*)`.
+    pub fn count_all() -> Self {
+        Self::count("*")
+    }
+
+    pub fn sum<T: IntoColumn<'a>>(expression: T) -> Self {
+        Self::new("SUM").arg(expression)
+    }
+
+    pub fn avg<T: IntoColumn<'a>>(expression: T) -> Self {
+        Self::new("AVG").arg(expression)
+    }
+
+    pub fn min<T: IntoColumn<'a>>(expression: T) -> Self {
+        Self::new("MIN").arg(expression)
+    }
+
+    pub fn max<T: IntoColumn<'a>>(expression: T) -> Self {
+        Self::new("MAX").arg(expression)
+    }
+
+    pub fn array_agg<T: IntoColumn<'a>>(expression: T) -> Self {
+        Self::new("array_agg").arg(expression)
+    }
+
+    /// `string_agg(expression, delimiter)`. The delimiter is put into SQL as it is,
+    /// so it's supposed to be a placeholder or a quoted literal.
+    pub fn string_agg<T: IntoColumn<'a>>(expression: T, delimiter: &'a str) -> Self {
+        Self::new("string_agg").arg(expression).arg(delimiter)
+    }
+
+    /// Adds an argument.
+    pub fn arg<T: IntoColumn<'a>>(mut self, argument: T) -> Self {
+        self.arguments.push(argument.into_column());
+        self
+    }
+
+    /// Specifies `DISTINCT` of arguments.
+    pub fn distinct(mut self) -> Self {
+        self.distinct = true;
+        self
+    }
+
+    /// Specifies `ORDER BY` inside of arguments, e.g. for `array_agg`.
+    pub fn order_by<T: IntoSortKey<'a>>(self, field: T, ordering: Ordering<'a>) -> Self {
+        let mut item = OrderBy::new(field);
+        item.ordering = ordering;
+        self.order_by_item(item)
+    }
+
+    /// Specifies `ORDER BY` item inside of arguments.
+    pub fn order_by_item(mut self, item: OrderBy<'a>) -> Self {
+        self.order_by.push(item);
+        self
+    }
+
+    /// Specifies `WITHIN GROUP (ORDER BY ...)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::ToSQL;
+    /// use lithium::function::Function;
+    /// use lithium::select::Ordering;
+    ///
+    /// let median = Function::new("percentile_cont").arg("0.5").within_group("price", Ordering::Ascending);
+    /// assert_eq!(median.to_sql(), "percentile_cont(0.5) WITHIN GROUP (ORDER BY price ASC)");
+    /// ```
+    pub fn within_group<T: IntoSortKey<'a>>(mut self, field: T, ordering: Ordering<'a>) -> Self {
+        let mut item = OrderBy::new(field);
+        item.ordering = ordering;
+        self.within_group.push(item);
+        self
+    }
+
+    /// Specifies `FILTER (WHERE ...)`. Several filters are joined with `AND`.
+    pub fn filter<T: IntoWhereType<'a>>(mut self, clause: T) -> Self {
+        self.filter.push(clause.into_where_type());
+        self
+    }
+
+    /// Specifies alias, which is used when the function is a column.
+    pub fn alias(mut self, alias: &'a str) -> Self {
+        self.alias = Some(alias);
+        self
+    }
+
+    /// Returns the name of the function.
+    pub fn get_name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the alias.
+    pub fn get_alias(&self) -> Option<&'a str> {
+        self.alias
+    }
+
+    /// Builds a clause comparing the result with `value`, e.g. for `HAVING`.
+    pub fn compare(&self, operator: &str, value: &str) -> WhereType<'a> {
+        format!("{} {} {}", self.to_sql(), operator, value).into_where_type()
+    }
+}
+
+fn order_by_sql(items: &[OrderBy]) -> String {
+    items.iter().map(|x| x.to_sql()).collect::<Vec<_>>().join(", ")
+}
+
+impl<'a> ToSQL for Function<'a> {
+    fn to_sql(&self) -> String {
+        let mut rv = String::new();
+        rv.push_str(self.name);
+        rv.push('(');
+        if self.distinct {
+            rv.push_str("DISTINCT");
+            rv.push(' ');
+        }
+        rv.push_str(&self.arguments.iter().map(|x| x.to_sql()).collect::<Vec<_>>().join(", "));
+        if !self.order_by.is_empty() {
+            rv.push(' ');
+            rv.push_str("ORDER BY");
+            rv.push(' ');
+            rv.push_str(&order_by_sql(&self.order_by));
+        }
+        rv.push(')');
+
+        if !self.within_group.is_empty() {
+            rv.push(' ');
+            rv.push_str(&format!("WITHIN GROUP (ORDER BY {})", order_by_sql(&self.within_group)));
+        }
+
+        if let Some(clause) = conjunction(&self.filter) {
+            rv.push(' ');
+            rv.push_str(&format!("FILTER (WHERE {})", clause));
+        }
+        rv
+    }
+}
+
+impl<'a> IntoColumn<'a> for Function<'a> {
+    fn into_column(self) -> Column<'a> {
+        match self.alias {
+            Some(alias) => Column::Function(Box::new(self)).alias(alias),
+            None => Column::Function(Box::new(self))
+        }
+    }
+}
+
+impl<'a> IntoSortKey<'a> for Function<'a> {
+    fn into_sort_key(self) -> SortKey<'a> {
+        SortKey::Function(Box::new(self))
+    }
+}
+
+impl<'a> Rewrite<'a> for Function<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        for argument in &mut self.arguments {
+            argument.walk(visitor);
+        }
+        for item in self.order_by.iter_mut().chain(self.within_group.iter_mut()) {
+            item.walk(visitor);
+        }
+        for clause in &mut self.filter {
+            clause.walk(visitor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Function;
+    use common::ToSQL;
+    use select::{Ordering, IntoColumn};
+    use where_cl::Where;
+
+    #[test]
+    fn test_simple() {
+        assert_eq!(Function::count_all().to_sql(), "COUNT(*)");
+        assert_eq!(Function::sum("amount").to_sql(), "SUM(amount)");
+        assert_eq!(Function::new("coalesce").arg("a").arg("b").to_sql(), "coalesce(a, b)");
+        assert_eq!(Function::new("now").to_sql(), "now()");
+    }
+
+    #[test]
+    fn test_nested() {
+        let inner = Function::new("lower").arg("name");
+        assert_eq!(Function::count(inner.to_sql()).distinct().to_sql(), "COUNT(DISTINCT lower(name))");
+    }
+
+    #[test]
+    fn test_order_by_and_filter() {
+        let function = Function::array_agg("id")
+            .order_by("created", Ordering::Descending)
+            .order_by("id", Ordering::Ascending)
+            .filter("NOT deleted")
+            .filter(Where::with_or().expr("a = 1").expr("b = 2"));
+        let expected = "array_agg(id ORDER BY created DESC, id ASC) FILTER (WHERE NOT deleted AND (a = 1 OR b = 2))";
+        assert_eq!(function.to_sql(), expected);
+    }
+
+    #[test]
+    fn test_alias() {
+        let function = Function::avg("price").alias("average");
+        assert_eq!(function.get_alias(), Some("average"));
+        assert_eq!(function.to_sql(), "AVG(price)");
+        assert!(function.into_column().to_sql() == "AVG(price) AS average");
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(Function::min("price").compare(">=", "$1").to_sql(), "MIN(price) >= $1");
+    }
+}
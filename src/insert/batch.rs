@@ -0,0 +1,219 @@
+//! Keeps stuff for splitting bulk inserts into several statements.
+
+use std::error::Error;
+use std::fmt;
+
+use dialect::Dialect;
+use params::{Parameters, Placeholder, Value};
+use super::{Insert, Values, Cell};
+
+/// Iterator over statements of a bulk insert, which is created with `Insert::batches`.
+///
+/// Every item is a statement along with values of its parameters, placeholders start over
+/// in every statement. Rows are taken from the source lazily, so it can be a stream of any
+/// length. A batch holds as many rows as fit into `max_parameters` (the limit of the dialect
+/// by default), but no more than `rows_per_batch` if it's specified. A row which alone is
+/// wider than the limit can't be inserted, so `BatchError` is returned in place of it and
+/// the iteration goes on with the next rows.
+///
+/// Run the statements in one transaction if the rows have to be inserted all at once.
+///
+/// # Example
+///
+/// ```
+/// use lithium::{Insert, Dialect};
+///
+/// let rows = (0..100000).map(|x| vec![x, x * 2]);
+/// let mut batches = Insert::into("points").columns(&["x", "y"]).batches(rows, Dialect::PostgreSQL);
+///
+/// let (query, values) = batches.next().unwrap().unwrap();
+/// assert_eq!(values.len(), 65534);
+/// assert!(query.to_sql().ends_with("($65533, $65534)"));
+/// assert_eq!(batches.map(|x| x.unwrap().1.len()).collect::<Vec<_>>(), vec![65534, 65534, 3398]);
+/// ```
+pub struct Batches<'a, I> {
+    template: Insert<'a>,
+    rows: I,
+    placeholder: Placeholder,
+    max_parameters: usize,
+    rows_per_batch: Option<usize>,
+    /// Number of rows taken from the source.
+    taken: usize,
+    /// Row which didn't fit into the previous batch, along with its index.
+    pending: Option<(usize, Vec<Value>)>
+}
+
+impl<'a, I, R, V> Batches<'a, I> where I: Iterator<Item = R>, R: IntoIterator<Item = V>, V: Into<Value> {
+    pub fn new(template: Insert<'a>, rows: I, dialect: Dialect) -> Self {
+        Batches {
+            template: template,
+            rows: rows,
+            placeholder: dialect.placeholder(),
+            max_parameters: dialect.max_parameters(),
+            rows_per_batch: None,
+            taken: 0,
+            pending: None
+        }
+    }
+
+    /// Overrides the limit of bound parameters, e.g. with 999 for SQLite before 3.32.0.
+    pub fn max_parameters(mut self, max_parameters: usize) -> Self {
+        self.max_parameters = max_parameters;
+        self
+    }
+
+    /// Limits the number of rows in a statement, 0 is treated as 1.
+    pub fn rows_per_batch(mut self, rows_per_batch: usize) -> Self {
+        self.rows_per_batch = Some(rows_per_batch.max(1));
+        self
+    }
+}
+
+impl<'a, I, R, V> Iterator for Batches<'a, I>
+    where I: Iterator<Item = R>, R: IntoIterator<Item = V>, V: Into<Value>
+{
+    type Item = Result<(Insert<'a>, Vec<Value>), BatchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut params = Parameters::new(self.placeholder);
+        let mut rows = vec![];
+        // a row is taken before checking the limits since its width isn't known beforehand,
+        // so the one which doesn't fit is kept for the next batch
+        while self.rows_per_batch.is_none_or(|x| rows.len() < x) {
+            let (index, row) = match self.pending.take() {
+                Some(pending) => pending,
+                None => match self.rows.next() {
+                    Some(row) => {
+                        self.taken += 1;
+                        (self.taken - 1, row.into_iter().map(Into::into).collect::<Vec<Value>>())
+                    },
+                    None => break
+                }
+            };
+            if params.len() + row.len() > self.max_parameters {
+                if !rows.is_empty() {
+                    self.pending = Some((index, row));
+                    break;
+                }
+                return Some(Err(BatchError::RowTooWide {
+                    row: index,
+                    values: row.len(),
+                    max_parameters: self.max_parameters
+                }));
+            }
+            rows.push(row.into_iter().map(|x| Cell::Owned(params.push(x))).collect::<Vec<_>>());
+        }
+
+        if rows.is_empty() {
+            return None;
+        }
+
+        let mut query = self.template.clone();
+        query.values = Values::Extended(rows);
+        Some(Ok((query, params.into_values())))
+    }
+}
+
+/// Error returned by `Batches` for a row which can't be inserted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchError {
+    /// Row has more values than bound parameters allowed in a statement. Keeps an index of
+    /// the row, the number of its values and the limit.
+    RowTooWide {
+        row: usize,
+        values: usize,
+        max_parameters: usize
+    }
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BatchError::RowTooWide { row, values, max_parameters } => {
+                write!(f, "row {} has {} values while at most {} parameters are allowed",
+                       row, values, max_parameters)
+            }
+        }
+    }
+}
+
+impl Error for BatchError {}
+
+#[cfg(test)]
+mod tests {
+    use super::BatchError;
+    use dialect::Dialect;
+    use insert::Insert;
+    use params::Value;
+
+    fn sizes(batches: Vec<Result<(Insert, Vec<Value>), BatchError>>) -> Vec<usize> {
+        batches.into_iter().map(|x| x.unwrap().1.len()).collect()
+    }
+
+    #[test]
+    fn test_limits() {
+        let rows = vec![vec![1, 2, 3]; 10];
+        let template = Insert::into("test_table").columns(&["a", "b", "c"]);
+
+        let batches = template.clone().batches(rows.clone(), Dialect::MySQL).collect::<Vec<_>>();
+        assert_eq!(sizes(batches), vec![30]);
+
+        let batches = template.clone().batches(rows.clone(), Dialect::MySQL).max_parameters(10).collect::<Vec<_>>();
+        assert_eq!(sizes(batches), vec![9, 9, 9, 3]);
+
+        let batches = template.clone().batches(rows.clone(), Dialect::MySQL)
+            .max_parameters(10)
+            .rows_per_batch(2)
+            .collect::<Vec<_>>();
+        assert_eq!(sizes(batches), vec![6, 6, 6, 6, 6]);
+
+        let batches = template.batches(rows, Dialect::MySQL).max_parameters(3).collect::<Vec<_>>();
+        assert_eq!(sizes(batches), vec![3; 10]);
+    }
+
+    #[test]
+    fn test_uneven_rows() {
+        let rows = vec![vec![1; 3], vec![1; 8], vec![1; 2], vec![1; 10], vec![1]];
+        let template = Insert::into("test_table");
+
+        let batches = template.clone().batches(rows.clone(), Dialect::MySQL).max_parameters(10).collect::<Vec<_>>();
+        assert_eq!(sizes(batches), vec![3, 10, 10, 1]);
+
+        let batches = template.batches(rows, Dialect::MySQL).rows_per_batch(0).collect::<Vec<_>>();
+        assert_eq!(sizes(batches), vec![3, 8, 2, 10, 1]);
+    }
+
+    #[test]
+    fn test_too_wide_rows() {
+        let rows = vec![vec![1; 2], vec![1; 5], vec![1; 2], vec![1; 2], vec![1; 6]];
+        let batches = Insert::into("test_table").batches(rows, Dialect::MySQL).max_parameters(4).collect::<Vec<_>>();
+
+        assert_eq!(batches.len(), 4);
+        assert_eq!(batches[0].as_ref().unwrap().1.len(), 2);
+        assert_eq!(batches[1].as_ref().err(), Some(&BatchError::RowTooWide { row: 1, values: 5, max_parameters: 4 }));
+        assert_eq!(batches[2].as_ref().unwrap().1.len(), 4);
+        assert_eq!(batches[3].as_ref().err(), Some(&BatchError::RowTooWide { row: 4, values: 6, max_parameters: 4 }));
+        assert_eq!(batches[3].as_ref().err().unwrap().to_string(), "row 4 has 6 values while at most 4 parameters are allowed");
+    }
+
+    #[test]
+    fn test_statements() {
+        let rows = vec![vec![Value::Int(1), Value::Null], vec![Value::Int(2), Value::from("x")]];
+        let template = Insert::into("test_table").columns(&["a", "b"]).values(vec!["0", "0"]).returning("a");
+        let batches = template.batches(rows, Dialect::PostgreSQL).rows_per_batch(1)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].0.to_sql(), "INSERT INTO test_table (a, b) VALUES ($1, $2) RETURNING a");
+        assert_eq!(batches[1].0.to_sql(), "INSERT INTO test_table (a, b) VALUES ($1, $2) RETURNING a");
+        assert_eq!(batches[1].1, vec![Value::Int(2), Value::Text("x".to_string())]);
+        assert!(batches[0].0.validate().is_ok());
+    }
+
+    #[test]
+    fn test_empty() {
+        let rows: Vec<Vec<Value>> = vec![];
+        assert_eq!(Insert::into("test_table").batches(rows, Dialect::SQLite).count(), 0);
+    }
+}
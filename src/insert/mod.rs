@@ -0,0 +1,817 @@
+//! Keeps `INSERT` related stuff.
+
+pub mod batch;
+
+use std::error::Error;
+use std::fmt;
+
+use select::{Select, Union};
+use common::{ToSQL, Pusheable};
+use dialect::Dialect;
+use params::Value;
+use visitor::{Visitor, Rewrite};
+
+pub use self::batch::{Batches, BatchError};
+
+// TODO: make it pretty
+const RETURNING: &'static str = " RETURNING ";
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Values<'a> {
+    Default,
+    Specified(Vec<Vec<&'a str>>),
+    /// Rows where some of the cells are `DEFAULT` or generated at runtime.
+    Extended(Vec<Vec<Cell<'a>>>),
+    Select(Box<Select<'a>>),
+    /// `UNION` of queries, rendered for the dialect of `INSERT` and walked by visitors.
+    Union(Box<Union<Values<'a>, Values<'a>>>),
+    /// SQL of a query, e.g. `WITH ... SELECT ...`.
+    Query(&'a str)
+}
+
+impl<'a> Values<'a> {
+    /// Returns lengths of rows, which is empty for everything but `VALUES`.
+    fn row_lengths(&self) -> Vec<usize> {
+        match *self {
+            Values::Specified(ref values) => values.iter().map(|x| x.len()).collect(),
+            Values::Extended(ref values) => values.iter().map(|x| x.len()).collect(),
+            _ => vec![]
+        }
+    }
+}
+
+impl<'a> ToSQL for Values<'a> {
+    fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
+        match *self {
+            Values::Default => "DEFAULT VALUES".to_string(),
+            Values::Specified(ref values) => {
+                let mut rv = String::new();
+                rv.push_str("VALUES");
+                rv.push(' ');
+                rv.push_str(&values.iter()
+                            .map(|x| format!("({})", x.join(", ")))
+                            .collect::<Vec<_>>()
+                            .join(", "));
+                rv
+            },
+            Values::Extended(ref values) => {
+                let mut rv = String::new();
+                rv.push_str("VALUES");
+                rv.push(' ');
+                rv.push_str(&values.iter()
+                            .map(|x| format!("({})", x.iter().map(|x| x.to_sql()).collect::<Vec<_>>().join(", ")))
+                            .collect::<Vec<_>>()
+                            .join(", "));
+                rv
+            },
+            Values::Select(ref query) => query.to_sql_for(dialect),
+            Values::Union(ref query) => query.to_sql_for(dialect),
+            Values::Query(query) => query.to_string()
+        }
+    }
+}
+
+impl<'a> From<Select<'a>> for Values<'a> {
+    fn from(query: Select<'a>) -> Self {
+        Values::Select(Box::new(query))
+    }
+}
+
+impl<'a> From<&'a str> for Values<'a> {
+    fn from(query: &'a str) -> Self {
+        Values::Query(query)
+    }
+}
+
+impl<'a> Rewrite<'a> for Values<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        match *self {
+            Values::Select(ref mut query) => query.walk(visitor),
+            Values::Union(ref mut query) => query.walk(visitor),
+            _ => {}
+        }
+    }
+}
+
+/// Cell of a `VALUES` row.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Cell<'a> {
+    Value(&'a str),
+    /// Value generated at runtime, e.g. a placeholder of a bound parameter.
+    Owned(String),
+    /// `DEFAULT` keyword, which makes the database use a default of the column.
+    Default
+}
+
+impl<'a> Cell<'a> {
+    pub fn to_sql(&self) -> String {
+        match *self {
+            Cell::Value(value) => value.to_string(),
+            Cell::Owned(ref value) => value.clone(),
+            Cell::Default => "DEFAULT".to_string()
+        }
+    }
+}
+
+/// `OVERRIDING` clause (PostgreSQL), which decides what happens with values of identity columns.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Overriding {
+    /// `OVERRIDING SYSTEM VALUE`: given values are used even for `GENERATED ALWAYS` columns.
+    System,
+    /// `OVERRIDING USER VALUE`: given values are ignored in favour of generated ones.
+    User
+}
+
+impl Overriding {
+    pub fn to_sql(&self) -> &str {
+        match *self {
+            Overriding::System => "OVERRIDING SYSTEM VALUE",
+            Overriding::User => "OVERRIDING USER VALUE"
+        }
+    }
+}
+
+/// Error returned by `Insert::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertError {
+    /// Row doesn't have a cell for every column. Keeps an index of the row, expected and
+    /// actual numbers of cells. Without columns every row is expected to be as long as the first one.
+    RowLength {
+        row: usize,
+        expected: usize,
+        found: usize
+    },
+    /// Row doesn't have cells at all, which isn't valid SQL.
+    EmptyRow(usize)
+}
+
+impl fmt::Display for InsertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InsertError::RowLength { row, expected, found } => {
+                write!(f, "row {} has {} values while {} are expected", row, found, expected)
+            },
+            InsertError::EmptyRow(row) => write!(f, "row {} is empty", row)
+        }
+    }
+}
+
+impl Error for InsertError {}
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Returning<'a> {
+    Empty,
+    All,
+    Specified(Vec<&'a str>)
+}
+
+/// Represents `INSERT` query.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub struct Insert<'a> {
+    table: &'a str,
+    alias: Option<&'a str>,
+    columns: Vec<&'a str>,
+    overriding: Option<Overriding>,
+    values: Values<'a>,
+    returning: Returning<'a>
+}
+
+impl<'a> Insert<'a> {
+    /// Method to start with. 
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// let query = Insert::into("test_table");
+    /// let expected = "INSERT INTO test_table DEFAULT VALUES".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn into(table: &'a str) -> Self {
+       Insert {
+           table: table,
+           alias: None,
+           columns: vec![],
+           overriding: None,
+           values: Values::Default,
+           returning: Returning::Empty,
+       }
+    }
+
+    /// Starts with an aliased target (PostgreSQL), which is handy to refer to existing rows
+    /// in `ON CONFLICT` or `RETURNING` clauses.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// let query = Insert::into_as("test_table", "t").columns("foo").values(vec!["1"]).returning("t.id");
+    /// let expected = "INSERT INTO test_table AS t (foo) VALUES (1) RETURNING t.id".to_string();
+    /// assert_eq!(query.get_alias(), Some("t"));
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn into_as(table: &'a str, alias: &'a str) -> Self {
+        let mut rv = Self::into(table);
+        rv.alias = Some(alias);
+        rv
+    }
+
+    /// Specifies columns for `INSERT`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// let query = Insert::into("test_table").columns(&["foo", "bar"]).columns("bazz");
+    /// ```
+    pub fn columns<T: Pusheable<'a>>(mut self, columns: T) -> Self {
+        columns.push_to(&mut self.columns);
+        self
+    }
+
+    /// Replaces previously specified columns.
+    pub fn replace_columns<T: Pusheable<'a>>(mut self, columns: T) -> Self {
+        self.columns.clear();
+        columns.push_to(&mut self.columns);
+        self
+    }
+
+    /// Specifies `INSERT` values. Sorry for receiving `Vec` here - we're going to find a better way
+    /// for this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// let query = Insert::into("foo").columns("bar").values(vec!["bazz"]);
+    /// ```
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// let query = Insert::into("foo").columns(&["bar", "bazz"])
+    ///     .values(vec!["123", "123"]).values(vec!["345", "678"]); 
+    /// let expected = "INSERT INTO foo (bar, bazz) VALUES (123, 123), (345, 678)".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    // pub fn values<T: Pusheable<'a>>(mut self, input_values: T) -> Self {
+    //     match self.values {
+    //         Values::Default | Values::Select(_) => {
+    //             let mut values = vec![];
+    //             input_values.push_to(&mut values);
+    //             self.values = Values::Specified(values);
+    //         },
+    //         Values::Specified(ref mut values) => input_values.push_to(values)
+    //     }
+    //     self
+    // }
+    pub fn values(mut self, input_values: Vec<&'a str>) -> Self {
+        match self.values {
+            Values::Default | Values::Select(_) | Values::Union(_) | Values::Query(_) => {
+                self.values = Values::Specified(vec![input_values]);
+            },
+            Values::Specified(ref mut values) => values.push(input_values),
+            Values::Extended(ref mut values) => values.push(input_values.into_iter().map(Cell::Value).collect())
+        }
+        self
+    }
+
+    /// Adds a row where cells can be `DEFAULT` or generated at runtime.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// use lithium::insert::Cell;
+    /// let query = Insert::into("foo").columns(&["id", "bar"])
+    ///     .values(vec!["1", "2"])
+    ///     .row(vec![Cell::Default, Cell::Owned(format!("${}", 1))]);
+    /// let expected = "INSERT INTO foo (id, bar) VALUES (1, 2), (DEFAULT, $1)".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn row(mut self, cells: Vec<Cell<'a>>) -> Self {
+        self.values = match self.values {
+            Values::Default | Values::Select(_) | Values::Union(_) | Values::Query(_) => Values::Extended(vec![cells]),
+            Values::Specified(values) => {
+                let mut values = values.into_iter()
+                    .map(|x| x.into_iter().map(Cell::Value).collect::<Vec<_>>())
+                    .collect::<Vec<_>>();
+                values.push(cells);
+                Values::Extended(values)
+            },
+            Values::Extended(mut values) => {
+                values.push(cells);
+                Values::Extended(values)
+            }
+        };
+        self
+    }
+
+    /// Specifies `SELECT` as `INSERT` value. Results in `INSERT INTO ... SELECT`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{Select, Insert};
+    /// let select = Select::from("foo").columns(&["a", "b"]);
+    /// let insert = Insert::into("bar").columns(&["a", "b"]).query(select);
+    /// let expected = "INSERT INTO bar (a, b) SELECT a, b FROM foo".to_string();
+    /// assert_eq!(insert.to_sql(), expected);
+    /// ```
+    pub fn query(mut self, query: Select<'a>) -> Self {
+        self.values = Values::Select(Box::new(query));
+        self
+    }
+
+    /// Specifies any query as `INSERT` value: `Select`, `Union` of them or `WITH ... SELECT` kept
+    /// in `&str`. Queries are rendered for the dialect of `INSERT` and walked by `rewrite`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{Select, Insert, Dialect};
+    /// use lithium::select::{UnionMode, Union, For};
+    /// let union = Union::new(UnionMode::All, Select::from("foo").columns("a"), Select::from("bar").columns("a"));
+    /// let insert = Insert::into("bazz").columns("a").source(union);
+    /// let expected = "INSERT INTO bazz (a) SELECT a FROM foo UNION ALL SELECT a FROM bar".to_string();
+    /// assert_eq!(insert.to_sql(), expected);
+    ///
+    /// let insert = Insert::into("bazz").columns("a").source("WITH x AS (SELECT a FROM foo) SELECT a FROM x");
+    /// let expected = "INSERT INTO bazz (a) WITH x AS (SELECT a FROM foo) SELECT a FROM x".to_string();
+    /// assert_eq!(insert.to_sql(), expected);
+    ///
+    /// let select = Select::from("foo").columns("a").for_(For::update());
+    /// let insert = Insert::into("bazz").columns("a").source(select);
+    /// assert_eq!(insert.to_sql_for(Dialect::SQLite), "INSERT INTO bazz (a) SELECT a FROM foo".to_string());
+    /// ```
+    pub fn source<T: Into<Values<'a>>>(mut self, query: T) -> Self {
+        self.values = query.into();
+        self
+    }
+
+    /// Specifies `OVERRIDING` clause (PostgreSQL).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// use lithium::insert::Overriding;
+    /// let query = Insert::into("foo").columns(&["id", "bar"]).overriding(Overriding::System).values(vec!["1", "2"]);
+    /// let expected = "INSERT INTO foo (id, bar) OVERRIDING SYSTEM VALUE VALUES (1, 2)".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn overriding(mut self, overriding: Overriding) -> Self {
+        self.overriding = Some(overriding);
+        self
+    }
+
+    /// Removes `OVERRIDING` clause.
+    pub fn remove_overriding(mut self) -> Self {
+        self.overriding = None;
+        self
+    }
+
+    /// Splits `rows` into statements which fit into the limit of bound parameters of the dialect.
+    /// The query is used as a template: its values are replaced by rows of a batch, every cell
+    /// of which becomes a parameter. See `Batches` for settings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{Insert, Dialect};
+    /// use lithium::params::Value;
+    ///
+    /// let rows = (0..5).map(|x| vec![Value::Int(x), Value::Text(format!("user {}", x))]);
+    /// let batches = Insert::into("users").columns(&["id", "name"]).batches(rows, Dialect::SQLite)
+    ///     .max_parameters(4)
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(batches.len(), 3);
+    /// assert_eq!(batches[0].0.to_sql(), "INSERT INTO users (id, name) VALUES (?, ?), (?, ?)");
+    /// assert_eq!(batches[2].0.to_sql(), "INSERT INTO users (id, name) VALUES (?, ?)");
+    /// assert_eq!(batches[2].1, vec![Value::Int(4), Value::Text("user 4".to_string())]);
+    /// ```
+    pub fn batches<I, R, V>(self, rows: I, dialect: Dialect) -> Batches<'a, I::IntoIter>
+        where I: IntoIterator<Item = R>, R: IntoIterator<Item = V>, V: Into<Value>
+    {
+        Batches::new(self, rows.into_iter(), dialect)
+    }
+
+    /// Removes specified values or query. Will result in `INSERT INTO ... DEFAULT VALUES`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// let query = Insert::into("foo").values(vec!["bar"]).clear_values();
+    /// let expected = "INSERT INTO foo DEFAULT VALUES".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn clear_values(mut self) -> Self {
+        self.values = Values::Default;
+        self
+    }
+
+    /// Specifies `RETURNING` clause. WIll result in `RETURNING *`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// let query = Insert::into("foo").values(vec!["bar"]).returning_all();
+    /// let expected = "INSERT INTO foo VALUES (bar) RETURNING *".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn returning_all(mut self) -> Self {
+        self.returning = Returning::All;
+        self
+    }
+
+    /// Specifies columns for `RETURNING` clause.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// let query = Insert::into("foo").values(vec!["bar", "bazz"]).returning(&["a", "b"]);
+    /// let expected = "INSERT INTO foo VALUES (bar, bazz) RETURNING a, b".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn returning<T: Pusheable<'a>>(mut self, input_columns: T) -> Self {
+        match self.returning {
+            Returning::Empty | Returning::All => {
+                let mut columns = vec![];
+                input_columns.push_to(&mut columns);
+                self.returning = Returning::Specified(columns);
+            },
+            Returning::Specified(ref mut columns) => input_columns.push_to(columns)
+        };
+        self
+    }
+
+    /// Removes `RETURNING` clause.
+    pub fn remove_returning(mut self) -> Self {
+        self.returning = Returning::Empty;
+        self
+    }
+
+    /// Returns target table.
+    pub fn get_table(&self) -> &'a str {
+        self.table
+    }
+
+    /// Returns an alias of target table.
+    pub fn get_alias(&self) -> Option<&'a str> {
+        self.alias
+    }
+
+    /// Returns `OVERRIDING` clause.
+    pub fn get_overriding(&self) -> Option<Overriding> {
+        self.overriding
+    }
+
+    /// Checks that every row of `VALUES` has a cell for every column. Without columns
+    /// every row has to be as long as the first one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// use lithium::insert::InsertError;
+    /// let query = Insert::into("foo").values(vec!["1", "2"]).values(vec!["3"]);
+    /// assert_eq!(query.validate(), Err(InsertError::RowLength { row: 1, expected: 2, found: 1 }));
+    /// assert!(Insert::into("foo").columns("a").values(vec!["1"]).validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> Result<(), InsertError> {
+        let lengths = self.values.row_lengths();
+        let expected = match lengths.first() {
+            _ if !self.columns.is_empty() => self.columns.len(),
+            Some(&length) => length,
+            None => return Ok(())
+        };
+        for (row, &found) in lengths.iter().enumerate() {
+            if found == 0 {
+                return Err(InsertError::EmptyRow(row));
+            }
+            if found != expected {
+                return Err(InsertError::RowLength { row: row, expected: expected, found: found });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns columns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Insert;
+    /// use lithium::insert::Values;
+    /// let query = Insert::into("foo").columns(&["a", "b"]).values(vec!["1", "2"]);
+    /// assert_eq!(query.get_columns(), &["a", "b"]);
+    /// assert!(*query.get_values() == Values::Specified(vec![vec!["1", "2"]]));
+    /// ```
+    pub fn get_columns(&self) -> &[&'a str] {
+        &self.columns
+    }
+
+    /// Returns values or query.
+    pub fn get_values(&self) -> &Values<'a> {
+        &self.values
+    }
+
+    /// Returns `RETURNING` clause.
+    pub fn get_returning(&self) -> &Returning<'a> {
+        &self.returning
+    }
+
+    /// Generates SQL.
+    pub fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    /// Generates SQL for the dialect, which is used for the source query.
+    pub fn to_sql_for(&self, dialect: Dialect) -> String {
+        let mut rv = String::new();
+        rv.push_str("INSERT INTO");
+        rv.push(' ');
+        rv.push_str(&self.table);
+        if let Some(alias) = self.alias {
+            rv.push(' ');
+            rv.push_str("AS");
+            rv.push(' ');
+            rv.push_str(alias);
+        }
+
+        if !self.columns.is_empty() {
+            rv.push(' ');
+            rv.push('(');
+            rv.push_str(&self.columns.join(", "));
+            rv.push(')');
+        }
+
+        if let Some(overriding) = self.overriding {
+            rv.push(' ');
+            rv.push_str(overriding.to_sql());
+        }
+
+        rv.push(' ');
+        rv.push_str(&self.values.to_sql_for(dialect));
+
+        match self.returning {
+            Returning::Empty => {},
+            Returning::All => {
+                rv.push_str(RETURNING);
+                rv.push('*');
+            },
+            Returning::Specified(ref values) => {
+                rv.push_str(RETURNING);
+                rv.push_str(&values.join(", "));
+            }
+        };
+
+        rv
+    }
+}
+
+impl<'a> ToSQL for Insert<'a> {
+    fn to_sql(&self) -> String {
+        Insert::to_sql(self)
+    }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
+        Insert::to_sql_for(self, dialect)
+    }
+}
+
+impl<'a> Rewrite<'a> for Insert<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        visitor.visit_insert(self);
+        visitor.visit_table(&mut self.table);
+
+        self.values.walk(visitor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Values, Cell, Overriding, Insert, InsertError, Returning};
+    use dialect::Dialect;
+    use select::{Select, UnionMode, Union};
+    use visitor::{Visitor, rewrite};
+
+    struct Shard;
+
+    impl<'a> Visitor<'a> for Shard {
+        fn visit_table(&mut self, table: &mut &'a str) {
+            *table = match *table {
+                "test_table" => "test_table_1",
+                "foo" => "foo_1",
+                other => other
+            };
+        }
+    }
+
+    #[test]
+    fn test_simple() {
+        let insert = Insert {
+            table: "test_table",
+            alias: None,
+            columns: vec![],
+            overriding: None,
+            values: Values::Default,
+            returning: Returning::Empty,
+        };
+
+        let built = Insert::into("test_table");
+
+        let expected = {
+            "INSERT INTO test_table \
+            DEFAULT VALUES".to_string()
+        };
+
+        assert!(insert == built);
+        assert_eq!(insert.to_sql(), expected);
+    }
+
+    #[test]
+    fn test_with_spec_return() {
+        let insert = Insert {
+            table: "test_table",
+            alias: None,
+            columns: vec![],
+            overriding: None,
+            values: Values::Default,
+            returning: Returning::Specified(vec!["foo", "bar"])
+        };
+
+        let built = Insert::into("test_table").returning("foo").returning("bar");
+
+        let expected = {
+            "INSERT INTO test_table \
+            DEFAULT VALUES \
+            RETURNING foo, bar"
+        };
+
+        assert!(insert == built);
+        assert_eq!(insert.to_sql(), expected);
+    }
+
+    #[test]
+    fn test_with_values() {
+        let insert = Insert {
+            table: "test_table",
+            alias: None,
+            columns: vec!["foo", "bar"],
+            overriding: None,
+            values: Values::Specified(vec![vec!["DEFAULT, fizz"], vec!["foo", "bar"]]),
+            returning: Returning::All
+        };
+
+        let built = Insert::into("test_table")
+            .columns("foo")
+            .columns(&["bar"])
+            .values(vec!["DEFAULT, fizz"])
+            .values(vec!["foo", "bar"])
+            .returning_all();
+
+        let expected = {
+            "INSERT INTO test_table (foo, bar) \
+            VALUES (DEFAULT, fizz), (foo, bar) \
+            RETURNING *"
+        };
+
+        assert!(insert == built);
+        assert_eq!(insert.to_sql(), expected);
+    }
+
+    #[test]
+    fn test_with_query() {
+        let query = Select::from("test_table");
+        let insert = Insert {
+            table: "test_table",
+            alias: None,
+            columns: vec!["foo", "bar"],
+            overriding: None,
+            values: Values::Select(Box::new(query.clone())),
+            returning: Returning::Specified(vec!["bar", "foo"])
+        };
+
+        let built = Insert::into("test_table")
+            .columns(&["foo", "bar"])
+            .query(query)
+            .returning(&["bar", "foo"]);
+
+        let expected = {
+            "INSERT INTO test_table (foo, bar) \
+            SELECT * FROM test_table \
+            RETURNING bar, foo"
+        };
+        
+        assert!(insert == built);
+        assert_eq!(insert.to_sql(), expected);
+    }
+
+    #[test]
+    fn test_getters_and_mutators() {
+        let query = Select::from("test_table");
+        let insert = Insert::into("test_table")
+            .columns(&["foo", "bar"])
+            .query(query.clone())
+            .returning("foo");
+
+        assert_eq!(insert.get_table(), "test_table");
+        assert_eq!(insert.get_columns(), &["foo", "bar"]);
+        assert!(*insert.get_values() == Values::Select(Box::new(query)));
+        assert!(*insert.get_returning() == Returning::Specified(vec!["foo"]));
+
+        let built = insert.replace_columns("bazz").clear_values().remove_returning();
+        assert_eq!(built.to_sql(), "INSERT INTO test_table (bazz) DEFAULT VALUES");
+    }
+
+    #[test]
+    fn test_extended_rows() {
+        let insert = Insert::into_as("test_table", "t")
+            .columns(&["id", "foo"])
+            .overriding(Overriding::User)
+            .values(vec!["1", "2"])
+            .row(vec![Cell::Default, Cell::Owned("$1".to_string())])
+            .values(vec!["3", "4"]);
+
+        let expected = {
+            "INSERT INTO test_table AS t (id, foo) \
+            OVERRIDING USER VALUE \
+            VALUES (1, 2), (DEFAULT, $1), (3, 4)"
+        };
+        assert_eq!(insert.to_sql(), expected);
+        assert!(insert.get_overriding() == Some(Overriding::User));
+        assert!(insert.validate().is_ok());
+
+        let built = insert.remove_overriding().row(vec![Cell::Value("5")]);
+        let expected = "INSERT INTO test_table AS t (id, foo) VALUES (1, 2), (DEFAULT, $1), (3, 4), (5)";
+        assert_eq!(built.to_sql(), expected);
+        assert_eq!(built.validate(), Err(InsertError::RowLength { row: 3, expected: 2, found: 1 }));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Insert::into("test_table").validate().is_ok());
+        assert!(Insert::into("test_table").columns("a").query(Select::from("foo")).validate().is_ok());
+
+        let insert = Insert::into("test_table").values(vec!["1"]).values(vec!["2"]);
+        assert!(insert.validate().is_ok());
+        let insert = insert.values(vec!["3", "4"]);
+        assert_eq!(insert.validate(), Err(InsertError::RowLength { row: 2, expected: 1, found: 2 }));
+
+        let insert = Insert::into("test_table").row(vec![]);
+        assert_eq!(insert.validate(), Err(InsertError::EmptyRow(0)));
+        let insert = Insert::into("test_table").columns(&["a", "b"]).values(vec!["1"]);
+        assert_eq!(insert.validate().unwrap_err().to_string(), "row 0 has 1 values while 2 are expected");
+    }
+
+    #[test]
+    fn test_with_source() {
+        let union = Union::new(UnionMode::Simple, Select::from("foo"), Select::from("bar"));
+        let insert = Insert::into("test_table").source(union).returning_all();
+        let expected = "INSERT INTO test_table SELECT * FROM foo UNION SELECT * FROM bar RETURNING *";
+        assert_eq!(insert.to_sql(), expected);
+        let union = Union::new(UnionMode::Simple, Values::from(Select::from("foo")), Values::from(Select::from("bar")));
+        assert!(*insert.get_values() == Values::Union(Box::new(union)));
+
+        let mut sharded = Insert::into("test_table").source(Union::new(UnionMode::All, Select::from("foo"), "SELECT 1"));
+        rewrite(&mut sharded, &mut Shard);
+        let expected = "INSERT INTO test_table_1 SELECT * FROM foo_1 UNION ALL SELECT 1";
+        assert_eq!(sharded.to_sql_for(Dialect::MySQL), expected);
+
+        let insert = insert.values(vec!["1"]);
+        assert_eq!(insert.to_sql(), "INSERT INTO test_table VALUES (1) RETURNING *");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let insert = Insert::into("test_table")
+            .columns(&["foo", "bar"])
+            .values(vec!["1", "2"])
+            .values(vec!["3", "4"])
+            .returning_all();
+
+        let json = ::serde_json::to_string(&insert).unwrap();
+        let restored: Insert = ::serde_json::from_str(&json).unwrap();
+        assert!(restored == insert);
+
+        let insert = Insert::into("test_table").query(Select::from("other_test_table"));
+        let json = ::serde_json::to_string(&insert).unwrap();
+        let restored: Insert = ::serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.to_sql(), "INSERT INTO test_table SELECT * FROM other_test_table");
+    }
+}
@@ -15,16 +15,43 @@
 //! lithium does **not** handle security in any way, it's on your DB driver!
 //!
 //! You can find examples in documentation for every struct.
+//!
+//! Enable `serde` feature to get `Serialize` and `Deserialize` implementations for queries,
+//! so they can be stored and rendered later. Deserialized queries borrow strings from the input,
+//! which means it has to outlive them. Read the input into a `document::Document` first when
+//! strings may need unescaping (e.g. `\"` in JSON).
+
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(test)]
+extern crate serde_json;
 
 pub mod common;
+pub mod dialect;
 pub mod select;
 pub mod where_cl;
 pub mod update;
 pub mod insert;
+pub mod visitor;
+pub mod analysis;
+pub mod params;
+pub mod filter;
+pub mod function;
+pub mod case;
+pub mod copy;
+pub mod merge;
+pub mod transaction;
+pub mod explain;
+pub mod lint;
+#[cfg(feature = "serde")]
+pub mod document;
 
 #[doc(inline)]
 pub use common::{ToSQL, AsStr, Pusheable};
 #[doc(inline)]
+pub use dialect::Dialect;
+#[doc(inline)]
 pub use select::Select;
 #[doc(inline)]
 pub use insert::Insert;
@@ -0,0 +1,423 @@
+//! Keeps the linter, which reports dangerous or suspicious queries before they reach a database.
+//!
+//! Like `analysis`, it only sees what is known structurally: expressions written as strings
+//! are inspected as text and SQL kept by a `Subquery` isn't checked at all.
+//!
+//! # Example
+//!
+//! ```
+//! use lithium::{Select, Update};
+//! use lithium::lint::{Linter, Rule, Severity, lint};
+//!
+//! let warnings = lint(&Update::new("users").set("active = false"));
+//! assert_eq!(warnings[0].rule, Rule::UnfilteredWrite);
+//! assert_eq!(warnings[0].severity, Severity::Error);
+//!
+//! let query = Select::from("events").limit("10");
+//! assert_eq!(lint(&query)[0].message, "LIMIT without ORDER BY in SELECT from events returns arbitrary rows");
+//! let linter = Linter::new().allow(Rule::LimitWithoutOrder);
+//! assert!(linter.check(&query).is_empty());
+//! ```
+
+use std::collections::BTreeMap;
+
+use analysis::has_aggregate;
+use select::{Select, SelectType, Column, DistinctType, LimitType, OffsetType, SortKey, Join};
+use update::Update;
+use merge::Merge;
+use where_cl::{Where, WhereType, conjunction};
+use visitor::{Visitor, Rewrite};
+
+/// Things the linter looks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Rule {
+    /// `UPDATE` or `DELETE` without `WHERE` and `MERGE` without `ON`, which change every row
+    /// of the table. Filters which are always true, e.g. `WHERE TRUE`, count as missing.
+    UnfilteredWrite,
+    /// `SELECT *` with joins, which returns every column of every joined table.
+    StarWithJoins,
+    /// `OFFSET` without `ORDER BY`, which skips arbitrary rows.
+    OffsetWithoutOrder,
+    /// `LIMIT` without `ORDER BY`, which returns or changes arbitrary rows.
+    LimitWithoutOrder,
+    /// `DISTINCT ON` whose expressions aren't the leftmost `ORDER BY` expressions, which
+    /// PostgreSQL rejects, or which has no `ORDER BY` at all and keeps arbitrary rows.
+    DistinctOnMismatch,
+    /// Row-locking clause with aggregates, `GROUP BY`, `HAVING` or `DISTINCT`, which PostgreSQL rejects.
+    LockingWithAggregates,
+    /// Join with a condition which is always true, e.g. `ON TRUE`, or several `FROM` items
+    /// (`FROM a, b`) without `WHERE` to join them.
+    CartesianJoin
+}
+
+impl Rule {
+    /// Returns every rule.
+    pub fn all() -> Vec<Rule> {
+        vec![
+            Rule::UnfilteredWrite,
+            Rule::StarWithJoins,
+            Rule::OffsetWithoutOrder,
+            Rule::LimitWithoutOrder,
+            Rule::DistinctOnMismatch,
+            Rule::LockingWithAggregates,
+            Rule::CartesianJoin
+        ]
+    }
+
+    /// Returns severity used unless it's configured: `Error` for writes without a filter and
+    /// queries which the database rejects, `Warning` otherwise.
+    pub fn default_severity(&self) -> Severity {
+        match *self {
+            Rule::UnfilteredWrite | Rule::DistinctOnMismatch | Rule::LockingWithAggregates => Severity::Error,
+            _ => Severity::Warning
+        }
+    }
+}
+
+/// How serious a reported problem is. `Allow` disables the rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Severity {
+    Allow,
+    Warning,
+    Error
+}
+
+/// Problem found by the linter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Warning {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub message: String
+}
+
+/// Checks queries with configurable severities of the rules.
+///
+/// # Example
+///
+/// ```
+/// use lithium::Select;
+/// use lithium::lint::{Linter, Rule, Severity};
+///
+/// let linter = Linter::new().severity(Rule::StarWithJoins, Severity::Error);
+/// let query = Select::from("orders").join("users", "users.id = orders.user_id");
+/// let warnings = linter.check(&query);
+/// assert_eq!(warnings.len(), 1);
+/// assert_eq!(warnings[0].severity, Severity::Error);
+/// assert_eq!(warnings[0].message, "SELECT * from orders with joins returns every column of every joined table");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Linter {
+    severities: BTreeMap<Rule, Severity>
+}
+
+impl Linter {
+    /// Method to start with. Every rule has its default severity.
+    pub fn new() -> Self {
+        Linter {
+            severities: BTreeMap::new()
+        }
+    }
+
+    /// Overrides severity of the rule.
+    pub fn severity(mut self, rule: Rule, severity: Severity) -> Self {
+        self.severities.insert(rule, severity);
+        self
+    }
+
+    /// Disables the rule.
+    pub fn allow(self, rule: Rule) -> Self {
+        self.severity(rule, Severity::Allow)
+    }
+
+    /// Returns severity of the rule.
+    pub fn get_severity(&self, rule: Rule) -> Severity {
+        self.severities.get(&rule).cloned().unwrap_or_else(|| rule.default_severity())
+    }
+
+    /// Checks the query and queries nested into it, returns problems in order they were found.
+    pub fn check<'a, Q: Rewrite<'a> + Clone>(&self, query: &Q) -> Vec<Warning> {
+        let mut checker = Checker { linter: self, warnings: vec![] };
+        query.clone().walk(&mut checker);
+        checker.warnings
+    }
+}
+
+/// Checks the query with default severities.
+pub fn lint<'a, Q: Rewrite<'a> + Clone>(query: &Q) -> Vec<Warning> {
+    Linter::new().check(query)
+}
+
+struct Checker<'l> {
+    linter: &'l Linter,
+    warnings: Vec<Warning>
+}
+
+impl<'l> Checker<'l> {
+    fn report(&mut self, rule: Rule, message: String) {
+        let severity = self.linter.get_severity(rule);
+        if severity != Severity::Allow {
+            self.warnings.push(Warning {
+                rule: rule,
+                severity: severity,
+                message: message
+            });
+        }
+    }
+
+    fn check_joins(&mut self, joins: &[Join]) {
+        for join in joins.iter().filter(|x| is_always_true(x.clause)) {
+            let target = join.alias.unwrap_or(join.target);
+            self.report(Rule::CartesianJoin, format!("join of {} on {} pairs every row with every row", target, join.clause));
+        }
+    }
+
+    /// Checks comma-separated `FROM` items, which can be joined only by `WHERE`.
+    fn check_from(&mut self, from: &str, clauses: &[WhereType], query: &str) {
+        if has_top_level_comma(from) && is_unfiltered(clauses) {
+            self.report(Rule::CartesianJoin, format!("FROM {} in {} without WHERE pairs every row with every row", from, query));
+        }
+    }
+}
+
+impl<'a, 'l> Visitor<'a> for Checker<'l> {
+    fn visit_select(&mut self, query: &mut Select<'a>) {
+        let from = query.get_from();
+        let ordered = !query.get_order_by().is_empty();
+
+        let star = match *query.get_select_type() {
+            SelectType::All => true,
+            SelectType::Specific(ref columns) => columns.iter().any(|x| x.trim() == "*"),
+            SelectType::Extended(ref columns) => columns.iter().any(|x| *x.expression() == Column::Simple("*"))
+        };
+        if star && !query.get_joins().is_empty() {
+            self.report(Rule::StarWithJoins, format!("SELECT * from {} with joins returns every column of every joined table", from));
+        }
+
+        if *query.get_offset() != OffsetType::Empty && !ordered {
+            self.report(Rule::OffsetWithoutOrder, format!("OFFSET without ORDER BY in SELECT from {} skips arbitrary rows", from));
+        }
+        if *query.get_limit() != LimitType::Empty && !ordered {
+            self.report(Rule::LimitWithoutOrder, format!("LIMIT without ORDER BY in SELECT from {} returns arbitrary rows", from));
+        }
+
+        if let DistinctType::Extended(ref columns) = *query.get_distinct() {
+            let order_by = query.get_order_by();
+            if !ordered {
+                self.report(Rule::DistinctOnMismatch, format!("DISTINCT ON without ORDER BY in SELECT from {} keeps arbitrary rows", from));
+            } else if !order_by.iter().any(|x| matches!(x.order_by, SortKey::Position(_))) {
+                // ORDER BY may be shorter than DISTINCT ON, but the keys it starts with have to be there
+                let distinct = columns.iter().map(|x| x.trim().to_string()).collect::<Vec<_>>();
+                let is_mismatch = order_by.iter()
+                    .take(columns.len())
+                    .any(|x| !distinct.contains(&x.order_by.to_sql().trim().to_string()));
+                if is_mismatch {
+                    self.report(Rule::DistinctOnMismatch, format!(
+                        "DISTINCT ON ({}) in SELECT from {} doesn't match the leftmost ORDER BY expressions",
+                        columns.join(", "), from
+                    ));
+                }
+            }
+        }
+
+        if !query.get_for().clauses().is_empty() {
+            let reason = if !query.get_group_by().is_empty() {
+                Some("GROUP BY")
+            } else if !query.get_having().is_empty() {
+                Some("HAVING")
+            } else if *query.get_distinct() != DistinctType::Empty {
+                Some("DISTINCT")
+            } else if has_aggregate(&query.get_select_type().to_sql()) {
+                Some("aggregate functions")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                self.report(Rule::LockingWithAggregates, format!("row-locking clause of SELECT from {} can't be used with {}", from, reason));
+            }
+        }
+
+        self.check_joins(query.get_joins());
+        self.check_from(from, query.get_where(), "SELECT");
+    }
+
+    fn visit_update(&mut self, query: &mut Update<'a>) {
+        let table = query.get_table();
+        if is_unfiltered(query.get_where()) {
+            self.report(Rule::UnfilteredWrite, format!("UPDATE {} without WHERE changes every row", table));
+        }
+        if *query.get_limit() != LimitType::Empty && query.get_order_by().is_empty() {
+            self.report(Rule::LimitWithoutOrder, format!("LIMIT without ORDER BY in UPDATE {} changes arbitrary rows", table));
+        }
+        self.check_joins(query.get_joins());
+        let from = query.get_from().items().iter().map(|x| x.0).collect::<Vec<_>>().join(", ");
+        self.check_from(&from, query.get_where(), &format!("UPDATE {}", table));
+    }
+
+    fn visit_merge(&mut self, query: &mut Merge<'a>) {
+        if is_unfiltered(query.get_on()) {
+            let target = query.get_target();
+            self.report(Rule::UnfilteredWrite, format!("MERGE INTO {} on a condition which is always true matches every row", target));
+        }
+    }
+}
+
+/// Checks if clauses joined with `AND` let every row through: there are none, they are
+/// empty groups or they are always true once normalized.
+fn is_unfiltered(clauses: &[WhereType]) -> bool {
+    let clause = clauses.iter().cloned().fold(Where::with_and(), |rv, x| rv.expr(x)).normalize();
+    conjunction(&[clause]).is_none_or(|x| is_always_true(&x))
+}
+
+/// Looks for a comma outside of parentheses and quotes, e.g. in `a, b` but not in `(SELECT a, b ...) AS c`.
+fn has_top_level_comma(from: &str) -> bool {
+    let mut depth = 0usize;
+    let mut quote = None;
+    for x in from.chars() {
+        match quote {
+            Some(q) => if x == q { quote = None },
+            None => match x {
+                '\'' | '"' => quote = Some(x),
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => return true,
+                _ => {}
+            }
+        }
+    }
+    false
+}
+
+fn is_always_true(clause: &str) -> bool {
+    let clause = clause.split_whitespace().collect::<Vec<_>>().join("").to_uppercase();
+    matches!(clause.as_str(), "" | "TRUE" | "1=1" | "(TRUE)" | "(1=1)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, is_always_true, has_top_level_comma, Linter, Rule, Severity};
+    use select::{Select, Ordering, For};
+    use insert::Insert;
+    use update::Update;
+    use merge::{Merge, When};
+    use where_cl::Where;
+    use function::Function;
+
+    fn rules(warnings: Vec<super::Warning>) -> Vec<Rule> {
+        warnings.into_iter().map(|x| x.rule).collect()
+    }
+
+    #[test]
+    fn test_helpers() {
+        assert!(is_always_true(" 1 = 1 "));
+        assert!(is_always_true("true"));
+        assert!(!is_always_true("a.id = b.id"));
+        assert!(has_top_level_comma("a, b AS c"));
+        assert!(!has_top_level_comma("(SELECT a, b FROM c) AS d"));
+        assert!(!has_top_level_comma("\"a,b\""));
+    }
+
+    #[test]
+    fn test_clean() {
+        let query = Select::from("foo")
+            .columns(&["a", "b"])
+            .join("bar", "bar.id = foo.id")
+            .order_by("a", Ordering::Ascending)
+            .limit("10")
+            .offset("20");
+        assert!(lint(&query).is_empty());
+        assert!(lint(&Update::new("foo").set("a = 1").filter("id = 1")).is_empty());
+        assert!(lint(&Insert::into("foo").values(vec!["1"])).is_empty());
+    }
+
+    #[test]
+    fn test_select() {
+        let query = Select::from("foo").join("bar", "TRUE").offset("5");
+        assert_eq!(rules(lint(&query)), vec![Rule::StarWithJoins, Rule::OffsetWithoutOrder, Rule::CartesianJoin]);
+
+        let query = Select::from("foo").distinct_on("a").order_by("b", Ordering::Ascending);
+        let warnings = lint(&query);
+        assert_eq!(warnings[0].message, "DISTINCT ON (a) in SELECT from foo doesn't match the leftmost ORDER BY expressions");
+        let query = Select::from("foo").distinct_on(&["a", "b"]).order_by("b", Ordering::Descending).order_by("a", Ordering::Ascending);
+        assert!(lint(&query).is_empty());
+        let query = Select::from("foo").distinct_on(&["a", "b"]).order_by("a", Ordering::Ascending);
+        assert!(lint(&query).is_empty());
+        let query = Select::from("foo").distinct_on(&["a", "b"]).order_by("c", Ordering::Ascending);
+        assert_eq!(rules(lint(&query)), vec![Rule::DistinctOnMismatch]);
+        let query = Select::from("foo").distinct_on("a").order_by("a", Ordering::Ascending).order_by("b", Ordering::Ascending);
+        assert!(lint(&query).is_empty());
+        assert_eq!(rules(lint(&Select::from("foo").distinct_on("a"))), vec![Rule::DistinctOnMismatch]);
+
+        let query = Select::from("foo, bar").columns("foo.a");
+        let warnings = lint(&query);
+        assert_eq!(rules(warnings.clone()), vec![Rule::CartesianJoin]);
+        assert_eq!(warnings[0].message, "FROM foo, bar in SELECT without WHERE pairs every row with every row");
+        assert!(lint(&query.filter("foo.id = bar.id")).is_empty());
+    }
+
+    #[test]
+    fn test_locking() {
+        let query = Select::from("jobs").column(Function::count("*")).for_(For::update());
+        let warnings = lint(&query);
+        assert_eq!(warnings[0].rule, Rule::LockingWithAggregates);
+        assert_eq!(warnings[0].message, "row-locking clause of SELECT from jobs can't be used with aggregate functions");
+        assert_eq!(rules(lint(&Select::from("jobs").group_by("a").for_(For::share()))), vec![Rule::LockingWithAggregates]);
+        assert!(lint(&Select::from("jobs").columns(&["id", "account(x)"]).for_(For::update())).is_empty());
+    }
+
+    #[test]
+    fn test_update() {
+        let query = Update::new("foo").set("a = 1").limit("1").left_join("bar", "1 = 1");
+        assert_eq!(rules(lint(&query)), vec![Rule::UnfilteredWrite, Rule::LimitWithoutOrder, Rule::CartesianJoin]);
+        assert_eq!(lint(&query)[0].message, "UPDATE foo without WHERE changes every row");
+
+        let query = Update::new("foo").set("a = 1");
+        assert_eq!(rules(lint(&query.clone().filter(Where::with_and()))), vec![Rule::UnfilteredWrite]);
+        assert_eq!(rules(lint(&query.clone().filter("(TRUE)").filter(true))), vec![Rule::UnfilteredWrite]);
+        assert_eq!(rules(lint(&query.clone().filter(Where::with_or().expr("a = 2").expr("TRUE")))), vec![Rule::UnfilteredWrite]);
+        assert!(lint(&query.clone().filter(Where::with_and()).filter("id = 1")).is_empty());
+
+        let query = query.from("bar").add_from("bazz");
+        let warnings = lint(&query);
+        assert_eq!(rules(warnings.clone()), vec![Rule::UnfilteredWrite, Rule::CartesianJoin]);
+        assert_eq!(warnings[1].message, "FROM bar, bazz in UPDATE foo without WHERE pairs every row with every row");
+        assert!(lint(&query.filter("foo.id = bar.id AND bar.id = bazz.id")).is_empty());
+        let query = Update::new("foo").set("a = 1").from("bar, bazz");
+        assert_eq!(rules(lint(&query)), vec![Rule::UnfilteredWrite, Rule::CartesianJoin]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let query = Merge::into("foo").using("bar", "TRUE").when(When::matched().delete());
+        let warnings = lint(&query);
+        assert_eq!(rules(warnings.clone()), vec![Rule::UnfilteredWrite]);
+        assert_eq!(warnings[0].message, "MERGE INTO foo on a condition which is always true matches every row");
+        assert_eq!(rules(lint(&Merge::into("foo").using("bar", Where::with_and()))), vec![Rule::UnfilteredWrite]);
+
+        let select = Select::from("bar").limit("1");
+        let query = Merge::into("foo").using_query(select, "b", "foo.id = b.id");
+        assert_eq!(rules(lint(&query)), vec![Rule::LimitWithoutOrder]);
+    }
+
+    #[test]
+    fn test_nested() {
+        let select = Select::from("bar").columns(&["a"]).limit("1");
+        let query = Insert::into("foo").columns("a").query(select);
+        assert_eq!(rules(lint(&query)), vec![Rule::LimitWithoutOrder]);
+    }
+
+    #[test]
+    fn test_severity() {
+        let linter = Linter::new()
+            .allow(Rule::UnfilteredWrite)
+            .severity(Rule::LimitWithoutOrder, Severity::Error);
+        assert_eq!(linter.get_severity(Rule::StarWithJoins), Severity::Warning);
+        let warnings = linter.check(&Update::new("foo").set("a = 1").limit("1"));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].severity, Severity::Error);
+        assert_eq!(Rule::all().len(), 7);
+        assert!(Severity::Error > Severity::Warning);
+    }
+}
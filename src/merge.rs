@@ -0,0 +1,517 @@
+//! Keeps `MERGE` (PostgreSQL 15+) related stuff.
+
+use common::{ToSQL, AsStr, Pusheable};
+use insert::Cell;
+use select::{Select, IntoColumn};
+use update::Assignment;
+use where_cl::{WhereType, IntoWhereType, conjunction};
+use visitor::{Visitor, Rewrite};
+
+/// Rows which are merged into the target.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Source<'a> {
+    /// Table or already generated SQL of a `Subquery`.
+    Table(&'a str),
+    Select(Box<Select<'a>>)
+}
+
+/// Action of a `WHEN` branch.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Action<'a> {
+    DoNothing,
+    /// `UPDATE SET ...`, for matched rows only.
+    Update(Vec<Assignment<'a>>),
+    /// `DELETE`, for matched rows only.
+    Delete,
+    /// `INSERT (...) VALUES (...)`, for rows which aren't matched only.
+    Insert {
+        columns: Vec<&'a str>,
+        values: Vec<Cell<'a>>
+    },
+    /// `INSERT DEFAULT VALUES`.
+    InsertDefault
+}
+
+impl<'a> Action<'a> {
+    pub fn to_sql(&self) -> String {
+        match *self {
+            Action::DoNothing => "DO NOTHING".to_string(),
+            Action::Update(ref assignments) => {
+                let assignments = assignments.iter().map(|x| x.to_sql()).collect::<Vec<_>>();
+                format!("UPDATE SET {}", assignments.join(", "))
+            },
+            Action::Delete => "DELETE".to_string(),
+            Action::Insert { ref columns, ref values } => {
+                let mut rv = String::new();
+                rv.push_str("INSERT");
+                if !columns.is_empty() {
+                    rv.push(' ');
+                    rv.push('(');
+                    rv.push_str(&columns.join(", "));
+                    rv.push(')');
+                }
+                rv.push(' ');
+                rv.push_str("VALUES");
+                rv.push(' ');
+                rv.push('(');
+                rv.push_str(&values.iter().map(|x| x.to_sql()).collect::<Vec<_>>().join(", "));
+                rv.push(')');
+                rv
+            },
+            Action::InsertDefault => "INSERT DEFAULT VALUES".to_string()
+        }
+    }
+}
+
+/// `WHEN [NOT] MATCHED [AND ...] THEN ...` branch of `MERGE`. Does nothing until an action
+/// is specified.
+///
+/// The database checks that `UPDATE` and `DELETE` are used only for matched rows and
+/// `INSERT` only for rows which aren't matched, lithium renders what it's given.
+///
+/// # Example
+///
+/// ```
+/// use lithium::merge::When;
+/// use lithium::insert::Cell;
+///
+/// let when = When::matched().and("s.amount > 0").set("amount = t.amount + s.amount").set_value("updated", "now()");
+/// assert_eq!(when.to_sql(), "WHEN MATCHED AND s.amount > 0 THEN UPDATE SET amount = t.amount + s.amount, updated = now()");
+///
+/// let when = When::not_matched().insert_row(&["id", "amount", "created"], vec![Cell::Value("s.id"), Cell::Value("s.amount"), Cell::Default]);
+/// assert_eq!(when.to_sql(), "WHEN NOT MATCHED THEN INSERT (id, amount, created) VALUES (s.id, s.amount, DEFAULT)");
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub struct When<'a> {
+    matched: bool,
+    condition: Vec<WhereType<'a>>,
+    action: Action<'a>
+}
+
+impl<'a> When<'a> {
+    fn new(matched: bool) -> Self {
+        When {
+            matched: matched,
+            condition: vec![],
+            action: Action::DoNothing
+        }
+    }
+
+    /// Method to start with a branch for rows of the source which have a row in the target.
+    pub fn matched() -> Self {
+        Self::new(true)
+    }
+
+    /// Method to start with a branch for rows of the source which don't have a row in the target.
+    pub fn not_matched() -> Self {
+        Self::new(false)
+    }
+
+    /// Specifies an additional condition. Several conditions are joined with `AND`.
+    pub fn and<T: IntoWhereType<'a>>(mut self, clause: T) -> Self {
+        self.condition.push(clause.into_where_type());
+        self
+    }
+
+    fn push_assignments<I: IntoIterator<Item = Assignment<'a>>>(mut self, input: I) -> Self {
+        match self.action {
+            Action::Update(ref mut assignments) => assignments.extend(input),
+            _ => self.action = Action::Update(input.into_iter().collect())
+        }
+        self
+    }
+
+    /// Specifies `UPDATE SET` expressions like `Update::set`.
+    pub fn set<T: Pusheable<'a>>(self, expressions: T) -> Self {
+        let mut input = vec![];
+        expressions.push_to(&mut input);
+        self.push_assignments(input.into_iter().map(Assignment::Simple))
+    }
+
+    /// Specifies `UPDATE SET column = value` like `Update::set_value`.
+    pub fn set_value<T: IntoColumn<'a>>(self, column: &'a str, value: T) -> Self {
+        self.push_assignments(Some(Assignment::Value { column: column, value: value.into_column() }))
+    }
+
+    /// Specifies `DELETE`.
+    pub fn delete(mut self) -> Self {
+        self.action = Action::Delete;
+        self
+    }
+
+    /// Specifies `INSERT (columns) VALUES (values)`. Columns can be omitted with `&[]`.
+    pub fn insert<T: Pusheable<'a>>(self, columns: T, values: Vec<&'a str>) -> Self {
+        self.insert_row(columns, values.into_iter().map(Cell::Value).collect())
+    }
+
+    /// Specifies `INSERT` of a row where cells can be `DEFAULT` or generated at runtime.
+    pub fn insert_row<T: Pusheable<'a>>(mut self, columns: T, values: Vec<Cell<'a>>) -> Self {
+        let mut input = vec![];
+        columns.push_to(&mut input);
+        self.action = Action::Insert { columns: input, values: values };
+        self
+    }
+
+    /// Specifies `INSERT DEFAULT VALUES`.
+    pub fn insert_default(mut self) -> Self {
+        self.action = Action::InsertDefault;
+        self
+    }
+
+    /// Specifies `DO NOTHING`, which is the action of a new branch.
+    pub fn do_nothing(mut self) -> Self {
+        self.action = Action::DoNothing;
+        self
+    }
+
+    /// Checks whether the branch is for matched rows.
+    pub fn is_matched(&self) -> bool {
+        self.matched
+    }
+
+    /// Returns conditions. They are joined with `AND`.
+    pub fn get_condition(&self) -> &[WhereType<'a>] {
+        &self.condition
+    }
+
+    /// Returns the action.
+    pub fn get_action(&self) -> &Action<'a> {
+        &self.action
+    }
+
+    pub fn to_sql(&self) -> String {
+        let mut rv = String::new();
+        rv.push_str(if self.matched { "WHEN MATCHED" } else { "WHEN NOT MATCHED" });
+        if let Some(clause) = conjunction(&self.condition) {
+            rv.push(' ');
+            rv.push_str("AND");
+            rv.push(' ');
+            rv.push_str(&clause);
+        }
+        rv.push(' ');
+        rv.push_str("THEN");
+        rv.push(' ');
+        rv.push_str(&self.action.to_sql());
+        rv
+    }
+}
+
+/// Represents `MERGE` query.
+///
+/// # Example
+///
+/// ```
+/// use lithium::{Select, Where};
+/// use lithium::merge::{Merge, When};
+///
+/// let changes = Select::from("staging").filter("batch_id = $1");
+/// let query = Merge::into_as("accounts", "t")
+///     .using_query(changes, "s", Where::with_and().expr("t.id = s.id").expr("t.region = s.region"))
+///     .when(When::matched().and("s.closed").delete())
+///     .when(When::matched().set_value("balance", "s.balance"))
+///     .when(When::not_matched().insert(&["id", "region", "balance"], vec!["s.id", "s.region", "s.balance"]));
+/// let expected = {
+///     "MERGE INTO accounts AS t \
+///     USING (SELECT * FROM staging WHERE batch_id = $1) AS s \
+///     ON (t.id = s.id AND t.region = s.region) \
+///     WHEN MATCHED AND s.closed THEN DELETE \
+///     WHEN MATCHED THEN UPDATE SET balance = s.balance \
+///     WHEN NOT MATCHED THEN INSERT (id, region, balance) VALUES (s.id, s.region, s.balance)".to_string()
+/// };
+/// assert_eq!(query.to_sql(), expected);
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub struct Merge<'a> {
+    target: &'a str,
+    alias: Option<&'a str>,
+    source: Source<'a>,
+    source_alias: Option<&'a str>,
+    on: Vec<WhereType<'a>>,
+    branches: Vec<When<'a>>
+}
+
+/// Target of `MERGE` which is waiting for a source, see `Merge::into`.
+#[derive(Clone)]
+pub struct MergeInto<'a> {
+    target: &'a str,
+    alias: Option<&'a str>
+}
+
+impl<'a> MergeInto<'a> {
+    fn finish<T: IntoWhereType<'a>>(self, source: Source<'a>, alias: Option<&'a str>, on: T) -> Merge<'a> {
+        Merge {
+            target: self.target,
+            alias: self.alias,
+            source: source,
+            source_alias: alias,
+            on: vec![on.into_where_type()],
+            branches: vec![]
+        }
+    }
+
+    /// Specifies `USING` clause along with `ON` one. Can take either `&str` or `&Subquery`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Select;
+    /// use lithium::merge::{Merge, When};
+    ///
+    /// let subquery = Select::from("staging").as_subquery().with_alias("s");
+    /// let query = Merge::into("accounts").using(&subquery, "accounts.id = s.id").when(When::matched().delete());
+    /// let expected = {
+    ///     "MERGE INTO accounts USING (SELECT * FROM staging) AS s \
+    ///     ON accounts.id = s.id WHEN MATCHED THEN DELETE".to_string()
+    /// };
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn using<T: AsStr<'a>, W: IntoWhereType<'a>>(self, source: T, on: W) -> Merge<'a> {
+        let alias = source.alias();
+        self.finish(Source::Table(source.as_str()), alias, on)
+    }
+
+    /// Specifies `USING` clause with an alias. An alias of a subquery is replaced.
+    pub fn using_as<T: AsStr<'a>, W: IntoWhereType<'a>>(self, source: T, alias: &'a str, on: W) -> Merge<'a> {
+        self.finish(Source::Table(source.as_str()), Some(alias), on)
+    }
+
+    /// Specifies `USING` clause with a query, which is visited by `rewrite` unlike a `Subquery`.
+    pub fn using_query<W: IntoWhereType<'a>>(self, query: Select<'a>, alias: &'a str, on: W) -> Merge<'a> {
+        self.finish(Source::Select(Box::new(query)), Some(alias), on)
+    }
+}
+
+impl<'a> Merge<'a> {
+    /// Method to start with. The source is specified with one of `using` methods along with
+    /// `ON` clause, so a query can't be built without them.
+    pub fn into(target: &'a str) -> MergeInto<'a> {
+        MergeInto {
+            target: target,
+            alias: None
+        }
+    }
+
+    /// Starts with an aliased target.
+    pub fn into_as(target: &'a str, alias: &'a str) -> MergeInto<'a> {
+        MergeInto {
+            target: target,
+            alias: Some(alias)
+        }
+    }
+
+    /// Adds a clause to `ON` one. Can take either `&str` or `Where`, clauses are joined with `AND`.
+    pub fn on<T: IntoWhereType<'a>>(mut self, clause: T) -> Self {
+        self.on.push(clause.into_where_type());
+        self
+    }
+
+    /// Adds a `WHEN` branch. Branches are checked in the order they were added.
+    pub fn when(mut self, branch: When<'a>) -> Self {
+        self.branches.push(branch);
+        self
+    }
+
+    /// Removes all `WHEN` branches.
+    pub fn clear_branches(mut self) -> Self {
+        self.branches.clear();
+        self
+    }
+
+    /// Returns target table.
+    pub fn get_target(&self) -> &'a str {
+        self.target
+    }
+
+    /// Returns an alias of target table.
+    pub fn get_alias(&self) -> Option<&'a str> {
+        self.alias
+    }
+
+    /// Returns the source.
+    pub fn get_source(&self) -> &Source<'a> {
+        &self.source
+    }
+
+    /// Returns an alias of the source.
+    pub fn get_source_alias(&self) -> Option<&'a str> {
+        self.source_alias
+    }
+
+    /// Returns `ON` clauses. They are joined with `AND`.
+    pub fn get_on(&self) -> &[WhereType<'a>] {
+        &self.on
+    }
+
+    /// Returns `WHEN` branches.
+    pub fn get_branches(&self) -> &[When<'a>] {
+        &self.branches
+    }
+
+    /// Generates SQL.
+    pub fn to_sql(&self) -> String {
+        let mut rv = String::new();
+        rv.push_str("MERGE INTO");
+        rv.push(' ');
+        rv.push_str(self.target);
+        if let Some(alias) = self.alias {
+            rv.push(' ');
+            rv.push_str("AS");
+            rv.push(' ');
+            rv.push_str(alias);
+        }
+
+        rv.push(' ');
+        rv.push_str("USING");
+        rv.push(' ');
+        match self.source {
+            Source::Table(table) => rv.push_str(table),
+            Source::Select(ref query) => {
+                rv.push('(');
+                rv.push_str(&query.to_sql());
+                rv.push(')');
+            }
+        }
+        if let Some(alias) = self.source_alias {
+            rv.push(' ');
+            rv.push_str("AS");
+            rv.push(' ');
+            rv.push_str(alias);
+        }
+
+        rv.push(' ');
+        rv.push_str("ON");
+        rv.push(' ');
+        // `ON` consisting of empty groups only is kept visible rather than dropped
+        rv.push_str(&conjunction(&self.on).unwrap_or_else(|| "TRUE".to_string()));
+
+        for branch in &self.branches {
+            rv.push(' ');
+            rv.push_str(&branch.to_sql());
+        }
+        rv
+    }
+}
+
+impl<'a> ToSQL for Merge<'a> {
+    fn to_sql(&self) -> String {
+        Merge::to_sql(self)
+    }
+}
+
+impl<'a> Rewrite<'a> for Merge<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        visitor.visit_merge(self);
+        visitor.visit_table(&mut self.target);
+        match self.source {
+            Source::Table(ref mut table) => visitor.visit_table(table),
+            Source::Select(ref mut query) => query.walk(visitor)
+        }
+
+        let branches = self.branches.iter_mut().flat_map(|x| x.condition.iter_mut());
+        for clause in self.on.iter_mut().chain(branches) {
+            clause.walk(visitor);
+        }
+
+        for branch in &mut self.branches {
+            if let Action::Update(ref mut assignments) = branch.action {
+                for assignment in assignments {
+                    assignment.walk(visitor);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Merge, When, Action, Source};
+    use case::Case;
+    use insert::Cell;
+    use select::{Select, Column};
+    use update::Assignment;
+    use where_cl::Where;
+    use visitor::{Visitor, rewrite};
+
+    #[test]
+    fn test_branches() {
+        let when = When::matched().set("a = s.a").set(&["b = s.b"]).set_value("c", "s.c".to_string());
+        assert!(when.is_matched());
+        assert_eq!(when.to_sql(), "WHEN MATCHED THEN UPDATE SET a = s.a, b = s.b, c = s.c");
+
+        let when = when.and("s.a IS NULL").and("t.locked").do_nothing();
+        assert_eq!(when.to_sql(), "WHEN MATCHED AND s.a IS NULL AND t.locked THEN DO NOTHING");
+        assert!(*when.get_action() == Action::DoNothing);
+
+        let when = When::not_matched().insert(&[], vec!["s.a", "s.b"]);
+        assert_eq!(when.to_sql(), "WHEN NOT MATCHED THEN INSERT VALUES (s.a, s.b)");
+        assert!(!when.is_matched());
+        let when = when.insert_default();
+        assert_eq!(when.to_sql(), "WHEN NOT MATCHED THEN INSERT DEFAULT VALUES");
+
+        let when = When::not_matched().insert_row("a", vec![Cell::Owned("$1".to_string())]);
+        assert!(*when.get_action() == Action::Insert { columns: vec!["a"], values: vec![Cell::Owned("$1".to_string())] });
+        let when = When::matched().delete().set_value("a", "1");
+        assert!(*when.get_action() == Action::Update(vec![Assignment::Value { column: "a", value: Column::Simple("1") }]));
+    }
+
+    #[test]
+    fn test_merge() {
+        let query = Merge::into("target_table")
+            .using_as("source_table", "s", "target_table.id = s.id")
+            .on("s.active")
+            .when(When::matched().set("a = s.a"))
+            .when(When::not_matched().insert("id", vec!["s.id"]));
+        let expected = {
+            "MERGE INTO target_table USING source_table AS s \
+            ON target_table.id = s.id AND s.active \
+            WHEN MATCHED THEN UPDATE SET a = s.a \
+            WHEN NOT MATCHED THEN INSERT (id) VALUES (s.id)"
+        };
+        assert_eq!(query.to_sql(), expected);
+        assert!(*query.get_source() == Source::Table("source_table"));
+        assert_eq!(query.get_source_alias(), Some("s"));
+        assert_eq!(query.get_branches().len(), 2);
+
+        let query = query.clear_branches();
+        assert_eq!(query.to_sql(), "MERGE INTO target_table USING source_table AS s ON target_table.id = s.id AND s.active");
+
+        let query = Merge::into("target_table").using("source_table", Where::with_and()).on(Where::with_and());
+        assert_eq!(query.get_source_alias(), None);
+        assert_eq!(query.to_sql(), "MERGE INTO target_table USING source_table ON TRUE");
+    }
+
+    struct Tables(Vec<String>);
+
+    impl<'a> Visitor<'a> for Tables {
+        fn visit_select(&mut self, query: &mut Select<'a>) {
+            *query = query.clone().filter("tenant_id = $1");
+        }
+
+        fn visit_table(&mut self, table: &mut &'a str) {
+            self.0.push(table.to_string());
+        }
+    }
+
+    #[test]
+    fn test_rewrite() {
+        let rank = Case::new().when(Where::exists(Select::from("bazz")), "1").otherwise("2");
+        let mut query = Merge::into_as("foo", "t")
+            .using_query(Select::from("bar"), "s", "t.id = s.id")
+            .when(When::matched().and("s.deleted").delete())
+            .when(When::matched().set_value("rank", rank));
+        let mut visitor = Tables(vec![]);
+        rewrite(&mut query, &mut visitor);
+
+        assert_eq!(visitor.0, vec!["foo", "bar", "bazz"]);
+        let expected = {
+            "MERGE INTO foo AS t USING (SELECT * FROM bar WHERE tenant_id = $1) AS s \
+            ON t.id = s.id WHEN MATCHED AND s.deleted THEN DELETE \
+            WHEN MATCHED THEN UPDATE SET rank = CASE WHEN EXISTS (SELECT * FROM bazz WHERE tenant_id = $1) THEN 1 ELSE 2 END"
+        };
+        assert_eq!(query.to_sql(), expected);
+    }
+}
@@ -0,0 +1,201 @@
+//! Keeps stuff for bound parameters, which is used when lithium generates clauses itself
+//! (e.g. from user-facing filters) and has to keep values out of SQL.
+
+use std::fmt;
+
+/// Value of a bound parameter.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(untagged))]
+pub enum Value {
+    #[default]
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String)
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Null => f.write_str("NULL"),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Text(ref value) => f.write_str(value)
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(value: &'a str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => Value::Null
+        }
+    }
+}
+
+/// Style of placeholders in generated SQL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placeholder {
+    /// `$1`, `$2`, ... (PostgreSQL)
+    Numbered,
+    /// `?` (MySQL, SQLite)
+    Question
+}
+
+/// Collects values of bound parameters and hands out placeholders for them.
+///
+/// # Example
+///
+/// ```
+/// use lithium::params::{Parameters, Placeholder, Value};
+///
+/// let mut params = Parameters::new(Placeholder::Numbered).offset(2);
+/// assert_eq!(params.push("bob"), "$3");
+/// assert_eq!(params.push(18), "$4");
+/// assert_eq!(params.values(), &[Value::Text("bob".to_string()), Value::Int(18)]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameters {
+    pub placeholder: Placeholder,
+    offset: usize,
+    values: Vec<Value>
+}
+
+impl Parameters {
+    /// Method to start with.
+    pub fn new(placeholder: Placeholder) -> Self {
+        Parameters {
+            placeholder: placeholder,
+            offset: 0,
+            values: vec![]
+        }
+    }
+
+    /// Specifies the number of parameters which are already used by the query,
+    /// so numbered placeholders continue after them.
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Adds a value and returns a placeholder for it.
+    pub fn push<T: Into<Value>>(&mut self, value: T) -> String {
+        self.values.push(value.into());
+        match self.placeholder {
+            Placeholder::Numbered => format!("${}", self.offset + self.values.len()),
+            Placeholder::Question => "?".to_string()
+        }
+    }
+
+    /// Returns the value of a numbered placeholder, e.g. `$3`. Question marks can't be told
+    /// apart, so there is nothing to return for them.
+    pub fn get(&self, placeholder: &str) -> Option<&Value> {
+        match self.placeholder {
+            Placeholder::Numbered => {
+                let number = placeholder.strip_prefix('$')?.parse::<usize>().ok()?;
+                number.checked_sub(self.offset + 1).and_then(|x| self.values.get(x))
+            },
+            Placeholder::Question => None
+        }
+    }
+
+    /// Returns collected values in the order of placeholders.
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// Returns collected values in the order of placeholders.
+    pub fn into_values(self) -> Vec<Value> {
+        self.values
+    }
+
+    /// Returns the number of collected values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Checks whether there are no collected values.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Parameters, Placeholder, Value};
+
+    #[test]
+    fn test_numbered() {
+        let mut params = Parameters::new(Placeholder::Numbered);
+        assert_eq!(params.push("a"), "$1");
+        assert_eq!(params.push(Some(1)), "$2");
+        assert_eq!(params.push(None::<i64>), "$3");
+        assert_eq!(params.len(), 3);
+        assert_eq!(params.into_values(), vec![Value::Text("a".to_string()), Value::Int(1), Value::Null]);
+    }
+
+    #[test]
+    fn test_get() {
+        let mut params = Parameters::new(Placeholder::Numbered).offset(2);
+        params.push("a");
+        assert_eq!(params.get("$3"), Some(&Value::Text("a".to_string())));
+        assert_eq!(params.get("$2"), None);
+        assert_eq!(params.get("$4"), None);
+        assert_eq!(params.get("?"), None);
+    }
+
+    #[test]
+    fn test_question() {
+        let mut params = Parameters::new(Placeholder::Question).offset(5);
+        assert!(params.is_empty());
+        assert_eq!(params.push(true), "?");
+        assert_eq!(params.push(1.5), "?");
+        assert_eq!(params.values(), &[Value::Bool(true), Value::Float(1.5)]);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Value::Null.to_string(), "NULL");
+        assert_eq!(Value::Int(-3).to_string(), "-3");
+        assert_eq!(Value::Text("a b".to_string()).to_string(), "a b");
+    }
+}
@@ -0,0 +1,69 @@
+use common::ToSQL;
+use dialect::Dialect;
+use super::Select;
+
+/// Counting query derived from a `Select` with `Select::count`.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Count<'a> {
+    /// `SELECT COUNT(*) FROM ...` with the same `FROM`, joins and `WHERE`.
+    Simple(Select<'a>),
+    /// `SELECT COUNT(*) FROM (...) AS counted`, used when grouping or `DISTINCT` change
+    /// the number of rows.
+    Wrapped(Select<'a>)
+}
+
+impl<'a> ToSQL for Count<'a> {
+    fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
+        match *self {
+            Count::Simple(ref query) => query.to_sql_for(dialect),
+            Count::Wrapped(ref query) => {
+                let mut rv = String::new();
+                rv.push_str("SELECT COUNT(*) FROM");
+                rv.push(' ');
+                rv.push('(');
+                rv.push_str(&query.to_sql_for(dialect));
+                rv.push(')');
+                rv.push(' ');
+                rv.push_str("AS counted");
+                rv
+            }
+        }
+    }
+}
+
+impl<'a> ToSQL for &'a Count<'a> {
+    fn to_sql(&self) -> String {
+        (**self).to_sql()
+    }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
+        (**self).to_sql_for(dialect)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Count;
+    use common::ToSQL;
+    use select::Select;
+
+    #[test]
+    fn test_simple() {
+        let count = Count::Simple(Select::from("test_table").columns("COUNT(*)"));
+        assert_eq!(count.to_sql(), "SELECT COUNT(*) FROM test_table");
+    }
+
+    #[test]
+    fn test_wrapped() {
+        let count = Count::Wrapped(Select::from("test_table").group_by("foo"));
+        let expected = {
+            "SELECT COUNT(*) FROM \
+            (SELECT * FROM test_table GROUP BY foo) AS counted"
+        };
+        assert_eq!(count.to_sql(), expected);
+    }
+}
@@ -1,4 +1,5 @@
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
 pub enum DistinctType<'a> {
     Empty,
     Simple,
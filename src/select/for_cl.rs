@@ -1,25 +1,50 @@
-use common::Pusheable;
+use common::{ToSQL, Pusheable};
+use dialect::Dialect;
 
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ForMode {
     Update,
-    Share
+    NoKeyUpdate,
+    Share,
+    KeyShare
 }
 
 impl ForMode {
     fn to_sql(&self) -> &str {
         match *self {
             ForMode::Update => "UPDATE",
-            ForMode::Share => "SHARE"
+            ForMode::NoKeyUpdate => "NO KEY UPDATE",
+            ForMode::Share => "SHARE",
+            ForMode::KeyShare => "KEY SHARE"
         }
     }
 }
 
+/// Row-locking clause.
+///
+/// PostgreSQL is the only dialect having `NO KEY UPDATE` and `KEY SHARE`, MySQL gets
+/// stronger `UPDATE` and `SHARE` locks instead. Plain `SHARE` is rendered as
+/// `LOCK IN SHARE MODE` for MySQL. SQLite has no row locks, so nothing is rendered there.
+///
+/// # Example
+///
+/// ```
+/// use lithium::{ToSQL, Dialect};
+/// use lithium::select::For;
+///
+/// let for_cl = For::no_key_update().table("jobs").skip_locked();
+/// assert_eq!(for_cl.to_sql(), "FOR NO KEY UPDATE OF jobs SKIP LOCKED");
+/// assert_eq!(for_cl.to_sql_for(Dialect::MySQL), "FOR UPDATE OF jobs SKIP LOCKED");
+/// assert_eq!(For::share().to_sql_for(Dialect::MySQL), "LOCK IN SHARE MODE");
+/// ```
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
 pub struct For<'a> {
     pub mode: ForMode,
     pub tables: Vec<&'a str>,
     pub nowait: bool,
+    pub skip_locked: bool
 }
 
 impl<'a> For<'a> {
@@ -27,7 +52,8 @@ impl<'a> For<'a> {
         For {
             mode: mode,
             tables: vec![],
-            nowait: false
+            nowait: false,
+            skip_locked: false
         }
     }
 
@@ -35,27 +61,60 @@ impl<'a> For<'a> {
         Self::new(ForMode::Update)
     }
 
+    pub fn no_key_update() -> Self {
+        Self::new(ForMode::NoKeyUpdate)
+    }
+
     pub fn share() -> Self {
         Self::new(ForMode::Share)
     }
 
+    pub fn key_share() -> Self {
+        Self::new(ForMode::KeyShare)
+    }
+
     pub fn table<T: Pusheable<'a>>(mut self, tables: T) -> Self {
         tables.push_to(&mut self.tables);
         self
     }
 
+    /// Specifies `NOWAIT`. Replaces `SKIP LOCKED`.
     pub fn nowait(mut self) -> Self {
         self.nowait = true;
+        self.skip_locked = false;
+        self
+    }
+
+    /// Specifies `SKIP LOCKED`. Replaces `NOWAIT`.
+    pub fn skip_locked(mut self) -> Self {
+        self.skip_locked = true;
+        self.nowait = false;
         self
     }
 }
 
-impl<'a> For<'a> {
-    pub fn to_sql(&self) -> String {
+impl<'a> ToSQL for For<'a> {
+    fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
+        let mode = match (dialect, &self.mode) {
+            (Dialect::SQLite, _) => return String::new(),
+            (Dialect::MySQL, &ForMode::NoKeyUpdate) => &ForMode::Update,
+            (Dialect::MySQL, &ForMode::KeyShare) => &ForMode::Share,
+            (_, mode) => mode
+        };
+
+        let plain = self.tables.is_empty() && !self.nowait && !self.skip_locked;
+        if dialect == Dialect::MySQL && *mode == ForMode::Share && plain {
+            return "LOCK IN SHARE MODE".to_string();
+        }
+
         let mut rv = String::new();
         rv.push_str("FOR");
         rv.push(' ');
-        rv.push_str(self.mode.to_sql());
+        rv.push_str(mode.to_sql());
 
         if !self.tables.is_empty() {
             rv.push(' ');
@@ -67,20 +126,64 @@ impl<'a> For<'a> {
         if self.nowait {
             rv.push(' ');
             rv.push_str("NOWAIT");
+        } else if self.skip_locked {
+            rv.push(' ');
+            rv.push_str("SKIP LOCKED");
         }
         rv
     }
 }
 
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
 pub enum ForType<'a> {
     Empty,
-    Specified(For<'a>)
+    Specified(For<'a>),
+    /// Several locking clauses, e.g. with different sets of tables.
+    Multiple(Vec<For<'a>>)
+}
+
+impl<'a> ForType<'a> {
+    /// Returns locking clauses, if there are any.
+    pub fn clauses(&self) -> &[For<'a>] {
+        match *self {
+            ForType::Empty => &[],
+            ForType::Specified(ref for_cl) => ::std::slice::from_ref(for_cl),
+            ForType::Multiple(ref clauses) => clauses
+        }
+    }
+
+    /// Adds one more locking clause.
+    pub fn push(self, for_cl: For<'a>) -> Self {
+        match self {
+            ForType::Empty => ForType::Specified(for_cl),
+            ForType::Specified(first) => ForType::Multiple(vec![first, for_cl]),
+            ForType::Multiple(mut clauses) => {
+                clauses.push(for_cl);
+                ForType::Multiple(clauses)
+            }
+        }
+    }
+
+    /// Renders every clause for the dialect, returns `None` if there is nothing to render.
+    pub fn to_sql_for(&self, dialect: Dialect) -> Option<String> {
+        let clauses = self.clauses().iter()
+            .map(|x| x.to_sql_for(dialect))
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<_>>();
+        if clauses.is_empty() {
+            None
+        } else {
+            Some(clauses.join(" "))
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{ForMode, For};
+    use super::{ForMode, For, ForType};
+    use common::ToSQL;
+    use dialect::Dialect;
 
     #[test]
     fn test_modes() {
@@ -96,7 +199,8 @@ mod tests {
         let for_cl = For {
             mode: ForMode::Update,
             tables: vec![],
-            nowait: false
+            nowait: false,
+            skip_locked: false
         };
 
         let built = For::update();
@@ -110,7 +214,8 @@ mod tests {
         let for_cl = For {
             mode: ForMode::Share,
             tables: vec!["foo", "bar"],
-            nowait: false
+            nowait: false,
+            skip_locked: false
         };
 
         let built = For::share().table(&["foo", "bar"]);
@@ -124,7 +229,8 @@ mod tests {
         let for_cl = For {
             mode: ForMode::Update,
             tables: vec!["foo", "bar"],
-            nowait: true
+            nowait: true,
+            skip_locked: false
         };
 
         let built = For::update().table("foo").table("bar").nowait();
@@ -132,4 +238,42 @@ mod tests {
         assert!(for_cl == built);
         assert_eq!(for_cl.to_sql(), "FOR UPDATE OF foo, bar NOWAIT")
     }
+
+    #[test]
+    fn test_key_modes_and_skip_locked() {
+        let for_cl = For {
+            mode: ForMode::NoKeyUpdate,
+            tables: vec![],
+            nowait: false,
+            skip_locked: true
+        };
+
+        let built = For::no_key_update().nowait().skip_locked();
+
+        assert!(for_cl == built);
+        assert_eq!(for_cl.to_sql(), "FOR NO KEY UPDATE SKIP LOCKED");
+        assert_eq!(For::key_share().table("foo").nowait().to_sql(), "FOR KEY SHARE OF foo NOWAIT");
+    }
+
+    #[test]
+    fn test_dialects() {
+        assert_eq!(For::share().to_sql_for(Dialect::MySQL), "LOCK IN SHARE MODE");
+        assert_eq!(For::key_share().to_sql_for(Dialect::MySQL), "LOCK IN SHARE MODE");
+        assert_eq!(For::share().table("foo").to_sql_for(Dialect::MySQL), "FOR SHARE OF foo");
+        assert_eq!(For::no_key_update().to_sql_for(Dialect::MySQL), "FOR UPDATE");
+        assert_eq!(For::update().to_sql_for(Dialect::SQLite), "");
+    }
+
+    #[test]
+    fn test_multiple() {
+        let for_type = ForType::Empty
+            .push(For::update().table("foo"))
+            .push(For::share().table("bar").skip_locked());
+
+        assert_eq!(for_type.clauses().len(), 2);
+        let expected = "FOR UPDATE OF foo FOR SHARE OF bar SKIP LOCKED".to_string();
+        assert_eq!(for_type.to_sql_for(Dialect::PostgreSQL), Some(expected));
+        assert_eq!(for_type.to_sql_for(Dialect::SQLite), None);
+        assert_eq!(ForType::Empty.to_sql_for(Dialect::PostgreSQL), None);
+    }
 }
@@ -0,0 +1,117 @@
+use common::Pusheable;
+
+/// Element of `GROUP BY` clause.
+///
+/// Sets of `ROLLUP`, `CUBE` and `GROUPING SETS` are lists of columns: a single column is
+/// rendered as it is, several ones are grouped with parentheses and an empty list is
+/// rendered as `()`, which is the grand total.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum GroupingElement<'a> {
+    Simple(&'a str),
+    Rollup(Vec<Vec<&'a str>>),
+    Cube(Vec<Vec<&'a str>>),
+    GroupingSets(Vec<Vec<&'a str>>)
+}
+
+impl<'a> GroupingElement<'a> {
+    /// `ROLLUP` where every column is a separate set.
+    pub fn rollup<T: Pusheable<'a>>(columns: T) -> Self {
+        GroupingElement::Rollup(split(columns))
+    }
+
+    /// `CUBE` where every column is a separate set.
+    pub fn cube<T: Pusheable<'a>>(columns: T) -> Self {
+        GroupingElement::Cube(split(columns))
+    }
+
+    /// Returns every column the element refers to.
+    pub fn columns(&self) -> Vec<&'a str> {
+        match *self {
+            GroupingElement::Simple(column) => vec![column],
+            GroupingElement::Rollup(ref sets) |
+            GroupingElement::Cube(ref sets) |
+            GroupingElement::GroupingSets(ref sets) => sets.iter().flat_map(|x| x.iter().cloned()).collect()
+        }
+    }
+
+    pub fn to_sql(&self) -> String {
+        let sets = |sets: &[Vec<&str>], parenthesize: bool| {
+            sets.iter()
+                .map(|x| if x.len() == 1 && !parenthesize { x[0].to_string() } else { format!("({})", x.join(", ")) })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match *self {
+            GroupingElement::Simple(column) => column.to_string(),
+            GroupingElement::Rollup(ref x) => format!("ROLLUP ({})", sets(x, false)),
+            GroupingElement::Cube(ref x) => format!("CUBE ({})", sets(x, false)),
+            GroupingElement::GroupingSets(ref x) => format!("GROUPING SETS ({})", sets(x, true))
+        }
+    }
+}
+
+fn split<'a, T: Pusheable<'a>>(columns: T) -> Vec<Vec<&'a str>> {
+    let mut rv = vec![];
+    columns.push_to(&mut rv);
+    rv.into_iter().map(|x| vec![x]).collect()
+}
+
+/// Builds `GROUPING(...)` function call, which tells whether columns are aggregated
+/// in a row. The result can be used in `Select::column` and `Select::having`.
+///
+/// # Example
+///
+/// ```
+/// use lithium::{ToSQL, Select};
+/// use lithium::select::grouping;
+///
+/// let query = Select::from("sales")
+///     .columns(&["region", "product"])
+///     .column(grouping(&["region", "product"]))
+///     .rollup(&["region", "product"])
+///     .having(format!("{} < 3", grouping("region")));
+/// let expected = {
+///     "SELECT region, product, GROUPING(region, product) FROM sales \
+///     GROUP BY ROLLUP (region, product) \
+///     HAVING GROUPING(region) < 3".to_string()
+/// };
+/// assert_eq!(query.to_sql(), expected);
+/// ```
+pub fn grouping<'a, T: Pusheable<'a>>(columns: T) -> String {
+    let mut rv = vec![];
+    columns.push_to(&mut rv);
+    format!("GROUPING({})", rv.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GroupingElement, grouping};
+
+    #[test]
+    fn test_simple() {
+        assert_eq!(GroupingElement::Simple("foo").to_sql(), "foo");
+    }
+
+    #[test]
+    fn test_rollup_and_cube() {
+        assert_eq!(GroupingElement::rollup(&["a", "b"]).to_sql(), "ROLLUP (a, b)");
+        assert_eq!(GroupingElement::cube("a").to_sql(), "CUBE (a)");
+
+        let rollup = GroupingElement::Rollup(vec![vec!["a"], vec!["b", "c"]]);
+        assert_eq!(rollup.to_sql(), "ROLLUP (a, (b, c))");
+        assert_eq!(rollup.columns(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_grouping_sets() {
+        let sets = GroupingElement::GroupingSets(vec![vec!["a"], vec!["a", "b"], vec![]]);
+        assert_eq!(sets.to_sql(), "GROUPING SETS ((a), (a, b), ())");
+    }
+
+    #[test]
+    fn test_grouping() {
+        assert_eq!(grouping(&["a", "b"]), "GROUPING(a, b)");
+    }
+}
@@ -1,5 +1,8 @@
+use visitor::{Visitor, Rewrite};
+
 // TODO: add cross join?
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum JoinType {
     Inner,
     Left,
@@ -19,9 +22,11 @@ impl JoinType {
 }
 
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
 pub struct Join<'a> {
     pub join_type: JoinType,
     pub target: &'a str,
+    pub alias: Option<&'a str>,
     pub clause: &'a str
 }
 
@@ -33,6 +38,12 @@ impl<'a> Join<'a> {
         rv.push_str("JOIN");
         rv.push(' ');
         rv.push_str(self.target);
+        if let Some(alias) = self.alias {
+            rv.push(' ');
+            rv.push_str("AS");
+            rv.push(' ');
+            rv.push_str(alias);
+        }
         rv.push(' ');
         rv.push_str("ON");
         rv.push(' ');
@@ -41,6 +52,13 @@ impl<'a> Join<'a> {
     }
 }
 
+impl<'a> Rewrite<'a> for Join<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        visitor.visit_join(self);
+        visitor.visit_table(&mut self.target);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{JoinType, Join};
@@ -63,8 +81,20 @@ mod tests {
         let join = Join {
             join_type: JoinType::Inner,
             target: "target_table",
+            alias: None,
             clause: "2 == 2"
         };
         assert_eq!(join.to_sql(), "INNER JOIN target_table ON 2 == 2");
     }
+
+    #[test]
+    fn test_join_with_alias() {
+        let join = Join {
+            join_type: JoinType::Left,
+            target: "target_table",
+            alias: Some("t"),
+            clause: "t.a = 1"
+        };
+        assert_eq!(join.to_sql(), "LEFT JOIN target_table AS t ON t.a = 1");
+    }
 }
@@ -1,5 +1,7 @@
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
 pub enum LimitType<'a> {
     Empty,
-    Specified(&'a str)
+    Specified(&'a str),
+    Number(u64)
 }
@@ -2,38 +2,52 @@
 
 pub mod select_type;
 pub mod distinct;
+pub mod group_by;
 pub mod join;
 pub mod order_by;
 pub mod limit;
 pub mod offset;
 pub mod for_cl;
 pub mod union;
+pub mod pagination;
+pub mod count;
 
 use common::{ToSQL, AsStr, Pusheable, Subquery};
-use where_cl::{WhereType, IntoWhereType};
+use dialect::Dialect;
+use where_cl::{WhereType, IntoWhereType, conjunction};
+use visitor::{Visitor, Rewrite};
+use analysis::has_aggregate;
 
-pub use self::select_type::SelectType;
+pub use self::select_type::{SelectType, Column, IntoColumn};
 pub use self::join::{Join, JoinType};
-pub use self::order_by::{OrderBy, Ordering};
+pub use self::order_by::{OrderBy, Ordering, Nulls, SortKey, IntoSortKey};
 pub use self::distinct::DistinctType;
+pub use self::group_by::{GroupingElement, grouping};
 pub use self::limit::LimitType;
 pub use self::offset::OffsetType;
 pub use self::for_cl::{For, ForType};
 pub use self::union::{UnionMode, Union};
+pub use self::pagination::{Keyset, Cursor, CursorError, PaginationError};
+pub use self::count::Count;
 
 /// Represents `SELECT` query.
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
 pub struct Select<'a> {
     select_type: SelectType<'a>,
     distinct: DistinctType<'a>,
     from: &'a str,
+    from_alias: Option<&'a str>,
     joins: Vec<Join<'a>>,
-    group_by: Vec<&'a str>,
+    group_by: Vec<GroupingElement<'a>>,
+    group_by_distinct: bool,
     order_by: Vec<OrderBy<'a>>,
+    #[cfg_attr(feature = "serde", serde(rename = "where"))]
     where_cl: Vec<WhereType<'a>>,
     having: Vec<WhereType<'a>>,
     limit: LimitType<'a>,
     offset: OffsetType<'a>,
+    #[cfg_attr(feature = "serde", serde(rename = "for"))]
     for_cl: ForType<'a>
 }
 
@@ -64,8 +78,10 @@ impl<'a> Select<'a> {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: from_table.as_str(),
+            from_alias: from_table.alias(),
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec![],
@@ -75,12 +91,48 @@ impl<'a> Select<'a> {
         }
     }
 
+    /// Starts with an aliased `FROM` target. Can receive either `&str` or `&Subquery`,
+    /// an alias of the subquery is replaced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    ///
+    /// let query = Select::from_as("users", "u").columns("u.id").join_as("orders", "o", "o.user_id = u.id");
+    /// let expected = "SELECT u.id FROM users AS u INNER JOIN orders AS o ON o.user_id = u.id".to_string();
+    /// assert_eq!(query.get_from_alias(), Some("u"));
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn from_as<T: AsStr<'a>>(from_table: T, alias: &'a str) -> Self {
+        Self::from(from_table).from_alias(alias)
+    }
+
+    /// Specifies an alias of `FROM` target, replacing the previous one.
+    pub fn from_alias(mut self, alias: &'a str) -> Self {
+        self.from_alias = Some(alias);
+        self
+    }
+
+    /// Drops an alias of `FROM` target.
+    pub fn remove_from_alias(mut self) -> Self {
+        self.from_alias = None;
+        self
+    }
+
     /// Specifies `SELECT` clause. Will result in `SELECT * ...` (which is a default behaviour).
     pub fn select_all(mut self) -> Self {
         self.select_type = SelectType::All;
         self
     }
 
+    /// Replaces `FROM` target. Can receive either `&str` or `&Subquery`.
+    pub fn replace_from<T: AsStr<'a>>(mut self, from_table: T) -> Self {
+        self.from = from_table.as_str();
+        self.from_alias = from_table.alias();
+        self
+    }
+
     /// This method is used to specify desired `SELECT` columns.
     /// It can receive either `&str` or `&[&str]`
     ///
@@ -100,11 +152,79 @@ impl<'a> Select<'a> {
                 input_columns.push_to(&mut columns);
                 self.select_type = SelectType::Specific(columns);
             },
-            SelectType::Specific(ref mut columns) => input_columns.push_to(columns)
+            SelectType::Specific(ref mut columns) => input_columns.push_to(columns),
+            SelectType::Extended(ref mut columns) => {
+                let mut input = vec![];
+                input_columns.push_to(&mut input);
+                columns.extend(input.into_iter().map(Column::Simple));
+            }
         }
         self
     }
 
+    /// Adds a single column, which can be generated at runtime (`String`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    ///
+    /// let query = Select::from("test_table").columns("foo").column(format!("{} AS bar", "$1"));
+    /// let expected = "SELECT foo, $1 AS bar FROM test_table".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn column<T: IntoColumn<'a>>(mut self, column: T) -> Self {
+        let column = column.into_column();
+        self.select_type = match self.select_type {
+            SelectType::All => SelectType::Extended(vec![column]),
+            SelectType::Specific(columns) => {
+                let mut columns = columns.into_iter().map(Column::Simple).collect::<Vec<_>>();
+                columns.push(column);
+                SelectType::Extended(columns)
+            },
+            SelectType::Extended(mut columns) => {
+                columns.push(column);
+                SelectType::Extended(columns)
+            }
+        };
+        self
+    }
+
+    /// Adds a single column with an alias.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    /// use lithium::function::Function;
+    ///
+    /// let query = Select::from("test_table")
+    ///     .column_as("foo", "f")
+    ///     .column_as(Function::count_all().alias("ignored"), "total");
+    /// let expected = "SELECT foo AS f, COUNT(*) AS total FROM test_table".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn column_as<T: IntoColumn<'a>>(self, column: T, alias: &'a str) -> Self {
+        self.column(column.into_column().alias(alias))
+    }
+
+    /// Replaces previously specified columns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    ///
+    /// let query = Select::from("test_table").columns(&["foo", "bar"]).replace_columns("id");
+    /// assert_eq!(query.to_sql(), "SELECT id FROM test_table".to_string());
+    /// ```
+    pub fn replace_columns<T: Pusheable<'a>>(mut self, input_columns: T) -> Self {
+        let mut columns = vec![];
+        input_columns.push_to(&mut columns);
+        self.select_type = SelectType::Specific(columns);
+        self
+    }
+
     /// Specifies `DISTINCT` clause. Will result in `SELECT DISTINCT ...`
     pub fn distinct(mut self) -> Self {
         self.distinct = DistinctType::Simple;
@@ -140,9 +260,11 @@ impl<'a> Select<'a> {
         self
     }
 
-    fn push_join<T: AsStr<'a>>(mut self, join_type: JoinType, target: T, clause: &'a str) -> Self {
+    fn push_join<T: AsStr<'a>>(mut self, join_type: JoinType, target: T, alias: Option<&'a str>,
+                               clause: &'a str) -> Self {
         self.joins.push(Join {
             join_type: join_type,
+            alias: alias.or_else(|| target.alias()),
             target: target.as_str(),
             clause: clause.as_str(),
         });
@@ -170,19 +292,62 @@ impl<'a> Select<'a> {
     /// assert_eq!(query.to_sql(), expected);
     /// ```
     pub fn join<T: AsStr<'a>>(self, target: T, clause: &'a str) -> Self {
-        self.push_join(JoinType::Inner, target, clause)
+        self.push_join(JoinType::Inner, target, None, clause)
     }
 
     pub fn left_join<T: AsStr<'a>>(self, target: T, clause: &'a str) -> Self {
-        self.push_join(JoinType::Left, target, clause)
+        self.push_join(JoinType::Left, target, None, clause)
     }
 
     pub fn right_join<T: AsStr<'a>>(self, target: T, clause: &'a str) -> Self {
-        self.push_join(JoinType::Right, target, clause)
+        self.push_join(JoinType::Right, target, None, clause)
     }
 
     pub fn outer_join<T: AsStr<'a>>(self, target: T, clause: &'a str) -> Self {
-        self.push_join(JoinType::Outer, target, clause)
+        self.push_join(JoinType::Outer, target, None, clause)
+    }
+
+    /// Specifies `INNER JOIN` with an aliased target. An alias of a subquery is replaced.
+    pub fn join_as<T: AsStr<'a>>(self, target: T, alias: &'a str, clause: &'a str) -> Self {
+        self.push_join(JoinType::Inner, target, Some(alias), clause)
+    }
+
+    pub fn left_join_as<T: AsStr<'a>>(self, target: T, alias: &'a str, clause: &'a str) -> Self {
+        self.push_join(JoinType::Left, target, Some(alias), clause)
+    }
+
+    pub fn right_join_as<T: AsStr<'a>>(self, target: T, alias: &'a str, clause: &'a str) -> Self {
+        self.push_join(JoinType::Right, target, Some(alias), clause)
+    }
+
+    pub fn outer_join_as<T: AsStr<'a>>(self, target: T, alias: &'a str, clause: &'a str) -> Self {
+        self.push_join(JoinType::Outer, target, Some(alias), clause)
+    }
+
+    /// Removes every join with the given target.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    ///
+    /// let query = Select::from("test_table")
+    ///     .join("foo", "foo.a == test_table.a")
+    ///     .left_join("bar", "bar.a == test_table.a")
+    ///     .remove_join("foo");
+    /// let expected = "SELECT * FROM test_table LEFT JOIN bar ON bar.a == test_table.a".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn remove_join<T: AsStr<'a>>(mut self, target: T) -> Self {
+        let target = target.as_str();
+        self.joins.retain(|x| x.target != target);
+        self
+    }
+
+    /// Removes all joins.
+    pub fn clear_joins(mut self) -> Self {
+        self.joins.clear();
+        self
     }
 
     /// Specifies `GROUP BY` clause.
@@ -198,26 +363,157 @@ impl<'a> Select<'a> {
     /// assert_eq!(query.to_sql(), expected);
     /// ```
     pub fn group_by<T: Pusheable<'a>>(mut self, columns: T) -> Self {
-        columns.push_to(&mut self.group_by);
+        let mut input = vec![];
+        columns.push_to(&mut input);
+        self.group_by.extend(input.into_iter().map(GroupingElement::Simple));
+        self
+    }
+
+    /// Adds an element of `GROUP BY` clause, e.g. `ROLLUP` with composite columns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    /// use lithium::select::GroupingElement;
+    ///
+    /// let query = Select::from("test_table")
+    ///     .group_by("foo")
+    ///     .group_by_element(GroupingElement::Rollup(vec![vec!["bar"], vec!["bazz", "fizz"]]));
+    /// let expected = "SELECT * FROM test_table GROUP BY foo, ROLLUP (bar, (bazz, fizz))".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn group_by_element(mut self, element: GroupingElement<'a>) -> Self {
+        self.group_by.push(element);
         self
     }
 
-    /// Specifies `ORDER BY` clause.
+    /// Adds `ROLLUP` of the given columns to `GROUP BY` clause.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    ///
+    /// let query = Select::from("test_table").group_by("foo").rollup(&["bar", "bazz"]);
+    /// let expected = "SELECT * FROM test_table GROUP BY foo, ROLLUP (bar, bazz)".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn rollup<T: Pusheable<'a>>(self, columns: T) -> Self {
+        self.group_by_element(GroupingElement::rollup(columns))
+    }
+
+    /// Adds `CUBE` of the given columns to `GROUP BY` clause.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    ///
+    /// let query = Select::from("test_table").cube(&["foo", "bar"]);
+    /// let expected = "SELECT * FROM test_table GROUP BY CUBE (foo, bar)".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn cube<T: Pusheable<'a>>(self, columns: T) -> Self {
+        self.group_by_element(GroupingElement::cube(columns))
+    }
+
+    /// Adds `GROUPING SETS` to `GROUP BY` clause. An empty set stands for the grand total.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    ///
+    /// let query = Select::from("test_table").grouping_sets(vec![vec!["foo"], vec!["foo", "bar"], vec![]]);
+    /// let expected = "SELECT * FROM test_table GROUP BY GROUPING SETS ((foo), (foo, bar), ())".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn grouping_sets(self, sets: Vec<Vec<&'a str>>) -> Self {
+        self.group_by_element(GroupingElement::GroupingSets(sets))
+    }
+
+    /// Specifies `GROUP BY DISTINCT`, which removes duplicate grouping sets (PostgreSQL).
     ///
     /// # Example
     ///
     /// ```
     /// use lithium::{ToSQL, Select};
+    ///
+    /// let query = Select::from("test_table").rollup(&["foo", "bar"]).cube("foo").group_by_distinct();
+    /// let expected = "SELECT * FROM test_table GROUP BY DISTINCT ROLLUP (foo, bar), CUBE (foo)".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn group_by_distinct(mut self) -> Self {
+        self.group_by_distinct = true;
+        self
+    }
+
+    /// Removes `GROUP BY` clause.
+    pub fn clear_group_by(mut self) -> Self {
+        self.group_by.clear();
+        self.group_by_distinct = false;
+        self
+    }
+
+    /// Specifies `ORDER BY` clause. Can receive a column or an expression (`&str`),
+    /// an expression generated at runtime (`String`) or a column position (`usize`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
     /// use lithium::select::Ordering;
     ///
     /// let query = Select::from("test_table").order_by("foo", Ordering::Ascending);
     /// assert_eq!(query.to_sql(), "SELECT * FROM test_table ORDER BY foo ASC".to_string());
     /// ```
-    pub fn order_by(mut self, field: &'a str, ordering: Ordering) -> Self {
-        self.order_by.push(OrderBy {
-            ordering: ordering,
-            order_by: field
-        });
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    /// use lithium::select::Ordering;
+    ///
+    /// let query = Select::from("test_table")
+    ///     .columns(&["foo", "bar"])
+    ///     .order_by(2usize, Ordering::Descending)
+    ///     .order_by(format!("array_position({}, foo)", "$1"), Ordering::Using("<"));
+    /// let expected = {
+    ///     "SELECT foo, bar FROM test_table \
+    ///     ORDER BY 2 DESC, array_position($1, foo) USING <".to_string()
+    /// };
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn order_by<T: IntoSortKey<'a>>(self, field: T, ordering: Ordering<'a>) -> Self {
+        let mut item = OrderBy::new(field);
+        item.ordering = ordering;
+        self.order_by_item(item)
+    }
+
+    /// Specifies `ORDER BY` item with `NULLS FIRST`/`NULLS LAST` or `COLLATE`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    /// use lithium::select::OrderBy;
+    ///
+    /// let query = Select::from("test_table")
+    ///     .order_by_item(OrderBy::new("foo").desc().nulls_last())
+    ///     .order_by_item(OrderBy::new("bar").collate("\"C\""));
+    /// let expected = {
+    ///     "SELECT * FROM test_table \
+    ///     ORDER BY foo DESC NULLS LAST, bar COLLATE \"C\" ASC".to_string()
+    /// };
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn order_by_item(mut self, item: OrderBy<'a>) -> Self {
+        self.order_by.push(item);
+        self
+    }
+
+    /// Removes `ORDER BY` clause.
+    pub fn clear_order_by(mut self) -> Self {
+        self.order_by.clear();
         self
     }
 
@@ -249,12 +545,96 @@ impl<'a> Select<'a> {
         self
     }
 
+    /// Specifies `WHERE` clause only if `condition` is `true`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    ///
+    /// let only_active = false;
+    /// let query = Select::from("test_table")
+    ///     .filter_if(only_active, "active")
+    ///     .filter_if(true, "foo == bar");
+    /// assert_eq!(query.to_sql(), "SELECT * FROM test_table WHERE foo == bar".to_string());
+    /// ```
+    pub fn filter_if<T: IntoWhereType<'a>>(self, condition: bool, clause: T) -> Self {
+        if condition {
+            self.filter(clause)
+        } else {
+            self
+        }
+    }
+
+    /// Specifies `WHERE` clause only if there is one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    ///
+    /// let name: Option<&str> = Some("bob");
+    /// let age: Option<u32> = None;
+    /// let query = Select::from("users")
+    ///     .filter_opt(name.map(|_| "name = $1"))
+    ///     .filter_opt(age.map(|_| "age = $2"));
+    /// assert_eq!(query.to_sql(), "SELECT * FROM users WHERE name = $1".to_string());
+    /// ```
+    pub fn filter_opt<T: IntoWhereType<'a>>(self, clause: Option<T>) -> Self {
+        match clause {
+            Some(clause) => self.filter(clause),
+            None => self
+        }
+    }
+
+    /// Specifies `WHERE` clause returned by the closure, if any.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select, Where};
+    ///
+    /// let emails = vec!["a@corp", "b@corp"];
+    /// let query = Select::from("users").filter_with(|| {
+    ///     if emails.is_empty() {
+    ///         return None;
+    ///     }
+    ///     Some(emails.iter().fold(Where::with_or(), |acc, _| acc.expr("email = ?")))
+    /// });
+    /// let expected = "SELECT * FROM users WHERE (email = ? OR email = ?)".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn filter_with<T: IntoWhereType<'a>, F: FnOnce() -> Option<T>>(self, f: F) -> Self {
+        self.filter_opt(f())
+    }
+
     /// Specifies `HAVING` clause. Has the same API and usage as `filter`.
     pub fn having<T: IntoWhereType<'a>>(mut self, clause: T) -> Self {
         self.having.push(clause.into_where_type());
         self
     }
 
+    /// Removes `WHERE` clause.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    ///
+    /// let query = Select::from("test_table").filter("foo == bar").clear_filters().filter("a == b");
+    /// assert_eq!(query.to_sql(), "SELECT * FROM test_table WHERE a == b".to_string());
+    /// ```
+    pub fn clear_filters(mut self) -> Self {
+        self.where_cl.clear();
+        self
+    }
+
+    /// Removes `HAVING` clause.
+    pub fn clear_having(mut self) -> Self {
+        self.having.clear();
+        self
+    }
+
     /// Specifies `LIMIT` clause.
     pub fn limit(mut self, value: &'a str) -> Self {
         self.limit = LimitType::Specified(value);
@@ -279,6 +659,87 @@ impl<'a> Select<'a> {
         self
     }
 
+    /// Specifies `LIMIT` and `OFFSET` to fetch `page` (starting from 1) of `per_page` rows.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    /// use lithium::select::Ordering;
+    ///
+    /// let query = Select::from("test_table").order_by("id", Ordering::Ascending).paginate(3, 20);
+    /// let expected = "SELECT * FROM test_table ORDER BY id ASC LIMIT 20 OFFSET 40".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn paginate(mut self, page: u64, per_page: u64) -> Self {
+        self.limit = LimitType::Number(per_page);
+        self.offset = match page.saturating_sub(1).saturating_mul(per_page) {
+            0 => OffsetType::Empty,
+            offset => OffsetType::Number(offset)
+        };
+        self
+    }
+
+    /// Adds keyset (seek) pagination predicate based on `ORDER BY` items to `WHERE` clause.
+    /// Use it together with `limit` instead of `OFFSET` to avoid scanning skipped rows.
+    /// Fails if the ordering can't be used for it, see `Keyset::predicate`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    /// use lithium::select::{Keyset, Ordering, PaginationError};
+    ///
+    /// let query = Select::from("test_table")
+    ///     .order_by("created", Ordering::Descending)
+    ///     .order_by("id", Ordering::Descending)
+    ///     .try_seek(Keyset::new().value("$1").value("$2"))
+    ///     .unwrap()
+    ///     .limit("20");
+    /// let expected = {
+    ///     "SELECT * FROM test_table WHERE (created, id) < ($1, $2) \
+    ///     ORDER BY created DESC, id DESC LIMIT 20".to_string()
+    /// };
+    /// assert_eq!(query.to_sql(), expected);
+    ///
+    /// let error = Select::from("test_table").try_seek(Keyset::new().value("$1"));
+    /// assert_eq!(error.err(), Some(PaginationError::MissingOrderBy));
+    /// ```
+    ///
+    /// Mixed directions and `NULL`s are expanded:
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    /// use lithium::select::{Keyset, Ordering};
+    ///
+    /// let query = Select::from("test_table")
+    ///     .order_by("name", Ordering::Descending)
+    ///     .order_by("id", Ordering::Ascending)
+    ///     .seek(Keyset::new().null().value("$1"));
+    /// let expected = {
+    ///     "SELECT * FROM test_table WHERE (name IS NOT NULL OR (name IS NULL AND id > $1)) \
+    ///     ORDER BY name DESC, id ASC".to_string()
+    /// };
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn try_seek(mut self, keyset: Keyset<'a>) -> Result<Self, PaginationError> {
+        let predicate = keyset.predicate(&self.order_by)?;
+        self.where_cl.push(predicate);
+        Ok(self)
+    }
+
+    /// Same as `try_seek`, for orderings known to be fine.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `try_seek` fails.
+    pub fn seek(self, keyset: Keyset<'a>) -> Self {
+        match self.try_seek(keyset) {
+            Ok(query) => query,
+            Err(error) => panic!("{}", error)
+        }
+    }
+
     /// Specifies `FOR` clause.
     ///
     /// # Example
@@ -296,20 +757,178 @@ impl<'a> Select<'a> {
         self
     }
 
+    /// Adds one more locking clause, e.g. to lock different tables in different modes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    /// use lithium::select::For;
+    ///
+    /// let query = Select::from("jobs")
+    ///     .join("users", "users.id = jobs.user_id")
+    ///     .add_for(For::update().table("jobs").skip_locked())
+    ///     .add_for(For::key_share().table("users"));
+    /// let expected = {
+    ///     "SELECT * FROM jobs INNER JOIN users ON users.id = jobs.user_id \
+    ///     FOR UPDATE OF jobs SKIP LOCKED FOR KEY SHARE OF users".to_string()
+    /// };
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn add_for(mut self, for_cl: For<'a>) -> Self {
+        let for_type = ::std::mem::replace(&mut self.for_cl, ForType::Empty);
+        self.for_cl = for_type.push(for_cl);
+        self
+    }
+
     /// Removes `FOR` clause.
     pub fn remove_for(mut self) -> Self {
         self.for_cl = ForType::Empty;
         self
     }
 
+    /// Derives a query counting rows which this query would return without `LIMIT`/`OFFSET`.
+    /// `ORDER BY`, `LIMIT`, `OFFSET` and `FOR` are dropped. Queries with `GROUP BY`, `HAVING`
+    /// or `DISTINCT` are wrapped into a subquery since they change the number of rows, and so are
+    /// queries with aggregates, which return a single row.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    /// use lithium::select::Ordering;
+    ///
+    /// let query = Select::from("test_table")
+    ///     .join("other", "other.id = test_table.other_id")
+    ///     .filter("foo = $1")
+    ///     .order_by("id", Ordering::Ascending)
+    ///     .paginate(2, 20);
+    /// let expected = {
+    ///     "SELECT COUNT(*) FROM test_table \
+    ///     INNER JOIN other ON other.id = test_table.other_id \
+    ///     WHERE foo = $1".to_string()
+    /// };
+    /// assert_eq!(query.count().to_sql(), expected);
+    /// ```
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select};
+    ///
+    /// let query = Select::from("test_table").columns("foo").distinct().limit("10");
+    /// let expected = {
+    ///     "SELECT COUNT(*) FROM \
+    ///     (SELECT DISTINCT foo FROM test_table) AS counted".to_string()
+    /// };
+    /// assert_eq!(query.count().to_sql(), expected);
+    /// ```
+    pub fn count(&self) -> Count<'a> {
+        let mut query = self.clone();
+        query.order_by = vec![];
+        query.limit = LimitType::Empty;
+        query.offset = OffsetType::Empty;
+        query.for_cl = ForType::Empty;
+
+        let is_grouped = !query.group_by.is_empty() || !query.having.is_empty();
+        let is_aggregated = has_aggregate(&query.select_type.to_sql());
+        if is_grouped || is_aggregated || query.distinct != DistinctType::Empty {
+            Count::Wrapped(query)
+        } else {
+            query.select_type = SelectType::Specific(vec!["COUNT(*)"]);
+            Count::Simple(query)
+        }
+    }
+
     /// Returns an instance of `Subquery` with generated SQL inside.
     pub fn as_subquery(self) -> Subquery<'a> {
         Subquery::new(self.to_sql())
     }
 }
 
+/// Read access to clauses, e.g. for layers which inspect a query before running it.
+impl<'a> Select<'a> {
+    /// Returns `SELECT` columns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Select;
+    /// use lithium::select::SelectType;
+    ///
+    /// let query = Select::from("test_table").columns(&["foo", "bar"]);
+    /// assert!(*query.get_select_type() == SelectType::Specific(vec!["foo", "bar"]));
+    /// assert_eq!(query.get_from(), "test_table");
+    /// ```
+    pub fn get_select_type(&self) -> &SelectType<'a> {
+        &self.select_type
+    }
+
+    /// Returns `DISTINCT` clause.
+    pub fn get_distinct(&self) -> &DistinctType<'a> {
+        &self.distinct
+    }
+
+    /// Returns `FROM` target.
+    pub fn get_from(&self) -> &'a str {
+        self.from
+    }
+
+    /// Returns an alias of `FROM` target.
+    pub fn get_from_alias(&self) -> Option<&'a str> {
+        self.from_alias
+    }
+
+    /// Returns joins in the order they were specified.
+    pub fn get_joins(&self) -> &[Join<'a>] {
+        &self.joins
+    }
+
+    /// Returns `GROUP BY` elements.
+    pub fn get_group_by(&self) -> &[GroupingElement<'a>] {
+        &self.group_by
+    }
+
+    /// Checks whether `GROUP BY DISTINCT` is specified.
+    pub fn is_group_by_distinct(&self) -> bool {
+        self.group_by_distinct
+    }
+
+    /// Returns `ORDER BY` items.
+    pub fn get_order_by(&self) -> &[OrderBy<'a>] {
+        &self.order_by
+    }
+
+    /// Returns `WHERE` clauses. They are joined with `AND`.
+    pub fn get_where(&self) -> &[WhereType<'a>] {
+        &self.where_cl
+    }
+
+    /// Returns `HAVING` clauses. They are joined with `AND`.
+    pub fn get_having(&self) -> &[WhereType<'a>] {
+        &self.having
+    }
+
+    /// Returns `LIMIT` clause.
+    pub fn get_limit(&self) -> &LimitType<'a> {
+        &self.limit
+    }
+
+    /// Returns `OFFSET` clause.
+    pub fn get_offset(&self) -> &OffsetType<'a> {
+        &self.offset
+    }
+
+    /// Returns `FOR` clause.
+    pub fn get_for(&self) -> &ForType<'a> {
+        &self.for_cl
+    }
+}
+
 impl<'a> ToSQL for Select<'a> {
     fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
         let mut rv = String::new();
         rv.push_str("SELECT");
 
@@ -335,37 +954,45 @@ impl<'a> ToSQL for Select<'a> {
         rv.push_str("FROM");
         rv.push(' ');
         rv.push_str(self.from);
-        
+        if let Some(alias) = self.from_alias {
+            rv.push(' ');
+            rv.push_str("AS");
+            rv.push(' ');
+            rv.push_str(alias);
+        }
+
         for join in &self.joins {
             rv.push(' ');
             rv.push_str(&join.to_sql());
         }
 
-        if !self.where_cl.is_empty() {
+        if let Some(clause) = conjunction(&self.where_cl) {
            rv.push(' ');
            rv.push_str("WHERE");
            rv.push(' ');
-           rv.push_str(&self.where_cl.iter()
-                       .map(|x| x.to_sql())
-                       .collect::<Vec<_>>()
-                       .join(" AND "));
+           rv.push_str(&clause);
         }
 
         if !self.group_by.is_empty() {
             rv.push(' ');
             rv.push_str("GROUP BY");
             rv.push(' ');
-            rv.push_str(&self.group_by.join(", "));
+            if self.group_by_distinct {
+                rv.push_str("DISTINCT");
+                rv.push(' ');
+            }
+            rv.push_str(&self.group_by
+                        .iter()
+                        .map(|x| x.to_sql())
+                        .collect::<Vec<String>>()
+                        .join(", "));
         }
 
-        if !self.having.is_empty() {
+        if let Some(clause) = conjunction(&self.having) {
            rv.push(' ');
            rv.push_str("HAVING");
            rv.push(' ');
-           rv.push_str(&self.having.iter()
-                       .map(|x| x.to_sql())
-                       .collect::<Vec<_>>()
-                       .join(" AND "));
+           rv.push_str(&clause);
         }
         
         if !self.order_by.is_empty() {
@@ -386,6 +1013,12 @@ impl<'a> ToSQL for Select<'a> {
                 rv.push_str("LIMIT");
                 rv.push(' ');
                 rv.push_str(clause);
+            },
+            LimitType::Number(value) => {
+                rv.push(' ');
+                rv.push_str("LIMIT");
+                rv.push(' ');
+                rv.push_str(&value.to_string());
             }
         }
 
@@ -396,25 +1029,66 @@ impl<'a> ToSQL for Select<'a> {
                 rv.push_str("OFFSET");
                 rv.push(' ');
                 rv.push_str(clause);
+            },
+            OffsetType::Number(value) => {
+                rv.push(' ');
+                rv.push_str("OFFSET");
+                rv.push(' ');
+                rv.push_str(&value.to_string());
             }
         }
 
-        match self.for_cl {
-            ForType::Empty => {},
-            ForType::Specified(ref for_clause) => {
-                rv.push(' ');
-                rv.push_str(&for_clause.to_sql())
-            }
+        if let Some(clause) = self.for_cl.to_sql_for(dialect) {
+            rv.push(' ');
+            rv.push_str(&clause);
         }
 
         rv
     }
 }
 
+impl<'a> Rewrite<'a> for Select<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        visitor.visit_select(self);
+        visitor.visit_table(&mut self.from);
+
+        for join in &mut self.joins {
+            join.walk(visitor);
+        }
+
+        if let SelectType::Extended(ref mut columns) = self.select_type {
+            for column in columns {
+                column.walk(visitor);
+            }
+        }
+
+        for clause in self.where_cl.iter_mut().chain(self.having.iter_mut()) {
+            clause.walk(visitor);
+        }
+
+        for item in &mut self.order_by {
+            item.walk(visitor);
+        }
+
+        let clauses = match self.for_cl {
+            ForType::Empty => &mut [][..],
+            ForType::Specified(ref mut clause) => ::std::slice::from_mut(clause),
+            ForType::Multiple(ref mut clauses) => &mut clauses[..]
+        };
+        for table in clauses.iter_mut().flat_map(|x| x.tables.iter_mut()) {
+            visitor.visit_table(table);
+        }
+    }
+}
+
 impl<'a> ToSQL for &'a Select<'a> {
     fn to_sql(&self) -> String {
         (**self).to_sql()
     }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
+        (**self).to_sql_for(dialect)
+    }
 }
 
 #[cfg(test)]
@@ -424,16 +1098,19 @@ mod tests {
     use self::test::Bencher;
 
     use common::{ToSQL};
+    use dialect::Dialect;
     use where_cl::{Where, IntoWhereType};
 
     use super::Select;
     use super::select_type::SelectType;
     use super::join::{JoinType, Join};
-    use super::order_by::{Ordering, OrderBy};
+    use super::order_by::{Ordering, OrderBy, Nulls, SortKey};
     use super::distinct::DistinctType;
+    use super::group_by::{GroupingElement, grouping};
     use super::limit::LimitType;
     use super::offset::OffsetType;
     use super::for_cl::{ForMode, For, ForType};
+    use super::pagination::{Keyset, PaginationError};
 
     #[test]
     fn select_all() {
@@ -441,8 +1118,10 @@ mod tests {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec![],
@@ -463,8 +1142,10 @@ mod tests {
             select_type: SelectType::Specific(vec!["foo", "bar"]),
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec![],
@@ -485,6 +1166,7 @@ mod tests {
         let join = Join {
             join_type: JoinType::Inner,
             target: "target_table",
+            alias: None,
             clause: "2 == 2"
         };
 
@@ -492,8 +1174,10 @@ mod tests {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![join],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec![],
@@ -519,12 +1203,14 @@ mod tests {
         let bar_join = Join {
             join_type: JoinType::Inner,
             target: "bar_table",
+            alias: None,
             clause: "1 == 1"
         };
 
         let bazz_join = Join {
             join_type: JoinType::Left,
             target: "bazz_table",
+            alias: None,
             clause: "2 == 2"
         };
 
@@ -532,8 +1218,10 @@ mod tests {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![bar_join, bazz_join],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec![],
@@ -563,8 +1251,10 @@ mod tests {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
-            group_by: vec!["foo"],
+            group_by: vec![GroupingElement::Simple("foo")],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec![],
@@ -591,8 +1281,10 @@ mod tests {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
-            group_by: vec!["foo", "bar"],
+            group_by: vec![GroupingElement::Simple("foo"), GroupingElement::Simple("bar")],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec![],
@@ -617,15 +1309,19 @@ mod tests {
     fn select_all_and_order_by() {
         let order_by_foo_asc = OrderBy {
             ordering: Ordering::Ascending,
-            order_by: "foo"
+            order_by: SortKey::Simple("foo"),
+            nulls: Nulls::Default,
+            collation: None
         };
 
         let query = Select {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![order_by_foo_asc],
             where_cl: vec![],
             having: vec![],
@@ -650,20 +1346,26 @@ mod tests {
     fn select_all_and_multi_order_by() {
         let order_by_foo_asc = OrderBy {
             ordering: Ordering::Ascending,
-            order_by: "foo"
+            order_by: SortKey::Simple("foo"),
+            nulls: Nulls::Default,
+            collation: None
         };
 
         let order_by_bar_desc = OrderBy {
             ordering: Ordering::Descending,
-            order_by: "bar"
+            order_by: SortKey::Simple("bar"),
+            nulls: Nulls::Default,
+            collation: None
         };
 
         let query = Select {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![order_by_foo_asc, order_by_bar_desc],
             where_cl: vec![],
             having: vec![],
@@ -692,8 +1394,10 @@ mod tests {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec!["foo == bar".into_where_type()],
             having: vec![],
@@ -720,8 +1424,10 @@ mod tests {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec!["foo == bar".into_where_type(), "lala == blah".into_where_type()],
             having: vec![],
@@ -748,8 +1454,10 @@ mod tests {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec!["foo == bar".into_where_type()],
@@ -776,8 +1484,10 @@ mod tests {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec!["foo == bar".into_where_type(), "lala == blah".into_where_type()],
@@ -804,8 +1514,10 @@ mod tests {
             select_type: SelectType::All,
             distinct: DistinctType::Simple,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec![],
@@ -831,8 +1543,10 @@ mod tests {
             select_type: SelectType::All,
             distinct: DistinctType::Extended(vec!["foo", "bar"]),
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec![],
@@ -857,15 +1571,18 @@ mod tests {
         let for_foo = For {
             mode: ForMode::Update,
             tables: vec![],
-            nowait: false
+            nowait: false,
+            skip_locked: false
         };
 
         let query = Select {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec![],
@@ -891,15 +1608,18 @@ mod tests {
         let for_foo = For {
             mode: ForMode::Update,
             tables: vec!["foo", "bar"],
-            nowait: false
+            nowait: false,
+            skip_locked: false
         };
 
         let query = Select {
             select_type: SelectType::All,
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![],
             group_by: vec![],
+            group_by_distinct: false,
             order_by: vec![],
             where_cl: vec![],
             having: vec![],
@@ -925,28 +1645,35 @@ mod tests {
         let for_bazz = For {
             mode: ForMode::Update,
             tables: vec!["foo", "bar"],
-            nowait: true
+            nowait: true,
+            skip_locked: false
         };
 
         let order_by_bar_desc = OrderBy {
             ordering: Ordering::Descending,
-            order_by: "bar"
+            order_by: SortKey::Simple("bar"),
+            nulls: Nulls::Default,
+            collation: None
         };
 
         let order_by_foo_asc = OrderBy {
             ordering: Ordering::Ascending,
-            order_by: "foo"
+            order_by: SortKey::Simple("foo"),
+            nulls: Nulls::Default,
+            collation: None
         };
 
         let bar_join = Join {
             join_type: JoinType::Inner,
             target: "bar_table",
+            alias: None,
             clause: "1 == 1"
         };
 
         let bazz_join = Join {
             join_type: JoinType::Left,
             target: "bazz_table",
+            alias: None,
             clause: "2 == 2"
         };
 
@@ -954,8 +1681,10 @@ mod tests {
             select_type: SelectType::Specific(vec!["foo", "bar"]),
             distinct: DistinctType::Extended(vec!["fizz", "bazz"]),
             from: "test_table",
+            from_alias: None,
             joins: vec![bar_join, bazz_join],
-            group_by: vec!["foo", "bar"],
+            group_by: vec![GroupingElement::Simple("foo"), GroupingElement::Simple("bar")],
+            group_by_distinct: false,
             order_by: vec![order_by_bar_desc, order_by_foo_asc],
             where_cl: vec!["foo == bar".into_where_type(), "lala == blah".into_where_type()],
             having: vec!["foo == bar".into_where_type(), "lala == blah".into_where_type()],
@@ -1027,29 +1756,301 @@ mod tests {
         assert_eq!(another.to_sql(), test_sql_string);
     }
 
+    #[test]
+    fn test_aliases() {
+        let subquery = Select::from("foo_table").columns("a").as_subquery().with_alias("foo");
+        let query = Select::from_as("test_table", "t")
+            .column_as("t.a", "x")
+            .column("t.b")
+            .join(&subquery, "foo.a = t.a")
+            .left_join_as(&subquery, "bar", "bar.a = t.b");
+        let test_sql_string = {
+            "SELECT t.a AS x, t.b FROM test_table AS t \
+            INNER JOIN (SELECT a FROM foo_table) AS foo ON foo.a = t.a \
+            LEFT JOIN (SELECT a FROM foo_table) AS bar ON bar.a = t.b".to_string()
+        };
+        assert_eq!(query.to_sql(), test_sql_string);
+        assert_eq!(query.to_sql_for(Dialect::MySQL), test_sql_string);
+
+        assert_eq!(query.get_from_alias(), Some("t"));
+        assert_eq!(query.get_joins()[0].alias, Some("foo"));
+        assert_eq!(query.get_joins()[1].target, "(SELECT a FROM foo_table)");
+        match *query.get_select_type() {
+            SelectType::Extended(ref columns) => {
+                assert_eq!(columns[0].get_alias(), Some("x"));
+                assert_eq!(columns[1].get_alias(), None);
+            },
+            _ => panic!("columns are expected")
+        }
+
+        let query = query.from_alias("u").replace_from("other_table");
+        assert_eq!(query.get_from_alias(), None);
+        let query = query.replace_from(&subquery).clear_joins().replace_columns("*");
+        assert_eq!(query.to_sql(), "SELECT * FROM (SELECT a FROM foo_table) AS foo");
+        assert_eq!(query.remove_from_alias().to_sql(), "SELECT * FROM (SELECT a FROM foo_table)");
+    }
+
+    #[test]
+    fn test_paginate() {
+        let query = Select::from("test_table").paginate(1, 10);
+        assert!(query.limit == LimitType::Number(10));
+        assert!(query.offset == OffsetType::Empty);
+        assert_eq!(query.to_sql(), "SELECT * FROM test_table LIMIT 10".to_string());
+
+        let query = query.paginate(4, 25);
+        assert_eq!(query.to_sql(), "SELECT * FROM test_table LIMIT 25 OFFSET 75".to_string());
+
+        let query = query.paginate(0, 25);
+        assert_eq!(query.to_sql(), "SELECT * FROM test_table LIMIT 25".to_string());
+    }
+
+    #[test]
+    fn test_seek() {
+        let query = Select::from("test_table")
+            .filter("foo == bar")
+            .order_by("id", Ordering::Ascending)
+            .seek(Keyset::new().value("$1"))
+            .limit("10");
+
+        let test_sql_string = {
+            "SELECT * \
+            FROM test_table \
+            WHERE foo == bar AND id > $1 \
+            ORDER BY id ASC \
+            LIMIT 10".to_string()
+        };
+        assert_eq!(query.to_sql(), test_sql_string);
+
+        let query = Select::from("test_table").order_by("id", Ordering::Ascending);
+        let error = query.try_seek(Keyset::new().value("$1").value("$2")).err();
+        assert_eq!(error, Some(PaginationError::ValuesMismatch { values: 2, order_by: 1 }));
+    }
+
+    #[test]
+    fn test_count() {
+        let query = Select::from("test_table")
+            .columns(&["foo", "bar"])
+            .left_join("bazz_table", "2 == 2")
+            .filter("foo == bar")
+            .order_by("foo", Ordering::Ascending)
+            .limit("10")
+            .offset("5")
+            .for_(For::update());
+
+        let test_sql_string = {
+            "SELECT COUNT(*) \
+            FROM test_table \
+            LEFT JOIN bazz_table ON 2 == 2 \
+            WHERE foo == bar".to_string()
+        };
+        assert_eq!(query.count().to_sql(), test_sql_string);
+    }
+
+    #[test]
+    fn test_count_grouped() {
+        let query = Select::from("test_table")
+            .columns(&["foo", "COUNT(*)"])
+            .group_by("foo")
+            .having("COUNT(*) > 1")
+            .order_by("foo", Ordering::Ascending);
+
+        let test_sql_string = {
+            "SELECT COUNT(*) FROM \
+            (SELECT foo, COUNT(*) \
+            FROM test_table \
+            GROUP BY foo \
+            HAVING COUNT(*) > 1) AS counted".to_string()
+        };
+        assert_eq!(query.count().to_sql(), test_sql_string);
+    }
+
+    #[test]
+    fn test_count_aggregated() {
+        let query = Select::from("test_table").columns("max(x)").filter("y > 1");
+        let expected = "SELECT COUNT(*) FROM (SELECT max(x) FROM test_table WHERE y > 1) AS counted";
+        assert_eq!(query.count().to_sql(), expected);
+    }
+
+    #[test]
+    fn test_conditional_filters() {
+        let query = Select::from("test_table")
+            .filter_if(false, "a == b")
+            .filter_opt(None::<&str>)
+            .filter_with(|| None::<&str>)
+            .filter(Where::with_and());
+        assert_eq!(query.to_sql(), "SELECT * FROM test_table".to_string());
+
+        let query = query
+            .filter_if(true, "a == b")
+            .filter_opt(Some("c == d"))
+            .filter_with(|| Some(Where::with_or()))
+            .having(Where::with_and().expr(Where::with_and()));
+        assert_eq!(query.to_sql(), "SELECT * FROM test_table WHERE a == b AND c == d AND FALSE".to_string());
+    }
+
+    #[test]
+    fn test_getters() {
+        let query = Select::from("test_table")
+            .columns("foo")
+            .distinct()
+            .join("bar_table", "1 == 1")
+            .group_by("foo")
+            .filter("foo == bar")
+            .having("lala == blah")
+            .order_by("foo", Ordering::Ascending)
+            .limit("10")
+            .offset("5")
+            .for_(For::update());
+
+        assert!(*query.get_select_type() == SelectType::Specific(vec!["foo"]));
+        assert!(*query.get_distinct() == DistinctType::Simple);
+        assert_eq!(query.get_from(), "test_table");
+        assert_eq!(query.get_joins().len(), 1);
+        assert_eq!(query.get_joins()[0].target, "bar_table");
+        assert!(query.get_group_by() == [GroupingElement::Simple("foo")]);
+        assert!(query.get_order_by()[0].order_by == SortKey::Simple("foo"));
+        assert!(query.get_where() == ["foo == bar".into_where_type()]);
+        assert!(query.get_having() == ["lala == blah".into_where_type()]);
+        assert!(*query.get_limit() == LimitType::Specified("10"));
+        assert!(*query.get_offset() == OffsetType::Specified("5"));
+        assert!(*query.get_for() == ForType::Specified(For::update()));
+    }
+
+    #[test]
+    fn test_mutators() {
+        let query = Select::from("test_table")
+            .columns(&["foo", "bar"])
+            .join("bar_table", "1 == 1")
+            .left_join("bazz_table", "2 == 2")
+            .join("bar_table", "3 == 3")
+            .group_by("foo")
+            .filter("foo == bar")
+            .having("lala == blah")
+            .order_by("foo", Ordering::Ascending)
+            .replace_from("another_table")
+            .replace_columns("bar")
+            .remove_join("bar_table")
+            .clear_group_by()
+            .clear_filters()
+            .clear_having()
+            .clear_order_by();
+
+        let test_sql_string = {
+            "SELECT bar \
+            FROM another_table \
+            LEFT JOIN bazz_table ON 2 == 2".to_string()
+        };
+        assert_eq!(query.to_sql(), test_sql_string);
+        assert_eq!(query.clear_joins().to_sql(), "SELECT bar FROM another_table".to_string());
+    }
+
+    #[test]
+    fn test_grouping() {
+        let query = Select::from("sales")
+            .columns("region")
+            .column(grouping(&["region", "year"]))
+            .columns(&["year", "SUM(amount)"])
+            .group_by("country")
+            .grouping_sets(vec![vec!["region"], vec!["region", "year"]])
+            .cube("year")
+            .group_by_distinct();
+
+        let test_sql_string = {
+            "SELECT region, GROUPING(region, year), year, SUM(amount) \
+            FROM sales \
+            GROUP BY DISTINCT country, GROUPING SETS ((region), (region, year)), CUBE (year)".to_string()
+        };
+        assert_eq!(query.to_sql(), test_sql_string);
+        assert!(query.is_group_by_distinct());
+        assert_eq!(query.get_group_by().len(), 3);
+
+        let query = query.clear_group_by();
+        assert!(!query.is_group_by_distinct());
+        assert_eq!(query.to_sql(), "SELECT region, GROUPING(region, year), year, SUM(amount) FROM sales".to_string());
+    }
+
+    #[test]
+    fn test_locking_dialects() {
+        let query = Select::from("jobs")
+            .filter("status = 'new'")
+            .limit("1")
+            .for_(For::update().skip_locked());
+
+        let expected = "SELECT * FROM jobs WHERE status = 'new' LIMIT 1 FOR UPDATE SKIP LOCKED";
+        assert_eq!(query.to_sql_for(Dialect::MySQL), expected.to_string());
+        assert_eq!(query.to_sql_for(Dialect::SQLite), "SELECT * FROM jobs WHERE status = 'new' LIMIT 1".to_string());
+
+        let query = query.for_(For::share()).add_for(For::key_share().table("jobs"));
+        let expected = "SELECT * FROM jobs WHERE status = 'new' LIMIT 1 FOR SHARE FOR KEY SHARE OF jobs";
+        assert_eq!(query.to_sql(), expected.to_string());
+        assert!(query.get_for().clauses() == [For::share(), For::key_share().table("jobs")]);
+        let expected = "SELECT * FROM jobs WHERE status = 'new' LIMIT 1 LOCK IN SHARE MODE FOR SHARE OF jobs";
+        assert_eq!(query.to_sql_for(Dialect::MySQL), expected.to_string());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let query = Select::from("test_table")
+            .columns(&["foo", "bar"])
+            .distinct_on("foo")
+            .join("bar_table", "1 == 1")
+            .group_by("foo")
+            .filter(Where::with_or().expr("foo == bar").expr(Where::in_query("a", Select::from("bazz").columns("a"))))
+            .having("lala == blah")
+            .order_by("foo", Ordering::Descending)
+            .paginate(3, 10)
+            .for_(For::share().nowait());
+
+        let json = ::serde_json::to_string(&query).unwrap();
+        let restored: Select = ::serde_json::from_str(&json).unwrap();
+        assert!(restored == query);
+
+        let restored = restored.filter("c == 1").limit("5");
+        let test_sql_string = {
+            "SELECT DISTINCT ON (foo) foo, bar \
+            FROM test_table \
+            INNER JOIN bar_table ON 1 == 1 \
+            WHERE (foo == bar OR a IN (SELECT a FROM bazz)) AND c == 1 \
+            GROUP BY foo \
+            HAVING lala == blah \
+            ORDER BY foo DESC \
+            LIMIT 5 \
+            OFFSET 20 \
+            FOR SHARE NOWAIT".to_string()
+        };
+        assert_eq!(restored.to_sql(), test_sql_string);
+    }
+
     #[bench]
     fn bench_query_with_extended_where(b: &mut Bencher) {
         let where_cl = Where::with_and().expr("foo == bar").expr("lala == blah");
 
         let order_by_bar_desc = OrderBy {
             ordering: Ordering::Descending,
-            order_by: "bar"
+            order_by: SortKey::Simple("bar"),
+            nulls: Nulls::Default,
+            collation: None
         };
 
         let order_by_foo_asc = OrderBy {
             ordering: Ordering::Ascending,
-            order_by: "foo"
+            order_by: SortKey::Simple("foo"),
+            nulls: Nulls::Default,
+            collation: None
         };
 
         let bar_join = Join {
             join_type: JoinType::Inner,
             target: "bar_table",
+            alias: None,
             clause: "1 == 1"
         };
 
         let bazz_join = Join {
             join_type: JoinType::Left,
             target: "bazz_table",
+            alias: None,
             clause: "2 == 2"
         };
 
@@ -1057,8 +2058,10 @@ mod tests {
             select_type: SelectType::Specific(vec!["foo", "bar"]),
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![bar_join, bazz_join],
-            group_by: vec!["foo", "bar"],
+            group_by: vec![GroupingElement::Simple("foo"), GroupingElement::Simple("bar")],
+            group_by_distinct: false,
             order_by: vec![order_by_bar_desc, order_by_foo_asc],
             where_cl: vec![where_cl.into_where_type()],
             having: vec![],
@@ -1074,23 +2077,29 @@ mod tests {
     fn bench_query_with_empty_where(b: &mut Bencher) {
         let order_by_bar_desc = OrderBy {
             ordering: Ordering::Descending,
-            order_by: "bar"
+            order_by: SortKey::Simple("bar"),
+            nulls: Nulls::Default,
+            collation: None
         };
 
         let order_by_foo_asc = OrderBy {
             ordering: Ordering::Ascending,
-            order_by: "foo"
+            order_by: SortKey::Simple("foo"),
+            nulls: Nulls::Default,
+            collation: None
         };
 
         let bar_join = Join {
             join_type: JoinType::Inner,
             target: "bar_table",
+            alias: None,
             clause: "1 == 1"
         };
 
         let bazz_join = Join {
             join_type: JoinType::Left,
             target: "bazz_table",
+            alias: None,
             clause: "2 == 2"
         };
 
@@ -1098,8 +2107,10 @@ mod tests {
             select_type: SelectType::Specific(vec!["foo", "bar"]),
             distinct: DistinctType::Empty,
             from: "test_table",
+            from_alias: None,
             joins: vec![bar_join, bazz_join],
-            group_by: vec!["foo", "bar"],
+            group_by: vec![GroupingElement::Simple("foo"), GroupingElement::Simple("bar")],
+            group_by_distinct: false,
             order_by: vec![order_by_bar_desc, order_by_foo_asc],
             where_cl: vec![],
             having: vec![],
@@ -1,38 +1,219 @@
+use common::ToSQL;
+use function::Function;
+use case::Case;
+use visitor::{Visitor, Rewrite};
+
 #[allow(dead_code)]
 #[derive(Clone, PartialEq, Eq)]
-pub enum Ordering {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Ordering<'a> {
     Ascending,
     Descending,
+    /// `USING {operator}` (PostgreSQL), e.g. `USING >`.
+    Using(&'a str)
 }
 
-impl Ordering {
+impl<'a> Ordering<'a> {
+    pub fn to_sql(&self) -> String {
+        match *self {
+            Ordering::Ascending => "ASC".to_string(),
+            Ordering::Descending => "DESC".to_string(),
+            Ordering::Using(operator) => format!("USING {}", operator)
+        }
+    }
+}
+
+/// Position of `NULL`s in the ordering.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Nulls {
+    /// Position is chosen by the database.
+    Default,
+    First,
+    Last
+}
+
+impl Nulls {
     pub fn to_sql(&self) -> &str {
         match *self {
-            Ordering::Ascending => "ASC",
-            Ordering::Descending => "DESC"
+            Nulls::Default => "",
+            Nulls::First => "NULLS FIRST",
+            Nulls::Last => "NULLS LAST"
+        }
+    }
+}
+
+/// Expression rows are sorted by.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum SortKey<'a> {
+    /// Column or expression.
+    Simple(&'a str),
+    /// Position of a column in the select list, starting with 1.
+    Position(usize),
+    /// Expression generated at runtime, e.g. with placeholders of bound parameters.
+    Owned(String),
+    /// Function call, which is kept as is so a `Visitor` can walk its `FILTER` clause.
+    Function(Box<Function<'a>>),
+    /// `CASE` expression, which is kept as is so a `Visitor` can walk its conditions.
+    Case(Box<Case<'a>>)
+}
+
+impl<'a> SortKey<'a> {
+    pub fn to_sql(&self) -> String {
+        match *self {
+            SortKey::Simple(key) => key.to_string(),
+            SortKey::Position(position) => position.to_string(),
+            SortKey::Owned(ref key) => key.clone(),
+            SortKey::Function(ref function) => function.to_sql(),
+            SortKey::Case(ref case) => case.to_sql()
         }
     }
 }
 
+/// Is used to receive either a column (`&str`), an expression (`String`) or a position
+/// (`usize`) for `ORDER BY`.
+pub trait IntoSortKey<'a> {
+    fn into_sort_key(self) -> SortKey<'a>;
+}
+
+impl<'a> IntoSortKey<'a> for SortKey<'a> {
+    fn into_sort_key(self) -> SortKey<'a> {
+        self
+    }
+}
+
+impl<'a> IntoSortKey<'a> for &'a str {
+    fn into_sort_key(self) -> SortKey<'a> {
+        SortKey::Simple(self)
+    }
+}
+
+impl<'a> IntoSortKey<'a> for String {
+    fn into_sort_key(self) -> SortKey<'a> {
+        SortKey::Owned(self)
+    }
+}
+
+impl<'a> IntoSortKey<'a> for usize {
+    fn into_sort_key(self) -> SortKey<'a> {
+        SortKey::Position(self)
+    }
+}
+
+/// Item of `ORDER BY` clause.
+///
+/// # Example
+///
+/// ```
+/// use lithium::select::OrderBy;
+///
+/// let item = OrderBy::new("name").collate("\"C\"").desc().nulls_last();
+/// assert_eq!(item.to_sql(), "name COLLATE \"C\" DESC NULLS LAST");
+///
+/// let item = OrderBy::new(2).using("~<~");
+/// assert_eq!(item.to_sql(), "2 USING ~<~");
+///
+/// let item = OrderBy::new(format!("array_position({}, id)", "$1"));
+/// assert_eq!(item.to_sql(), "array_position($1, id) ASC");
+/// ```
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
 pub struct OrderBy<'a> {
-    pub ordering: Ordering,
-    pub order_by: &'a str
+    pub ordering: Ordering<'a>,
+    pub order_by: SortKey<'a>,
+    pub nulls: Nulls,
+    pub collation: Option<&'a str>
 }
 
 impl<'a> OrderBy<'a> {
+    /// Method to start with. Sorts in ascending order.
+    pub fn new<T: IntoSortKey<'a>>(key: T) -> Self {
+        OrderBy {
+            ordering: Ordering::Ascending,
+            order_by: key.into_sort_key(),
+            nulls: Nulls::Default,
+            collation: None
+        }
+    }
+
+    pub fn asc(mut self) -> Self {
+        self.ordering = Ordering::Ascending;
+        self
+    }
+
+    pub fn desc(mut self) -> Self {
+        self.ordering = Ordering::Descending;
+        self
+    }
+
+    /// Specifies `USING {operator}` instead of `ASC`/`DESC` (PostgreSQL).
+    pub fn using(mut self, operator: &'a str) -> Self {
+        self.ordering = Ordering::Using(operator);
+        self
+    }
+
+    pub fn nulls_first(mut self) -> Self {
+        self.nulls = Nulls::First;
+        self
+    }
+
+    pub fn nulls_last(mut self) -> Self {
+        self.nulls = Nulls::Last;
+        self
+    }
+
+    /// Specifies `COLLATE {collation}`. The collation is put into SQL as it is,
+    /// so quote it if needed.
+    pub fn collate(mut self, collation: &'a str) -> Self {
+        self.collation = Some(collation);
+        self
+    }
+
+    /// Returns the key along with `COLLATE`, which is the expression actually compared.
+    pub fn expression(&self) -> String {
+        match self.collation {
+            Some(collation) => format!("{} COLLATE {}", self.order_by.to_sql(), collation),
+            None => self.order_by.to_sql()
+        }
+    }
+
+    /// Checks whether `NULL`s go after other values, taking the database default
+    /// (`NULLS LAST` for `ASC`, `NULLS FIRST` for `DESC`) into account.
+    pub fn nulls_go_last(&self) -> bool {
+        match self.nulls {
+            Nulls::First => false,
+            Nulls::Last => true,
+            Nulls::Default => self.ordering != Ordering::Descending
+        }
+    }
+
     pub fn to_sql(&self) -> String {
         let mut rv = String::new();
-        rv.push_str(self.order_by);
+        rv.push_str(&self.expression());
         rv.push(' ');
-        rv.push_str(self.ordering.to_sql());
+        rv.push_str(&self.ordering.to_sql());
+        if self.nulls != Nulls::Default {
+            rv.push(' ');
+            rv.push_str(self.nulls.to_sql());
+        }
         rv
     }
 }
 
+impl<'a> Rewrite<'a> for OrderBy<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        match self.order_by {
+            SortKey::Function(ref mut function) => function.walk(visitor),
+            SortKey::Case(ref mut case) => case.walk(visitor),
+            SortKey::Simple(_) | SortKey::Position(_) | SortKey::Owned(_) => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{OrderBy, Ordering};
+    use super::{OrderBy, Ordering, Nulls, SortKey};
 
     #[test]
     fn test_ordering() {
@@ -41,14 +222,39 @@ mod tests {
 
         assert_eq!(ascending.to_sql(), "ASC");
         assert_eq!(descending.to_sql(), "DESC");
+        assert_eq!(Ordering::Using(">").to_sql(), "USING >");
     }
 
     #[test]
     fn test_order_by() {
         let order_by = OrderBy {
             ordering: Ordering::Ascending,
-            order_by: "fizz"
+            order_by: SortKey::Simple("fizz"),
+            nulls: Nulls::Default,
+            collation: None
         };
         assert_eq!(order_by.to_sql(), "fizz ASC")
     }
+
+    #[test]
+    fn test_nulls_and_collation() {
+        let order_by = OrderBy {
+            ordering: Ordering::Descending,
+            order_by: SortKey::Position(1),
+            nulls: Nulls::First,
+            collation: Some("utf8mb4_bin")
+        };
+        assert_eq!(order_by.to_sql(), "1 COLLATE utf8mb4_bin DESC NULLS FIRST");
+        assert!(OrderBy::new(1).collate("utf8mb4_bin").desc().nulls_first() == order_by);
+        assert!(OrderBy::new("fizz".to_string()).order_by == SortKey::Owned("fizz".to_string()));
+    }
+
+    #[test]
+    fn test_nulls_go_last() {
+        assert!(OrderBy::new("a").nulls_go_last());
+        assert!(!OrderBy::new("a").desc().nulls_go_last());
+        assert!(OrderBy::new("a").desc().nulls_last().nulls_go_last());
+        assert!(!OrderBy::new("a").nulls_first().nulls_go_last());
+        assert!(OrderBy::new("a").using(">").nulls_go_last());
+    }
 }
@@ -0,0 +1,453 @@
+//! Keeps pagination related stuff: keyset (seek) predicates and opaque cursor tokens.
+
+use std::error::Error;
+use std::fmt;
+
+use common::Pusheable;
+use where_cl::{Where, WhereType, IntoWhereType};
+use super::order_by::{OrderBy, Ordering, SortKey};
+
+/// Describes the last row of a previous page for keyset (seek) pagination.
+///
+/// Values are matched one-to-one with `ORDER BY` items of a `Select` and are put into SQL
+/// as they are, so they are supposed to be placeholders (`$1`, `?`) or trusted literals.
+/// `NULL`s are placed as `NULLS FIRST`/`NULLS LAST` of the items say, and PostgreSQL's default
+/// (`NULLS LAST` for `ASC`, `NULLS FIRST` for `DESC`) is assumed otherwise.
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Keyset<'a> {
+    pub values: Vec<Option<&'a str>>,
+    pub nullable: Vec<&'a str>
+}
+
+impl<'a> Keyset<'a> {
+    /// Method to start with.
+    pub fn new() -> Self {
+        Keyset {
+            values: vec![],
+            nullable: vec![]
+        }
+    }
+
+    /// Adds a value of the next `ORDER BY` item.
+    pub fn value(mut self, value: &'a str) -> Self {
+        self.values.push(Some(value));
+        self
+    }
+
+    /// Adds a `NULL` value of the next `ORDER BY` item.
+    pub fn null(mut self) -> Self {
+        self.values.push(None);
+        self
+    }
+
+    /// Marks columns which can contain `NULL`. Rows having `NULL` there are not skipped
+    /// even when the last value in that column wasn't `NULL`.
+    pub fn nullable<T: Pusheable<'a>>(mut self, columns: T) -> Self {
+        columns.push_to(&mut self.nullable);
+        self
+    }
+
+    fn is_nullable(&self, column: &str) -> bool {
+        self.nullable.contains(&column)
+    }
+
+    /// Builds predicate which selects rows going after the last row in the given ordering.
+    /// Fails if there is no `ORDER BY`, the number of values doesn't match it or an item uses
+    /// a column position or `USING`.
+    pub fn predicate(&self, order_by: &[OrderBy<'a>]) -> Result<WhereType<'a>, PaginationError> {
+        if order_by.is_empty() {
+            return Err(PaginationError::MissingOrderBy);
+        }
+        if self.values.len() != order_by.len() {
+            return Err(PaginationError::ValuesMismatch { values: self.values.len(), order_by: order_by.len() });
+        }
+        for item in order_by {
+            if let SortKey::Position(_) = item.order_by {
+                return Err(PaginationError::Position);
+            }
+            if let Ordering::Using(_) = item.ordering {
+                return Err(PaginationError::Using);
+            }
+        }
+        let columns = order_by.iter().map(|x| x.expression()).collect::<Vec<_>>();
+
+        if let Some(predicate) = self.row_comparison(order_by, &columns) {
+            return Ok(predicate.into_where_type());
+        }
+
+        let mut alternatives = vec![];
+        for (idx, item) in order_by.iter().enumerate() {
+            let after = match self.after(item, &columns[idx], self.values[idx]) {
+                Some(after) => after,
+                None => continue
+            };
+
+            let mut terms = columns[..idx].iter()
+                .zip(self.values.iter())
+                .map(|(prev, value)| equals(prev, *value))
+                .collect::<Vec<_>>();
+            terms.push(after);
+
+            alternatives.push(if terms.len() == 1 {
+                terms.pop().unwrap().into_where_type()
+            } else {
+                terms.into_iter().fold(Where::with_and(), |acc, x| acc.expr(x)).into_where_type()
+            });
+        }
+
+        Ok(match alternatives.len() {
+            0 => false.into_where_type(),
+            1 => alternatives.pop().unwrap(),
+            _ => alternatives.into_iter().fold(Where::with_or(), |acc, x| acc.expr(x)).into_where_type()
+        })
+    }
+
+    /// `(a, b) > ($1, $2)` form, which is possible only with the same direction for every
+    /// column and no `NULL`s involved.
+    fn row_comparison(&self, order_by: &[OrderBy<'a>], columns: &[String]) -> Option<String> {
+        let ordering = &order_by[0].ordering;
+
+        if order_by.iter().zip(columns).any(|(x, column)| x.ordering != *ordering || self.is_nullable(column)) {
+            return None;
+        }
+
+        let values = self.values.iter().cloned().collect::<Option<Vec<_>>>()?;
+
+        let operator = comparison(ordering);
+        if values.len() == 1 {
+            return Some(format!("{} {} {}", columns[0], operator, values[0]));
+        }
+
+        Some(format!("({}) {} ({})", columns.join(", "), operator, values.join(", ")))
+    }
+
+    /// Condition for a row to go strictly after `value` in a single column.
+    fn after(&self, item: &OrderBy<'a>, column: &str, value: Option<&'a str>) -> Option<String> {
+        let operator = comparison(&item.ordering);
+        match (item.nulls_go_last(), value) {
+            (true, Some(value)) => {
+                if self.is_nullable(column) {
+                    Some(format!("({} {} {} OR {} IS NULL)", column, operator, value, column))
+                } else {
+                    Some(format!("{} {} {}", column, operator, value))
+                }
+            },
+            (true, None) => None,
+            (false, Some(value)) => Some(format!("{} {} {}", column, operator, value)),
+            (false, None) => Some(format!("{} IS NOT NULL", column))
+        }
+    }
+}
+
+/// `USING` is rejected by `Keyset::predicate` before comparisons are built.
+fn comparison(ordering: &Ordering) -> &'static str {
+    match *ordering {
+        Ordering::Descending => "<",
+        _ => ">"
+    }
+}
+
+fn equals(column: &str, value: Option<&str>) -> String {
+    match value {
+        Some(value) => format!("{} = {}", column, value),
+        None => format!("{} IS NULL", column)
+    }
+}
+
+/// Error returned when a keyset predicate can't be built for the ordering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaginationError {
+    /// There is no `ORDER BY`, so there is no "after".
+    MissingOrderBy,
+    /// Number of keyset values doesn't match the number of `ORDER BY` items.
+    ValuesMismatch {
+        values: usize,
+        order_by: usize
+    },
+    /// `ORDER BY` uses a column position, which can't be compared with a value.
+    Position,
+    /// `ORDER BY ... USING`, whose direction isn't known.
+    Using
+}
+
+impl fmt::Display for PaginationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PaginationError::MissingOrderBy => write!(f, "keyset pagination requires ORDER BY"),
+            PaginationError::ValuesMismatch { values, order_by } => {
+                write!(f, "keyset has {} values, but there are {} ORDER BY items", values, order_by)
+            },
+            PaginationError::Position => write!(f, "keyset pagination can't use column positions in ORDER BY"),
+            PaginationError::Using => write!(f, "keyset pagination can't use ORDER BY ... USING")
+        }
+    }
+}
+
+impl Error for PaginationError {}
+
+/// Error returned when a cursor token can't be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CursorError {
+    /// Token is not valid URL-safe base64.
+    Encoding,
+    /// Token decodes, but doesn't contain a list of values.
+    Format
+}
+
+impl fmt::Display for CursorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            CursorError::Encoding => "cursor is not valid base64",
+            CursorError::Format => "cursor has invalid format"
+        })
+    }
+}
+
+impl Error for CursorError {}
+
+/// Opaque token keeping values of the last row of a page.
+///
+/// Cursor values are **never** put into SQL: bind them as parameters to the placeholders
+/// used in `Keyset`.
+///
+/// # Example
+///
+/// ```
+/// use lithium::select::Cursor;
+///
+/// let token = Cursor::new().value("2016-01-01").null().value("42").encode();
+/// let cursor = Cursor::decode(&token).unwrap();
+/// assert_eq!(cursor.values, vec![Some("2016-01-01".to_string()), None, Some("42".to_string())]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Cursor {
+    pub values: Vec<Option<String>>
+}
+
+impl Cursor {
+    /// Method to start with.
+    pub fn new() -> Self {
+        Cursor {
+            values: vec![]
+        }
+    }
+
+    /// Adds a value.
+    pub fn value<T: Into<String>>(mut self, value: T) -> Self {
+        self.values.push(Some(value.into()));
+        self
+    }
+
+    /// Adds a `NULL` value.
+    pub fn null(mut self) -> Self {
+        self.values.push(None);
+        self
+    }
+
+    /// Encodes values into URL-safe token.
+    pub fn encode(&self) -> String {
+        let mut raw = String::new();
+        for value in &self.values {
+            match *value {
+                Some(ref value) => {
+                    raw.push('s');
+                    raw.push_str(&value.len().to_string());
+                    raw.push(':');
+                    raw.push_str(value);
+                },
+                None => raw.push('n')
+            }
+        }
+        base64_encode(raw.as_bytes())
+    }
+
+    /// Decodes token produced by `encode`.
+    pub fn decode(token: &str) -> Result<Self, CursorError> {
+        let raw = base64_decode(token).ok_or(CursorError::Encoding)?;
+        let raw = String::from_utf8(raw).map_err(|_| CursorError::Format)?;
+
+        let mut values = vec![];
+        let mut rest = &raw[..];
+        while !rest.is_empty() {
+            if rest.starts_with('n') {
+                values.push(None);
+                rest = &rest[1..];
+                continue;
+            }
+            if !rest.starts_with('s') {
+                return Err(CursorError::Format);
+            }
+
+            let colon = rest.find(':').ok_or(CursorError::Format)?;
+            let len = rest[1..colon].parse::<usize>().map_err(|_| CursorError::Format)?;
+            let start = colon + 1;
+            let end = start.checked_add(len).ok_or(CursorError::Format)?;
+            let value = rest.get(start..end).ok_or(CursorError::Format)?;
+            values.push(Some(value.to_string()));
+            rest = &rest[end..];
+        }
+
+        Ok(Cursor {
+            values: values
+        })
+    }
+}
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64_encode(input: &[u8]) -> String {
+    let mut rv = String::new();
+    for chunk in input.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let triple = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for idx in 0..chunk.len() + 1 {
+            rv.push(BASE64[(triple >> (18 - 6 * idx) & 0x3f) as usize] as char);
+        }
+    }
+    rv
+}
+
+fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    if input.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut rv = vec![];
+    for chunk in input.as_bytes().chunks(4) {
+        let mut triple = 0u32;
+        for (idx, byte) in chunk.iter().enumerate() {
+            let sextet = BASE64.iter().position(|x| x == byte)? as u32;
+            triple |= sextet << (18 - 6 * idx);
+        }
+        for idx in 0..chunk.len() - 1 {
+            rv.push((triple >> (16 - 8 * idx)) as u8);
+        }
+    }
+    Some(rv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Keyset, Cursor, CursorError, PaginationError, base64_encode, base64_decode};
+    use select::order_by::{OrderBy, Ordering, Nulls, SortKey};
+    use common::ToSQL;
+
+    fn order_by<'a>(items: &[(&'a str, Ordering<'a>)]) -> Vec<OrderBy<'a>> {
+        items.iter().map(|&(field, ref ordering)| OrderBy {
+            ordering: ordering.clone(),
+            order_by: SortKey::Simple(field),
+            nulls: Nulls::Default,
+            collation: None
+        }).collect()
+    }
+
+    #[test]
+    fn test_single_column() {
+        let order = order_by(&[("id", Ordering::Ascending)]);
+        let keyset = Keyset::new().value("$1");
+        assert_eq!(keyset.predicate(&order).unwrap().to_sql(), "id > $1");
+    }
+
+    #[test]
+    fn test_row_comparison() {
+        let order = order_by(&[("created", Ordering::Descending), ("id", Ordering::Descending)]);
+        let keyset = Keyset::new().value("$1").value("$2");
+        assert_eq!(keyset.predicate(&order).unwrap().to_sql(), "(created, id) < ($1, $2)");
+    }
+
+    #[test]
+    fn test_mixed_directions() {
+        let order = order_by(&[("name", Ordering::Ascending), ("id", Ordering::Descending)]);
+        let keyset = Keyset::new().value("$1").value("$2");
+        let expected = "(name > $1 OR (name = $1 AND id < $2))";
+        assert_eq!(keyset.predicate(&order).unwrap().to_sql(), expected);
+    }
+
+    #[test]
+    fn test_nulls() {
+        let order = order_by(&[("name", Ordering::Ascending), ("id", Ordering::Ascending)]);
+        let keyset = Keyset::new().null().value("$1");
+        assert_eq!(keyset.predicate(&order).unwrap().to_sql(), "(name IS NULL AND id > $1)");
+
+        let order = order_by(&[("name", Ordering::Descending), ("id", Ordering::Ascending)]);
+        let expected = "(name IS NOT NULL OR (name IS NULL AND id > $1))";
+        assert_eq!(keyset.predicate(&order).unwrap().to_sql(), expected);
+    }
+
+    #[test]
+    fn test_nullable_column() {
+        let order = order_by(&[("name", Ordering::Ascending), ("id", Ordering::Ascending)]);
+        let keyset = Keyset::new().value("$1").value("$2").nullable("name");
+        let expected = "((name > $1 OR name IS NULL) OR (name = $1 AND id > $2))";
+        assert_eq!(keyset.predicate(&order).unwrap().to_sql(), expected);
+    }
+
+    #[test]
+    fn test_explicit_nulls() {
+        let order = vec![OrderBy::new("name").nulls_first(), OrderBy::new("id")];
+        let keyset = Keyset::new().null().value("$1");
+        let expected = "(name IS NOT NULL OR (name IS NULL AND id > $1))";
+        assert_eq!(keyset.predicate(&order).unwrap().to_sql(), expected);
+
+        let order = vec![OrderBy::new("name").desc().nulls_last(), OrderBy::new("id")];
+        let keyset = Keyset::new().value("$1").value("$2").nullable("name");
+        let expected = "((name < $1 OR name IS NULL) OR (name = $1 AND id > $2))";
+        assert_eq!(keyset.predicate(&order).unwrap().to_sql(), expected);
+    }
+
+    #[test]
+    fn test_collation() {
+        let order = vec![OrderBy::new("name").collate("\"C\""), OrderBy::new("id")];
+        let keyset = Keyset::new().value("$1").value("$2");
+        assert_eq!(keyset.predicate(&order).unwrap().to_sql(), "(name COLLATE \"C\", id) > ($1, $2)");
+    }
+
+    #[test]
+    fn test_errors() {
+        let keyset = Keyset::new().value("$1");
+        assert_eq!(keyset.predicate(&[OrderBy::new(1)]).err(), Some(PaginationError::Position));
+        assert_eq!(keyset.predicate(&[]).err(), Some(PaginationError::MissingOrderBy));
+        let order = order_by(&[("name", Ordering::Using("<"))]);
+        assert_eq!(keyset.predicate(&order).err(), Some(PaginationError::Using));
+    }
+
+    #[test]
+    fn test_nothing_after() {
+        let order = order_by(&[("name", Ordering::Ascending)]);
+        assert_eq!(Keyset::new().null().predicate(&order).unwrap().to_sql(), "FALSE");
+    }
+
+    #[test]
+    fn test_values_mismatch() {
+        let order = order_by(&[("name", Ordering::Ascending)]);
+        let error = Keyset::new().value("$1").value("$2").predicate(&order).err().unwrap();
+        assert_eq!(error, PaginationError::ValuesMismatch { values: 2, order_by: 1 });
+        assert_eq!(error.to_string(), "keyset has 2 values, but there are 1 ORDER BY items");
+    }
+
+    #[test]
+    fn test_base64() {
+        for input in &["", "f", "fo", "foo", "foob", "fooba", "foobar"] {
+            let encoded = base64_encode(input.as_bytes());
+            assert_eq!(base64_decode(&encoded).unwrap(), input.as_bytes());
+        }
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode(b"\xfb\xff"), "-_8");
+    }
+
+    #[test]
+    fn test_cursor() {
+        let cursor = Cursor::new().value("a:b").null().value("").value("Δ");
+        assert_eq!(Cursor::decode(&cursor.encode()), Ok(cursor));
+        assert_eq!(Cursor::decode(""), Ok(Cursor::new()));
+    }
+
+    #[test]
+    fn test_invalid_cursor() {
+        assert_eq!(Cursor::decode("a"), Err(CursorError::Encoding));
+        assert_eq!(Cursor::decode("!!!!"), Err(CursorError::Encoding));
+        assert_eq!(Cursor::decode(&base64_encode(b"x")), Err(CursorError::Format));
+        assert_eq!(Cursor::decode(&base64_encode(b"s10:ab")), Err(CursorError::Format));
+        assert_eq!(Cursor::decode(&base64_encode(b"s18446744073709551615:x")), Err(CursorError::Format));
+    }
+}
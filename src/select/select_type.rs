@@ -1,7 +1,15 @@
+use common::ToSQL;
+use function::Function;
+use case::Case;
+use visitor::{Visitor, Rewrite};
+
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
 pub enum SelectType<'a> {
     All,
-    Specific(Vec<&'a str>)
+    Specific(Vec<&'a str>),
+    /// Columns where some of them are generated at runtime.
+    Extended(Vec<Column<'a>>)
 }
 
 impl<'a> SelectType<'a> {
@@ -9,14 +17,124 @@ impl<'a> SelectType<'a> {
     pub fn to_sql(&self) -> String {
         match *self {
             SelectType::All => "*".to_string(),
-            SelectType::Specific(ref clauses) => clauses.join(", ")
+            SelectType::Specific(ref clauses) => clauses.join(", "),
+            SelectType::Extended(ref columns) => {
+                columns.iter().map(|x| x.to_sql()).collect::<Vec<_>>().join(", ")
+            }
+        }
+    }
+}
+
+/// Column of `SELECT`.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Column<'a> {
+    Simple(&'a str),
+    /// Expression generated at runtime, e.g. `GROUPING(a, b)`.
+    Owned(String),
+    /// Function call, which is kept as is so a `Visitor` can walk its `FILTER` clause.
+    Function(Box<Function<'a>>),
+    /// `CASE` expression, which is kept as is so a `Visitor` can walk its conditions.
+    Case(Box<Case<'a>>),
+    /// `{expression} AS {alias}`.
+    Aliased {
+        expression: Box<Column<'a>>,
+        alias: &'a str
+    }
+}
+
+impl<'a> Column<'a> {
+    /// Gives the column an alias, replacing the previous one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::select::{Column, IntoColumn};
+    ///
+    /// let column = "COUNT(*)".into_column().alias("total");
+    /// assert_eq!(column.get_alias(), Some("total"));
+    /// assert_eq!(column.to_sql(), "COUNT(*) AS total");
+    /// assert_eq!(column.alias("n").to_sql(), "COUNT(*) AS n");
+    /// ```
+    pub fn alias(self, alias: &'a str) -> Self {
+        Column::Aliased {
+            expression: Box::new(self.unaliased()),
+            alias: alias
+        }
+    }
+
+    /// Drops the alias.
+    pub fn unaliased(self) -> Self {
+        match self {
+            Column::Aliased { expression, .. } => *expression,
+            column => column
+        }
+    }
+
+    /// Returns the alias.
+    pub fn get_alias(&self) -> Option<&'a str> {
+        match *self {
+            Column::Aliased { alias, .. } => Some(alias),
+            _ => None
+        }
+    }
+
+    /// Returns the expression without the alias.
+    pub fn expression(&self) -> &Column<'a> {
+        match *self {
+            Column::Aliased { ref expression, .. } => expression,
+            ref column => column
+        }
+    }
+
+    pub fn to_sql(&self) -> String {
+        match *self {
+            Column::Simple(column) => column.to_string(),
+            Column::Owned(ref column) => column.clone(),
+            Column::Function(ref function) => function.to_sql(),
+            Column::Case(ref case) => case.to_sql(),
+            Column::Aliased { ref expression, alias } => format!("{} AS {}", expression.to_sql(), alias)
+        }
+    }
+}
+
+/// Is used to receive either `&str`, `String` or an expression builder as a column.
+pub trait IntoColumn<'a> {
+    fn into_column(self) -> Column<'a>;
+}
+
+impl<'a> IntoColumn<'a> for Column<'a> {
+    fn into_column(self) -> Column<'a> {
+        self
+    }
+}
+
+impl<'a> IntoColumn<'a> for &'a str {
+    fn into_column(self) -> Column<'a> {
+        Column::Simple(self)
+    }
+}
+
+impl<'a> IntoColumn<'a> for String {
+    fn into_column(self) -> Column<'a> {
+        Column::Owned(self)
+    }
+}
+
+impl<'a> Rewrite<'a> for Column<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        match *self {
+            Column::Function(ref mut function) => function.walk(visitor),
+            Column::Case(ref mut case) => case.walk(visitor),
+            Column::Aliased { ref mut expression, .. } => expression.walk(visitor),
+            Column::Simple(_) | Column::Owned(_) => {}
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::SelectType;
+    use super::{SelectType, Column};
 
     #[test]
     fn select_all() {
@@ -35,4 +153,21 @@ mod tests {
         let select = SelectType::Specific(vec!["foo", "bar"]);
         assert_eq!(select.to_sql(), "foo, bar".to_string());
     }
+
+    #[test]
+    fn select_extended() {
+        let select = SelectType::Extended(vec![Column::Simple("foo"), Column::Owned("COUNT(*)".to_string())]);
+        assert_eq!(select.to_sql(), "foo, COUNT(*)".to_string());
+    }
+
+    #[test]
+    fn select_aliased() {
+        let column = Column::Simple("foo").alias("f").alias("bar");
+        assert!(*column.expression() == Column::Simple("foo"));
+        assert_eq!(column.get_alias(), Some("bar"));
+        assert!(column.clone().unaliased() == Column::Simple("foo"));
+
+        let select = SelectType::Extended(vec![column, Column::Simple("bazz")]);
+        assert_eq!(select.to_sql(), "foo AS bar, bazz".to_string());
+    }
 }
@@ -1,10 +1,17 @@
 use common::ToSQL;
+use dialect::Dialect;
+use visitor::{Visitor, Rewrite};
+use insert::Values;
 
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UnionMode {
     Simple,
     All
 }
 
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Union<L: ToSQL, R: ToSQL> {
     left: L,
     right: R,
@@ -60,8 +67,12 @@ impl<L: ToSQL, R:ToSQL> Union<L, R> {
 
 impl<L: ToSQL, R: ToSQL> ToSQL for Union<L, R> {
     fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
         let mut rv = String::new();
-        rv.push_str(&self.left.to_sql());
+        rv.push_str(&self.left.to_sql_for(dialect));
         rv.push(' ');
         rv.push_str("UNION");
         rv.push(' ');
@@ -71,15 +82,32 @@ impl<L: ToSQL, R: ToSQL> ToSQL for Union<L, R> {
             rv.push(' ');
         }
 
-        rv.push_str(&self.right.to_sql());
+        rv.push_str(&self.right.to_sql_for(dialect));
         rv
     }
 }
 
+impl<'a, L: ToSQL + Rewrite<'a>, R: ToSQL + Rewrite<'a>> Rewrite<'a> for Union<L, R> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        self.left.walk(visitor);
+        self.right.walk(visitor);
+    }
+}
+
+impl<'a, L: ToSQL + Into<Values<'a>>, R: ToSQL + Into<Values<'a>>> From<Union<L, R>> for Values<'a> {
+    fn from(union: Union<L, R>) -> Self {
+        Values::Union(Box::new(Union::new(union.mode, union.left.into(), union.right.into())))
+    }
+}
+
 impl<'a, L: ToSQL, R:ToSQL> ToSQL for &'a Union<L, R> {
     fn to_sql(&self) -> String {
         (**self).to_sql()
     }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
+        (**self).to_sql_for(dialect)
+    }
 }
 
 #[cfg(test)]
@@ -142,4 +170,14 @@ mod tests {
         };
         assert_eq!(union.to_sql(), expected);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let union = Union::new(UnionMode::All, Select::from("foo"), Select::from("bar").filter("a = 1"));
+
+        let json = ::serde_json::to_string(&union).unwrap();
+        let restored: Union<Select, Select> = ::serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.to_sql(), union.to_sql());
+    }
 }
@@ -0,0 +1,414 @@
+//! Keeps transaction control stuff: `BEGIN` with its options, `COMMIT`, `ROLLBACK` and savepoints,
+//! `Script` putting statements into one transaction and `transaction` running a closure inside
+//! of one with an `Executor`.
+
+use common::ToSQL;
+use dialect::Dialect;
+
+/// Isolation level of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IsolationLevel {
+    ReadUncommitted,
+    ReadCommitted,
+    RepeatableRead,
+    Serializable
+}
+
+impl IsolationLevel {
+    pub fn to_sql(&self) -> &str {
+        match *self {
+            IsolationLevel::ReadUncommitted => "READ UNCOMMITTED",
+            IsolationLevel::ReadCommitted => "READ COMMITTED",
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            IsolationLevel::Serializable => "SERIALIZABLE"
+        }
+    }
+}
+
+/// Represents the start of a transaction.
+///
+/// PostgreSQL gets `BEGIN` with every option. MySQL gets `START TRANSACTION`, which is preceded
+/// by `SET TRANSACTION ISOLATION LEVEL ...` when the level is specified, and ignores
+/// `DEFERRABLE`. SQLite transactions are always serializable, so it gets plain `BEGIN`.
+///
+/// # Example
+///
+/// ```
+/// use lithium::{ToSQL, Dialect};
+/// use lithium::transaction::{Begin, IsolationLevel};
+///
+/// let begin = Begin::new().isolation(IsolationLevel::Serializable).read_only().deferrable();
+/// assert_eq!(begin.to_sql(), "BEGIN ISOLATION LEVEL SERIALIZABLE, READ ONLY, DEFERRABLE");
+/// assert_eq!(
+///     begin.to_sql_for(Dialect::MySQL),
+///     "SET TRANSACTION ISOLATION LEVEL SERIALIZABLE; START TRANSACTION READ ONLY"
+/// );
+/// assert_eq!(begin.to_sql_for(Dialect::SQLite), "BEGIN");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Begin {
+    isolation: Option<IsolationLevel>,
+    read_only: Option<bool>,
+    deferrable: bool
+}
+
+impl Begin {
+    /// Method to start with. Options of the database are used by default.
+    pub fn new() -> Self {
+        Begin {
+            isolation: None,
+            read_only: None,
+            deferrable: false
+        }
+    }
+
+    /// Specifies the isolation level.
+    pub fn isolation(mut self, level: IsolationLevel) -> Self {
+        self.isolation = Some(level);
+        self
+    }
+
+    /// Specifies `READ ONLY`.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = Some(true);
+        self
+    }
+
+    /// Specifies `READ WRITE`.
+    pub fn read_write(mut self) -> Self {
+        self.read_only = Some(false);
+        self
+    }
+
+    /// Specifies `DEFERRABLE` (PostgreSQL), which makes a serializable read-only transaction
+    /// wait for a snapshot instead of failing on conflicts.
+    pub fn deferrable(mut self) -> Self {
+        self.deferrable = true;
+        self
+    }
+
+    /// Returns the isolation level.
+    pub fn get_isolation(&self) -> Option<IsolationLevel> {
+        self.isolation
+    }
+
+    /// Returns statements starting the transaction, which is more than one for MySQL
+    /// with the isolation level specified.
+    pub fn statements_for(&self, dialect: Dialect) -> Vec<String> {
+        let access = self.read_only.map(|x| if x { "READ ONLY" } else { "READ WRITE" });
+        match dialect {
+            Dialect::PostgreSQL => {
+                let mut modes = vec![];
+                if let Some(level) = self.isolation {
+                    modes.push(format!("ISOLATION LEVEL {}", level.to_sql()));
+                }
+                if let Some(access) = access {
+                    modes.push(access.to_string());
+                }
+                if self.deferrable {
+                    modes.push("DEFERRABLE".to_string());
+                }
+                if modes.is_empty() {
+                    vec!["BEGIN".to_string()]
+                } else {
+                    vec![format!("BEGIN {}", modes.join(", "))]
+                }
+            },
+            Dialect::MySQL => {
+                let mut rv = vec![];
+                if let Some(level) = self.isolation {
+                    rv.push(format!("SET TRANSACTION ISOLATION LEVEL {}", level.to_sql()));
+                }
+                match access {
+                    Some(access) => rv.push(format!("START TRANSACTION {}", access)),
+                    None => rv.push("START TRANSACTION".to_string())
+                }
+                rv
+            },
+            Dialect::SQLite => vec!["BEGIN".to_string()]
+        }
+    }
+}
+
+impl ToSQL for Begin {
+    fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
+        self.statements_for(dialect).join("; ")
+    }
+}
+
+/// Transaction control statement.
+///
+/// # Example
+///
+/// ```
+/// use lithium::ToSQL;
+/// use lithium::transaction::Statement;
+///
+/// assert_eq!(Statement::Savepoint("before_import").to_sql(), "SAVEPOINT before_import");
+/// assert_eq!(Statement::RollbackTo("before_import").to_sql(), "ROLLBACK TO SAVEPOINT before_import");
+/// assert_eq!(Statement::Commit.to_sql(), "COMMIT");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Statement<'a> {
+    Begin(Begin),
+    Commit,
+    Rollback,
+    Savepoint(&'a str),
+    /// `RELEASE SAVEPOINT`, which keeps changes made after the savepoint.
+    Release(&'a str),
+    /// `ROLLBACK TO SAVEPOINT`, which discards changes made after the savepoint.
+    RollbackTo(&'a str)
+}
+
+impl<'a> ToSQL for Statement<'a> {
+    fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
+        match *self {
+            Statement::Begin(ref begin) => begin.to_sql_for(dialect),
+            Statement::Commit => "COMMIT".to_string(),
+            Statement::Rollback => "ROLLBACK".to_string(),
+            Statement::Savepoint(name) => format!("SAVEPOINT {}", name),
+            Statement::Release(name) => format!("RELEASE SAVEPOINT {}", name),
+            Statement::RollbackTo(name) => format!("ROLLBACK TO SAVEPOINT {}", name)
+        }
+    }
+}
+
+/// Statements wrapped into one transaction: `BEGIN`, the statements and `COMMIT`, every one
+/// of which ends with `;`. Keeps references, so the statements are rendered for the dialect
+/// the script is rendered for.
+///
+/// # Example
+///
+/// ```
+/// use lithium::{ToSQL, Dialect, Insert, Update};
+/// use lithium::transaction::{Script, Begin, IsolationLevel};
+///
+/// let insert = Insert::into("log").columns("message").values(vec!["'moved'"]);
+/// let update = Update::new("accounts").set("balance = balance - 10").filter("id = 1");
+/// let script = Script::new(Begin::new().isolation(IsolationLevel::RepeatableRead))
+///     .push(&update)
+///     .push(&insert);
+/// let expected = {
+///     "BEGIN ISOLATION LEVEL REPEATABLE READ;\n\
+///     UPDATE accounts SET balance = balance - 10 WHERE id = 1;\n\
+///     INSERT INTO log (message) VALUES ('moved');\n\
+///     COMMIT;".to_string()
+/// };
+/// assert_eq!(script.to_sql(), expected);
+/// assert!(script.to_sql_for(Dialect::MySQL).starts_with("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ;\nSTART TRANSACTION;\n"));
+/// ```
+#[derive(Clone)]
+pub struct Script<'s> {
+    begin: Begin,
+    statements: Vec<&'s dyn ToSQL>
+}
+
+impl<'s> Script<'s> {
+    /// Method to start with.
+    pub fn new(begin: Begin) -> Self {
+        Script {
+            begin: begin,
+            statements: vec![]
+        }
+    }
+
+    /// Adds a statement, which can be a query as well as `Statement`, e.g. a savepoint.
+    pub fn push(mut self, statement: &'s dyn ToSQL) -> Self {
+        self.statements.push(statement);
+        self
+    }
+
+    /// Returns statements of the script including `BEGIN` and `COMMIT`.
+    pub fn statements_for(&self, dialect: Dialect) -> Vec<String> {
+        let mut rv = self.begin.statements_for(dialect);
+        rv.extend(self.statements.iter().map(|x| x.to_sql_for(dialect)));
+        rv.push(Statement::Commit.to_sql_for(dialect));
+        rv
+    }
+}
+
+impl<'s> ToSQL for Script<'s> {
+    fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    fn to_sql_for(&self, dialect: Dialect) -> String {
+        self.statements_for(dialect).iter().map(|x| format!("{};", x)).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Runs statements, which is implemented on top of a database driver to use `transaction`.
+pub trait Executor {
+    type Error;
+
+    /// Runs a single statement.
+    fn execute(&mut self, sql: &str) -> Result<(), Self::Error>;
+
+    /// Returns the dialect statements are rendered for.
+    fn dialect(&self) -> Dialect {
+        Dialect::PostgreSQL
+    }
+}
+
+/// Runs `f` inside of a transaction, which is committed when `f` returns `Ok` and rolled back
+/// otherwise. An error of the rollback is dropped in favour of the one returned by `f`.
+///
+/// # Example
+///
+/// ```
+/// use lithium::{ToSQL, Update};
+/// use lithium::transaction::{Executor, Begin, transaction};
+///
+/// struct Log(Vec<String>);
+///
+/// impl Executor for Log {
+///     type Error = String;
+///
+///     fn execute(&mut self, sql: &str) -> Result<(), String> {
+///         self.0.push(sql.to_string());
+///         Ok(())
+///     }
+/// }
+///
+/// let mut log = Log(vec![]);
+/// let result = transaction(&mut log, Begin::new(), |log| {
+///     log.execute(&Update::new("foo").set("a = 1").to_sql())?;
+///     Err::<(), _>("oops".to_string())
+/// });
+/// assert_eq!(result, Err("oops".to_string()));
+/// assert_eq!(log.0, vec!["BEGIN", "UPDATE foo SET a = 1", "ROLLBACK"]);
+/// ```
+pub fn transaction<E, F, T>(executor: &mut E, begin: Begin, f: F) -> Result<T, E::Error>
+    where E: Executor, F: FnOnce(&mut E) -> Result<T, E::Error>
+{
+    let dialect = executor.dialect();
+    for statement in begin.statements_for(dialect) {
+        executor.execute(&statement)?;
+    }
+    finish(executor, f, Statement::Commit, Statement::Rollback)
+}
+
+/// Runs `f` inside of a savepoint of the current transaction, which is released when `f`
+/// returns `Ok` and rolled back to otherwise.
+pub fn savepoint<E, F, T>(executor: &mut E, name: &str, f: F) -> Result<T, E::Error>
+    where E: Executor, F: FnOnce(&mut E) -> Result<T, E::Error>
+{
+    let dialect = executor.dialect();
+    executor.execute(&Statement::Savepoint(name).to_sql_for(dialect))?;
+    finish(executor, f, Statement::Release(name), Statement::RollbackTo(name))
+}
+
+fn finish<E, F, T>(executor: &mut E, f: F, success: Statement, failure: Statement) -> Result<T, E::Error>
+    where E: Executor, F: FnOnce(&mut E) -> Result<T, E::Error>
+{
+    let dialect = executor.dialect();
+    match f(executor) {
+        Ok(value) => {
+            executor.execute(&success.to_sql_for(dialect))?;
+            Ok(value)
+        },
+        Err(error) => {
+            let _ = executor.execute(&failure.to_sql_for(dialect));
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Begin, IsolationLevel, Statement, Script, Executor, transaction, savepoint};
+    use common::ToSQL;
+    use dialect::Dialect;
+    use select::Select;
+
+    #[test]
+    fn test_begin() {
+        assert_eq!(Begin::new().to_sql(), "BEGIN");
+        assert_eq!(Begin::new().to_sql_for(Dialect::MySQL), "START TRANSACTION");
+        let begin = Begin::new().isolation(IsolationLevel::ReadCommitted).read_write();
+        assert_eq!(begin.to_sql(), "BEGIN ISOLATION LEVEL READ COMMITTED, READ WRITE");
+        assert_eq!(begin.get_isolation(), Some(IsolationLevel::ReadCommitted));
+        assert_eq!(Begin::new().read_only().to_sql_for(Dialect::MySQL), "START TRANSACTION READ ONLY");
+        assert_eq!(Statement::Begin(begin).to_sql_for(Dialect::SQLite), "BEGIN");
+    }
+
+    #[test]
+    fn test_statements() {
+        assert_eq!(Statement::Rollback.to_sql(), "ROLLBACK");
+        assert_eq!(Statement::Release("a").to_sql_for(Dialect::SQLite), "RELEASE SAVEPOINT a");
+    }
+
+    #[test]
+    fn test_script() {
+        let select = Select::from("foo");
+        let savepoint = Statement::Savepoint("a");
+        let script = Script::new(Begin::new()).push(&savepoint).push(&select);
+        assert_eq!(script.to_sql(), "BEGIN;\nSAVEPOINT a;\nSELECT * FROM foo;\nCOMMIT;");
+        assert_eq!(Script::new(Begin::new()).statements_for(Dialect::MySQL), vec!["START TRANSACTION", "COMMIT"]);
+    }
+
+    struct Log {
+        statements: Vec<String>,
+        failing: Option<&'static str>
+    }
+
+    impl Executor for Log {
+        type Error = String;
+
+        fn execute(&mut self, sql: &str) -> Result<(), String> {
+            self.statements.push(sql.to_string());
+            match self.failing {
+                Some(failing) if sql.starts_with(failing) => Err(format!("{} failed", failing)),
+                _ => Ok(())
+            }
+        }
+
+        fn dialect(&self) -> Dialect {
+            Dialect::MySQL
+        }
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut log = Log { statements: vec![], failing: Some("INSERT") };
+        let result = transaction(&mut log, Begin::new().isolation(IsolationLevel::Serializable), |log| {
+            log.execute("UPDATE foo SET a = 1")?;
+            let inner = savepoint(log, "b", |log| log.execute("INSERT INTO foo DEFAULT VALUES"));
+            assert_eq!(inner, Err("INSERT failed".to_string()));
+            Ok(1)
+        });
+        assert_eq!(result, Ok(1));
+        assert_eq!(log.statements, vec![
+            "SET TRANSACTION ISOLATION LEVEL SERIALIZABLE",
+            "START TRANSACTION",
+            "UPDATE foo SET a = 1",
+            "SAVEPOINT b",
+            "INSERT INTO foo DEFAULT VALUES",
+            "ROLLBACK TO SAVEPOINT b",
+            "COMMIT"
+        ]);
+    }
+
+    #[test]
+    fn test_failing_commit() {
+        let mut log = Log { statements: vec![], failing: Some("COMMIT") };
+        let result = transaction(&mut log, Begin::new(), |_| Ok(()));
+        assert_eq!(result, Err("COMMIT failed".to_string()));
+
+        let mut log = Log { statements: vec![], failing: Some("START") };
+        let result = transaction(&mut log, Begin::new(), |_| -> Result<(), String> { panic!("isn't called") });
+        assert_eq!(result, Err("START failed".to_string()));
+        assert_eq!(log.statements, vec!["START TRANSACTION"]);
+    }
+}
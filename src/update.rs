@@ -1,31 +1,162 @@
 //! Keeps `UPDATE` related stuff.
 
+use std::error::Error;
+use std::fmt;
+
 use common::{ToSQL, Pusheable, AsStr};
-use where_cl::{WhereType, IntoWhereType};
+use dialect::Dialect;
+use params::{Parameters, Value};
+use select::{Select, Column, IntoColumn, Join, JoinType, OrderBy, Ordering, IntoSortKey, LimitType};
+use where_cl::{WhereType, IntoWhereType, conjunction};
+use visitor::{Visitor, Rewrite};
 
 // TODO: make it pretty
 const RETURNING: &'static str = " RETURNING ";
 
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
 pub enum FromType<'a> {
     Empty,
-    Specified(&'a str)
+    Specified(&'a str),
+    /// `FROM {table} AS {alias}`.
+    Aliased {
+        table: &'a str,
+        alias: &'a str
+    },
+    /// Several tables with optional aliases, `FROM a, b AS c`.
+    Multiple(Vec<(&'a str, Option<&'a str>)>)
+}
+
+impl<'a> FromType<'a> {
+    /// Returns tables along with their aliases.
+    pub fn items(&self) -> Vec<(&'a str, Option<&'a str>)> {
+        match *self {
+            FromType::Empty => vec![],
+            FromType::Specified(table) => vec![(table, None)],
+            FromType::Aliased { table, alias } => vec![(table, Some(alias))],
+            FromType::Multiple(ref items) => items.clone()
+        }
+    }
+
+    fn push(self, table: &'a str, alias: Option<&'a str>) -> Self {
+        let mut items = self.items();
+        items.push((table, alias));
+        FromType::Multiple(items)
+    }
+
+    fn to_sql(&self) -> String {
+        self.items().iter()
+            .map(|&(table, alias)| match alias {
+                Some(alias) => format!("{} AS {}", table, alias),
+                None => table.to_string()
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
 
+/// Item of `SET` clause.
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Assignment<'a> {
+    /// Preformed expression like `a = 1`.
+    Simple(&'a str),
+    /// `column = value`, where the value can be generated at runtime.
+    Value {
+        column: &'a str,
+        value: Column<'a>
+    },
+    /// `column = DEFAULT`.
+    Default(&'a str),
+    /// `(a, b) = (SELECT ...)`, which isn't supported by MySQL.
+    Row {
+        columns: Vec<&'a str>,
+        query: Box<Select<'a>>
+    }
+}
+
+impl<'a> Assignment<'a> {
+    /// Returns columns which are assigned, which is empty for preformed expressions.
+    pub fn columns(&self) -> Vec<&'a str> {
+        match *self {
+            Assignment::Simple(_) => vec![],
+            Assignment::Value { column, .. } | Assignment::Default(column) => vec![column],
+            Assignment::Row { ref columns, .. } => columns.clone()
+        }
+    }
+
+    pub fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    pub fn to_sql_for(&self, dialect: Dialect) -> String {
+        match *self {
+            Assignment::Simple(expression) => expression.to_string(),
+            Assignment::Value { column, ref value } => format!("{} = {}", column, value.to_sql()),
+            Assignment::Default(column) => format!("{} = DEFAULT", column),
+            Assignment::Row { ref columns, ref query } => {
+                format!("({}) = ({})", columns.join(", "), query.to_sql_for(dialect))
+            }
+        }
+    }
+}
+
+impl<'a> Rewrite<'a> for Assignment<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        match *self {
+            Assignment::Value { ref mut value, .. } => value.walk(visitor),
+            Assignment::Row { ref mut query, .. } => query.walk(visitor),
+            Assignment::Simple(_) | Assignment::Default(_) => {}
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
 pub enum Returning<'a> {
     Empty,
     All,
     Specified(Vec<&'a str>)
 }
 
+/// Error returned by `Update::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateError {
+    /// Joins are specified without `FROM` clause, which is required outside of MySQL.
+    JoinWithoutFrom,
+    /// Clause isn't supported by the dialect, e.g. `LIMIT` by PostgreSQL or by MySQL along
+    /// with joins.
+    Unsupported {
+        clause: &'static str,
+        dialect: Dialect
+    }
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UpdateError::JoinWithoutFrom => write!(f, "joins require FROM clause"),
+            UpdateError::Unsupported { clause, dialect } => {
+                write!(f, "{} isn't supported by {:?} in UPDATE", clause, dialect)
+            }
+        }
+    }
+}
+
+impl Error for UpdateError {}
+
 /// Represents `UPDATE` query
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
 pub struct Update<'a> {
     table: &'a str,
-    expressions: Vec<&'a str>,
+    expressions: Vec<Assignment<'a>>,
     from: FromType<'a>,
+    joins: Vec<Join<'a>>,
+    #[cfg_attr(feature = "serde", serde(rename = "where"))]
     where_cl: Vec<WhereType<'a>>,
+    order_by: Vec<OrderBy<'a>>,
+    limit: LimitType<'a>,
     returning: Returning<'a>
 }
 
@@ -36,7 +167,10 @@ impl<'a> Update<'a> {
             table: table,
             expressions: vec![],
             from: FromType::Empty,
+            joins: vec![],
             where_cl: vec![],
+            order_by: vec![],
+            limit: LimitType::Empty,
             returning: Returning::Empty
         }
     }
@@ -46,36 +180,168 @@ impl<'a> Update<'a> {
     /// # Example
     ///
     /// ```
-    /// use lithium::Update;
+    /// use lithium::{ToSQL, Update};
     /// let query = Update::new("foo").set("a = 1").set(&["b = 2", "c = 3"]);
     /// let expected = "UPDATE foo SET a = 1, b = 2, c = 3".to_string();
     /// assert_eq!(query.to_sql(), expected);
     /// ```
     pub fn set<T: Pusheable<'a>>(mut self, expressions: T) -> Self {
-        expressions.push_to(&mut self.expressions);
+        let mut input = vec![];
+        expressions.push_to(&mut input);
+        self.expressions.extend(input.into_iter().map(Assignment::Simple));
+        self
+    }
+
+    /// Specifies `column = value` expression. The value can be a column, a literal,
+    /// a placeholder or an expression builder like `Case`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Update};
+    /// use lithium::params::{Parameters, Placeholder};
+    ///
+    /// let mut params = Parameters::new(Placeholder::Numbered);
+    /// let query = Update::new("foo").set_value("a", params.push(1)).set_value("b", "a + 1");
+    /// assert_eq!(query.to_sql(), "UPDATE foo SET a = $1, b = a + 1".to_string());
+    /// ```
+    pub fn set_value<T: IntoColumn<'a>>(mut self, column: &'a str, value: T) -> Self {
+        self.expressions.push(Assignment::Value {
+            column: column,
+            value: value.into_column()
+        });
         self
     }
 
+    /// Specifies `column = placeholder` expression, adding the value to `params`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Update};
+    /// use lithium::params::{Parameters, Placeholder, Value};
+    ///
+    /// let mut params = Parameters::new(Placeholder::Question);
+    /// let query = Update::new("foo").set_param("a", 1, &mut params).set_param("b", "x", &mut params);
+    /// assert_eq!(query.to_sql(), "UPDATE foo SET a = ?, b = ?".to_string());
+    /// assert_eq!(params.into_values(), vec![Value::Int(1), Value::Text("x".to_string())]);
+    /// ```
+    pub fn set_param<T: Into<Value>>(self, column: &'a str, value: T, params: &mut Parameters) -> Self {
+        self.set_value(column, params.push(value))
+    }
+
+    /// Specifies `column = DEFAULT` expression.
+    pub fn set_default(mut self, column: &'a str) -> Self {
+        self.expressions.push(Assignment::Default(column));
+        self
+    }
+
+    /// Specifies `(a, b) = (SELECT ...)` expression (PostgreSQL, SQLite).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select, Update};
+    ///
+    /// let totals = Select::from("orders").columns(&["SUM(amount)", "MAX(created)"]).filter("orders.user_id = users.id");
+    /// let query = Update::new("users").set_row(&["total", "last_order"], totals).set_default("updated");
+    /// let expected = {
+    ///     "UPDATE users SET (total, last_order) = \
+    ///     (SELECT SUM(amount), MAX(created) FROM orders WHERE orders.user_id = users.id), \
+    ///     updated = DEFAULT".to_string()
+    /// };
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn set_row<T: Pusheable<'a>>(mut self, columns: T, query: Select<'a>) -> Self {
+        let mut input = vec![];
+        columns.push_to(&mut input);
+        self.expressions.push(Assignment::Row {
+            columns: input,
+            query: Box::new(query)
+        });
+        self
+    }
+
+    /// Replaces previously specified update expressions.
+    pub fn replace_set<T: Pusheable<'a>>(mut self, expressions: T) -> Self {
+        self.expressions.clear();
+        self.set(expressions)
+    }
+
     /// Specifies `FROM` clause. Can take either `&str` or `&Subquery`.
     ///
     /// # Examples
     ///
     /// ```
-    /// use lithium::Update;
+    /// use lithium::{ToSQL, Update};
     /// let query = Update::new("foo").set("a = blah.a").from("blah");
     /// let expected = "UPDATE foo SET a = blah.a FROM blah".to_string();
     /// assert_eq!(query.to_sql(), expected);
     /// ```
     ///
     /// ```
-    /// use lithium::{Select, Update};
+    /// use lithium::{ToSQL, Select, Update};
     /// let subquery = Select::from("foo").columns(&["a", "b"]).as_subquery().with_alias("foo");
     /// let update = Update::new("bar").set(&["a = foo.a", "b = foo.b"]).from(&subquery);
     /// let expected = "UPDATE bar SET a = foo.a, b = foo.b FROM (SELECT a, b FROM foo) AS foo".to_string();
     /// assert_eq!(update.to_sql(), expected);
     /// ```
     pub fn from<T: AsStr<'a>>(mut self, table: T) -> Self {
-        self.from = FromType::Specified(table.as_str());
+        self.from = match table.alias() {
+            Some(alias) => FromType::Aliased { table: table.as_str(), alias: alias },
+            None => FromType::Specified(table.as_str())
+        };
+        self
+    }
+
+    /// Specifies `FROM` clause with an alias. An alias of a subquery is replaced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Update};
+    /// let query = Update::new("foo").set("a = b.a").from_as("bar", "b");
+    /// assert_eq!(query.to_sql(), "UPDATE foo SET a = b.a FROM bar AS b".to_string());
+    /// ```
+    pub fn from_as<T: AsStr<'a>>(mut self, table: T, alias: &'a str) -> Self {
+        self.from = FromType::Aliased { table: table.as_str(), alias: alias };
+        self
+    }
+
+    /// Adds a table to `FROM` clause. Can take either `&str` or `&Subquery`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Update, Dialect};
+    ///
+    /// let query = Update::new("foo")
+    ///     .set("a = bar.a + bazz.b")
+    ///     .from("bar")
+    ///     .add_from_as("bazz_table", "bazz")
+    ///     .filter("bar.id = foo.id AND bazz.id = foo.id");
+    /// let expected = {
+    ///     "UPDATE foo SET a = bar.a + bazz.b FROM bar, bazz_table AS bazz \
+    ///     WHERE bar.id = foo.id AND bazz.id = foo.id".to_string()
+    /// };
+    /// assert_eq!(query.to_sql(), expected);
+    ///
+    /// let expected = {
+    ///     "UPDATE foo, bar, bazz_table AS bazz SET a = bar.a + bazz.b \
+    ///     WHERE bar.id = foo.id AND bazz.id = foo.id".to_string()
+    /// };
+    /// assert_eq!(query.to_sql_for(Dialect::MySQL), expected);
+    /// ```
+    pub fn add_from<T: AsStr<'a>>(mut self, table: T) -> Self {
+        let from = ::std::mem::replace(&mut self.from, FromType::Empty);
+        self.from = from.push(table.as_str(), table.alias());
+        self
+    }
+
+    /// Adds a table with an alias to `FROM` clause. An alias of a subquery is replaced.
+    pub fn add_from_as<T: AsStr<'a>>(mut self, table: T, alias: &'a str) -> Self {
+        let from = ::std::mem::replace(&mut self.from, FromType::Empty);
+        self.from = from.push(table.as_str(), Some(alias));
         self
     }
 
@@ -85,22 +351,151 @@ impl<'a> Update<'a> {
         self
     }
 
+    fn push_join<T: AsStr<'a>>(mut self, join_type: JoinType, target: T, alias: Option<&'a str>,
+                               clause: &'a str) -> Self {
+        self.joins.push(Join {
+            join_type: join_type,
+            alias: alias.or_else(|| target.alias()),
+            target: target.as_str(),
+            clause: clause
+        });
+        self
+    }
+
+    /// Specifies `INNER JOIN`. PostgreSQL and SQLite join tables of `FROM` clause, so there
+    /// has to be one, while MySQL joins the target table. Without `FROM` joins are still rendered
+    /// right after the target, so the database rejects the query; `validate` reports it beforehand.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Update, Dialect};
+    /// use lithium::select::Ordering;
+    ///
+    /// let query = Update::new("items")
+    ///     .join("orders", "orders.id = items.order_id")
+    ///     .set("items.status = orders.status")
+    ///     .filter("orders.closed")
+    ///     .order_by("items.id", Ordering::Ascending)
+    ///     .limit("100");
+    /// let expected = {
+    ///     "UPDATE items INNER JOIN orders ON orders.id = items.order_id \
+    ///     SET items.status = orders.status \
+    ///     WHERE orders.closed \
+    ///     ORDER BY items.id ASC \
+    ///     LIMIT 100".to_string()
+    /// };
+    /// assert_eq!(query.to_sql_for(Dialect::MySQL), expected);
+    /// ```
+    pub fn join<T: AsStr<'a>>(self, target: T, clause: &'a str) -> Self {
+        self.push_join(JoinType::Inner, target, None, clause)
+    }
+
+    pub fn left_join<T: AsStr<'a>>(self, target: T, clause: &'a str) -> Self {
+        self.push_join(JoinType::Left, target, None, clause)
+    }
+
+    /// Specifies `INNER JOIN` with an aliased target. An alias of a subquery is replaced.
+    pub fn join_as<T: AsStr<'a>>(self, target: T, alias: &'a str, clause: &'a str) -> Self {
+        self.push_join(JoinType::Inner, target, Some(alias), clause)
+    }
+
+    pub fn left_join_as<T: AsStr<'a>>(self, target: T, alias: &'a str, clause: &'a str) -> Self {
+        self.push_join(JoinType::Left, target, Some(alias), clause)
+    }
+
+    /// Removes all joins.
+    pub fn clear_joins(mut self) -> Self {
+        self.joins.clear();
+        self
+    }
+
+    /// Specifies `ORDER BY` clause (MySQL, SQLite). It's rendered for every dialect, since
+    /// dropping it along with `LIMIT` would update more rows; `validate` reports PostgreSQL.
+    pub fn order_by<T: IntoSortKey<'a>>(mut self, field: T, ordering: Ordering<'a>) -> Self {
+        let mut item = OrderBy::new(field);
+        item.ordering = ordering;
+        self.order_by.push(item);
+        self
+    }
+
+    /// Removes `ORDER BY` clause.
+    pub fn clear_order_by(mut self) -> Self {
+        self.order_by.clear();
+        self
+    }
+
+    /// Specifies `LIMIT` clause (MySQL, SQLite), see `order_by` about PostgreSQL.
+    pub fn limit(mut self, value: &'a str) -> Self {
+        self.limit = LimitType::Specified(value);
+        self
+    }
+
+    /// Removes `LIMIT` clause.
+    pub fn remove_limit(mut self) -> Self {
+        self.limit = LimitType::Empty;
+        self
+    }
+
     /// Specifies `WHERE` clause. Can take either `&str` or `Where`.
     ///
+    /// Unlike `Select`, a filter made only of empty groups isn't dropped: it's rendered as
+    /// `WHERE TRUE`, so an update of every row is visible in the generated SQL.
+    ///
     /// # Example
     ///
     /// ```
-    /// use lithium::{Update, Where};
+    /// use lithium::{ToSQL, Update, Where};
     /// let where_cl = Where::with_or().expr("a > 2").expr("b < 3");
     /// let update = Update::new("foo").set("a = 2").filter(where_cl).filter("c > 4");
     /// let expected = "UPDATE foo SET a = 2 WHERE (a > 2 OR b < 3) AND c > 4".to_string();
     /// assert_eq!(update.to_sql(), expected);
+    ///
+    /// let update = Update::new("foo").set("a = 2").filter(Where::with_and());
+    /// assert_eq!(update.to_sql(), "UPDATE foo SET a = 2 WHERE TRUE");
     /// ```
     pub fn filter<T: IntoWhereType<'a>>(mut self, expr: T) -> Self {
         self.where_cl.push(expr.into_where_type());
         self
     }
 
+    /// Specifies `WHERE` clause only if `condition` is `true`.
+    pub fn filter_if<T: IntoWhereType<'a>>(self, condition: bool, expr: T) -> Self {
+        if condition {
+            self.filter(expr)
+        } else {
+            self
+        }
+    }
+
+    /// Specifies `WHERE` clause only if there is one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Update};
+    /// let id: Option<u32> = Some(10);
+    /// let update = Update::new("foo").set("a = 2").filter_opt(id.map(|_| "id = $1"));
+    /// assert_eq!(update.to_sql(), "UPDATE foo SET a = 2 WHERE id = $1".to_string());
+    /// ```
+    pub fn filter_opt<T: IntoWhereType<'a>>(self, expr: Option<T>) -> Self {
+        match expr {
+            Some(expr) => self.filter(expr),
+            None => self
+        }
+    }
+
+    /// Specifies `WHERE` clause returned by the closure, if any.
+    pub fn filter_with<T: IntoWhereType<'a>, F: FnOnce() -> Option<T>>(self, f: F) -> Self {
+        self.filter_opt(f())
+    }
+
+    /// Removes `WHERE` clause.
+    pub fn clear_filters(mut self) -> Self {
+        self.where_cl.clear();
+        self
+    }
+
     /// Specifies `RETURNING` clause. Will result in `UPDATE ... RETURNING *`
     pub fn returning_all(mut self) -> Self {
         self.returning = Returning::All;
@@ -112,7 +507,7 @@ impl<'a> Update<'a> {
     /// # Example
     ///
     /// ```
-    /// use lithium::Update;
+    /// use lithium::{ToSQL, Update};
     /// let query = Update::new("test_table").set("a = 2").returning("a").returning(&["b", "c"]);
     /// let expected = "UPDATE test_table SET a = 2 RETURNING a, b, c".to_string();
     /// assert_eq!(query.to_sql(), expected);
@@ -135,32 +530,174 @@ impl<'a> Update<'a> {
         self
     }
 
-    /// Generates SQL.
-    pub fn to_sql(&self) -> String {
+    /// Returns target table.
+    pub fn get_table(&self) -> &'a str {
+        self.table
+    }
+
+    /// Returns update expressions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::Update;
+    /// let query = Update::new("foo").set("a = 1").filter("b = 2");
+    /// assert_eq!(query.get_expressions()[0].to_sql(), "a = 1");
+    /// assert_eq!(query.get_where().len(), 1);
+    /// ```
+    pub fn get_expressions(&self) -> &[Assignment<'a>] {
+        &self.expressions
+    }
+
+    /// Returns `FROM` clause.
+    pub fn get_from(&self) -> &FromType<'a> {
+        &self.from
+    }
+
+    /// Returns joins in the order they were specified.
+    pub fn get_joins(&self) -> &[Join<'a>] {
+        &self.joins
+    }
+
+    /// Returns `WHERE` clauses. They are joined with `AND`.
+    pub fn get_where(&self) -> &[WhereType<'a>] {
+        &self.where_cl
+    }
+
+    /// Returns `ORDER BY` items.
+    pub fn get_order_by(&self) -> &[OrderBy<'a>] {
+        &self.order_by
+    }
+
+    /// Returns `LIMIT` clause.
+    pub fn get_limit(&self) -> &LimitType<'a> {
+        &self.limit
+    }
+
+    /// Returns `RETURNING` clause.
+    pub fn get_returning(&self) -> &Returning<'a> {
+        &self.returning
+    }
+
+    /// Checks that the query can be run in the dialect: joins have `FROM` clause to attach to
+    /// outside of MySQL and PostgreSQL gets neither `ORDER BY` nor `LIMIT`. MySQL doesn't allow
+    /// them either when it updates several tables (joins or `FROM` items), and it supports
+    /// neither row assignments nor `RETURNING`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{Update, Dialect};
+    /// use lithium::update::UpdateError;
+    ///
+    /// let query = Update::new("items").join("orders", "orders.id = items.order_id").set("a = 1");
+    /// assert!(query.validate(Dialect::MySQL).is_ok());
+    /// assert_eq!(query.validate(Dialect::SQLite), Err(UpdateError::JoinWithoutFrom));
+    ///
+    /// let query = query.limit("100");
+    /// assert_eq!(query.validate(Dialect::MySQL), Err(UpdateError::Unsupported { clause: "LIMIT", dialect: Dialect::MySQL }));
+    ///
+    /// let query = query.clear_joins();
+    /// assert!(query.validate(Dialect::MySQL).is_ok());
+    /// assert_eq!(query.validate(Dialect::PostgreSQL), Err(UpdateError::Unsupported { clause: "LIMIT", dialect: Dialect::PostgreSQL }));
+    /// ```
+    pub fn validate(&self, dialect: Dialect) -> Result<(), UpdateError> {
+        if dialect != Dialect::MySQL && !self.joins.is_empty() && self.from == FromType::Empty {
+            return Err(UpdateError::JoinWithoutFrom);
+        }
+        let is_multiple = !self.joins.is_empty() || self.from != FromType::Empty;
+        if dialect == Dialect::PostgreSQL || (dialect == Dialect::MySQL && is_multiple) {
+            if !self.order_by.is_empty() {
+                return Err(UpdateError::Unsupported { clause: "ORDER BY", dialect: dialect });
+            }
+            if self.limit != LimitType::Empty {
+                return Err(UpdateError::Unsupported { clause: "LIMIT", dialect: dialect });
+            }
+        }
+        if dialect == Dialect::MySQL {
+            if self.expressions.iter().any(|x| matches!(*x, Assignment::Row { .. })) {
+                return Err(UpdateError::Unsupported { clause: "row assignment", dialect: dialect });
+            }
+            if self.returning != Returning::Empty {
+                return Err(UpdateError::Unsupported { clause: "RETURNING", dialect: dialect });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ToSQL for Update<'a> {
+    fn to_sql(&self) -> String {
+        self.to_sql_for(Dialect::PostgreSQL)
+    }
+
+    /// Generates SQL for the given dialect. MySQL gets tables of `FROM` clause and joins
+    /// right after the target table, as in `UPDATE a, b JOIN c ON ... SET ...`. Other dialects
+    /// get joins there only when there is no `FROM` clause, see `validate`.
+    fn to_sql_for(&self, dialect: Dialect) -> String {
         let mut rv = String::new();
         rv.push_str("UPDATE");
         rv.push(' ');
         rv.push_str(self.table);
+
+        let joins = self.joins.iter().map(|x| x.to_sql()).collect::<Vec<_>>();
+        // joins without `FROM` aren't dropped, the database rejects them instead
+        if dialect == Dialect::MySQL || self.from == FromType::Empty {
+            if self.from != FromType::Empty {
+                rv.push_str(", ");
+                rv.push_str(&self.from.to_sql());
+            }
+            for join in &joins {
+                rv.push(' ');
+                rv.push_str(join);
+            }
+        }
+
         rv.push(' ');
         rv.push_str("SET");
         rv.push(' ');
-        rv.push_str(&self.expressions.join(", "));
+        rv.push_str(&self.expressions.iter().map(|x| x.to_sql_for(dialect)).collect::<Vec<_>>().join(", "));
 
-        if let FromType::Specified(table) = self.from {
+        if dialect != Dialect::MySQL && self.from != FromType::Empty {
             rv.push(' ');
             rv.push_str("FROM");
             rv.push(' ');
-            rv.push_str(table);
+            rv.push_str(&self.from.to_sql());
+            for join in &joins {
+                rv.push(' ');
+                rv.push_str(join);
+            }
         }
 
         if !self.where_cl.is_empty() {
-           rv.push(' ');
-           rv.push_str("WHERE");
-           rv.push(' ');
-           rv.push_str(&self.where_cl.iter()
-                       .map(|x| x.to_sql())
-                       .collect::<Vec<_>>()
-                       .join(" AND "));
+            let clause = conjunction(&self.where_cl).unwrap_or_else(|| "TRUE".to_string());
+            rv.push(' ');
+            rv.push_str("WHERE");
+            rv.push(' ');
+            rv.push_str(&clause);
+        }
+
+        if !self.order_by.is_empty() {
+            rv.push(' ');
+            rv.push_str("ORDER BY");
+            rv.push(' ');
+            rv.push_str(&self.order_by.iter().map(|x| x.to_sql()).collect::<Vec<_>>().join(", "));
+        }
+
+        match self.limit {
+            LimitType::Empty => {},
+            LimitType::Specified(clause) => {
+                rv.push(' ');
+                rv.push_str("LIMIT");
+                rv.push(' ');
+                rv.push_str(clause);
+            },
+            LimitType::Number(value) => {
+                rv.push(' ');
+                rv.push_str("LIMIT");
+                rv.push(' ');
+                rv.push_str(&value.to_string());
+            }
         }
 
         match self.returning {
@@ -179,12 +716,48 @@ impl<'a> Update<'a> {
     }
 }
 
+impl<'a> Rewrite<'a> for Update<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        visitor.visit_update(self);
+        visitor.visit_table(&mut self.table);
+
+        match self.from {
+            FromType::Empty => {},
+            FromType::Specified(ref mut table) | FromType::Aliased { ref mut table, .. } => {
+                visitor.visit_table(table);
+            },
+            FromType::Multiple(ref mut items) => {
+                for &mut (ref mut table, _) in items {
+                    visitor.visit_table(table);
+                }
+            }
+        }
+
+        for join in &mut self.joins {
+            join.walk(visitor);
+        }
+
+        for assignment in &mut self.expressions {
+            assignment.walk(visitor);
+        }
+
+        for clause in &mut self.where_cl {
+            clause.walk(visitor);
+        }
+
+        for item in &mut self.order_by {
+            item.walk(visitor);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{FromType, Returning, Update};
+    use super::{FromType, Returning, Update, UpdateError, Assignment};
     use common::ToSQL;
     use where_cl::{Where, IntoWhereType};
-    use select::Select;
+    use select::{Select, LimitType, Ordering};
+    use dialect::Dialect;
 
     #[test]
     fn smoke_test_builder() {
@@ -204,9 +777,12 @@ mod tests {
     fn test_simple() {
         let update = Update {
             table: "test_table",
-            expressions: vec!["a = 2", "b = 3"],
+            expressions: vec![Assignment::Simple("a = 2"), Assignment::Simple("b = 3")],
             from: FromType::Empty,
+            joins: vec![],
             where_cl: vec![],
+            order_by: vec![],
+            limit: LimitType::Empty,
             returning: Returning::Empty
         };
 
@@ -225,9 +801,12 @@ mod tests {
     fn test_returning_all() {
         let update = Update {
             table: "test_table",
-            expressions: vec!["a = 2", "b = 3"],
+            expressions: vec![Assignment::Simple("a = 2"), Assignment::Simple("b = 3")],
             from: FromType::Specified("other_test_table"),
+            joins: vec![],
             where_cl: vec!["d == 3".into_where_type()],
+            order_by: vec![],
+            limit: LimitType::Empty,
             returning: Returning::All
         };
 
@@ -257,9 +836,12 @@ mod tests {
 
         let update = Update {
             table: "test_table",
-            expressions: vec!["a = 2", "b = 3"],
+            expressions: vec![Assignment::Simple("a = 2"), Assignment::Simple("b = 3")],
             from: FromType::Empty,
+            joins: vec![],
             where_cl: vec![where_cl.clone().into_where_type()],
+            order_by: vec![],
+            limit: LimitType::Empty,
             returning: Returning::Specified(vec!["a", "b"])
         };
 
@@ -290,5 +872,183 @@ mod tests {
             "UPDATE test_table SET foo = bar FROM (SELECT * FROM blah_table) AS alias".to_string()
         };
         assert_eq!(update.to_sql(), expected);
+        assert!(*update.get_from() == FromType::Aliased { table: "(SELECT * FROM blah_table)", alias: "alias" });
+
+        let update = update.from_as(&subquery, "other");
+        let expected = {
+            "UPDATE test_table SET foo = bar FROM (SELECT * FROM blah_table) AS other".to_string()
+        };
+        assert_eq!(update.to_sql(), expected);
+    }
+
+    #[test]
+    fn test_conditional_filters() {
+        let update = Update::new("test_table")
+            .set("a = 2")
+            .filter_if(false, "b == 3")
+            .filter_opt(None::<&str>)
+            .filter_with(|| Some(Where::with_and()));
+        assert_eq!(update.to_sql(), "UPDATE test_table SET a = 2 WHERE TRUE".to_string());
+
+        let update = update
+            .filter_if(true, "b == 3")
+            .filter_opt(Some("c == 4"))
+            .filter_with(|| Some(Where::with_or().expr("d == 5")));
+        assert_eq!(update.to_sql(), "UPDATE test_table SET a = 2 WHERE b == 3 AND c == 4 AND (d == 5)".to_string());
+    }
+
+    #[test]
+    fn test_getters_and_mutators() {
+        let update = Update::new("test_table")
+            .set(&["a = 2", "b = 3"])
+            .from("other_test_table")
+            .filter("d == 3")
+            .returning("a");
+
+        assert_eq!(update.get_table(), "test_table");
+        assert!(update.get_expressions() == [Assignment::Simple("a = 2"), Assignment::Simple("b = 3")]);
+        assert!(*update.get_from() == FromType::Specified("other_test_table"));
+        assert!(update.get_where() == ["d == 3".into_where_type()]);
+        assert!(*update.get_returning() == Returning::Specified(vec!["a"]));
+
+        let built = update.replace_set("c = 4").clear_filters().remove_from().empty_returning();
+        assert_eq!(built.to_sql(), "UPDATE test_table SET c = 4".to_string());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let update = Update::new("test_table")
+            .set("a = 2")
+            .from("other_test_table")
+            .filter(Where::not(Where::is_null("b")))
+            .returning_all();
+
+        let json = ::serde_json::to_string(&update).unwrap();
+        let restored: Update = ::serde_json::from_str(&json).unwrap();
+        assert!(restored == update);
+        let expected = "UPDATE test_table SET a = 2, c = 3 FROM other_test_table WHERE NOT (b IS NULL) RETURNING *";
+        assert_eq!(restored.set("c = 3").to_sql(), expected.to_string());
+    }
+
+    #[test]
+    fn test_set_value() {
+        let update = Update::new("test_table")
+            .set("a = 2")
+            .set_value("b", "3")
+            .set_value("c", "b + 1".to_string());
+        assert_eq!(update.to_sql(), "UPDATE test_table SET a = 2, b = 3, c = b + 1".to_string());
+        assert_eq!(update.get_expressions()[1].to_sql(), "b = 3");
+
+        let update = update.replace_set("d = 4");
+        assert_eq!(update.to_sql(), "UPDATE test_table SET d = 4".to_string());
+    }
+
+    #[test]
+    fn test_row_and_default() {
+        let query = Select::from("other_test_table").columns(&["a", "b"]).filter("id = test_table.id");
+        let update = Update::new("test_table")
+            .set_row(&["a", "b"], query.clone())
+            .set_default("c")
+            .set("d = 1");
+        let expected = {
+            "UPDATE test_table SET (a, b) = \
+            (SELECT a, b FROM other_test_table WHERE id = test_table.id), c = DEFAULT, d = 1"
+        };
+        assert_eq!(update.to_sql(), expected);
+        assert!(update.get_expressions()[0] == Assignment::Row { columns: vec!["a", "b"], query: Box::new(query) });
+        assert_eq!(update.get_expressions()[0].columns(), vec!["a", "b"]);
+        assert_eq!(update.get_expressions()[1].columns(), vec!["c"]);
+        assert!(update.get_expressions()[2].columns().is_empty());
+    }
+
+    #[test]
+    fn test_from_and_joins() {
+        let subquery = Select::from("fizz").as_subquery().with_alias("f");
+        let update = Update::new("test_table")
+            .set("a = b.a")
+            .add_from_as("other_test_table", "b")
+            .add_from(&subquery)
+            .left_join("c", "c.id = b.id")
+            .filter("b.id = test_table.id");
+        assert!(*update.get_from() == FromType::Multiple(vec![
+            ("other_test_table", Some("b")),
+            ("(SELECT * FROM fizz)", Some("f"))
+        ]));
+
+        let expected = {
+            "UPDATE test_table SET a = b.a \
+            FROM other_test_table AS b, (SELECT * FROM fizz) AS f \
+            LEFT JOIN c ON c.id = b.id \
+            WHERE b.id = test_table.id"
+        };
+        assert_eq!(update.to_sql(), expected);
+        assert_eq!(update.to_sql_for(Dialect::SQLite), expected);
+
+        let expected = {
+            "UPDATE test_table, other_test_table AS b, (SELECT * FROM fizz) AS f \
+            LEFT JOIN c ON c.id = b.id \
+            SET a = b.a \
+            WHERE b.id = test_table.id"
+        };
+        assert_eq!(update.to_sql_for(Dialect::MySQL), expected);
+
+        let update = update.clear_joins().from("x").add_from("y");
+        assert_eq!(update.get_joins().len(), 0);
+        assert_eq!(update.to_sql(), "UPDATE test_table SET a = b.a FROM x, y WHERE b.id = test_table.id");
+    }
+
+    #[test]
+    fn test_mysql_order_by_and_limit() {
+        let update = Update::new("test_table")
+            .set_value("a", "1")
+            .order_by("test_table.id", Ordering::Descending)
+            .limit("10");
+        assert_eq!(update.to_sql_for(Dialect::MySQL), "UPDATE test_table SET a = 1 ORDER BY test_table.id DESC LIMIT 10");
+        assert!(*update.get_limit() == LimitType::Specified("10"));
+        assert_eq!(update.get_order_by().len(), 1);
+        assert!(update.validate(Dialect::MySQL).is_ok());
+        assert!(update.validate(Dialect::SQLite).is_ok());
+
+        // MySQL rejects both clauses in multiple-table `UPDATE`
+        let update = update.join_as("other_test_table", "b", "b.id = test_table.id");
+        let expected = {
+            "UPDATE test_table INNER JOIN other_test_table AS b ON b.id = test_table.id \
+            SET a = 1 ORDER BY test_table.id DESC LIMIT 10"
+        };
+        assert_eq!(update.to_sql_for(Dialect::MySQL), expected);
+        assert_eq!(update.to_sql(), expected);
+        let error = update.validate(Dialect::MySQL).unwrap_err();
+        assert_eq!(error, UpdateError::Unsupported { clause: "ORDER BY", dialect: Dialect::MySQL });
+        assert_eq!(update.validate(Dialect::PostgreSQL), Err(UpdateError::JoinWithoutFrom));
+
+        let update = update.clear_order_by();
+        assert_eq!(update.validate(Dialect::MySQL), Err(UpdateError::Unsupported { clause: "LIMIT", dialect: Dialect::MySQL }));
+        let update = update.clear_joins().from("c");
+        assert_eq!(update.validate(Dialect::MySQL), Err(UpdateError::Unsupported { clause: "LIMIT", dialect: Dialect::MySQL }));
+
+        let update = update.order_by("test_table.id", Ordering::Ascending);
+        let error = update.validate(Dialect::PostgreSQL).unwrap_err();
+        assert_eq!(error, UpdateError::Unsupported { clause: "ORDER BY", dialect: Dialect::PostgreSQL });
+        assert_eq!(error.to_string(), "ORDER BY isn't supported by PostgreSQL in UPDATE");
+        assert!(update.validate(Dialect::SQLite).is_ok());
+
+        let update = update.clear_order_by().remove_limit().remove_from();
+        assert_eq!(update.to_sql_for(Dialect::MySQL), "UPDATE test_table SET a = 1");
+        assert!(update.validate(Dialect::PostgreSQL).is_ok());
+    }
+
+    #[test]
+    fn test_mysql_unsupported() {
+        let update = Update::new("test_table").set_row(&["a", "b"], Select::from("other_test_table").columns(&["a", "b"]));
+        assert!(update.validate(Dialect::PostgreSQL).is_ok());
+        let error = update.validate(Dialect::MySQL).unwrap_err();
+        assert_eq!(error, UpdateError::Unsupported { clause: "row assignment", dialect: Dialect::MySQL });
+        assert_eq!(error.to_string(), "row assignment isn't supported by MySQL in UPDATE");
+
+        let update = Update::new("test_table").set("a = 1").returning("a");
+        assert!(update.validate(Dialect::SQLite).is_ok());
+        assert_eq!(update.validate(Dialect::MySQL), Err(UpdateError::Unsupported { clause: "RETURNING", dialect: Dialect::MySQL }));
+        assert!(update.empty_returning().validate(Dialect::MySQL).is_ok());
     }
 }
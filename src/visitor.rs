@@ -0,0 +1,266 @@
+//! Keeps query visitor stuff, which is used to inspect or rewrite built queries.
+//!
+//! Implement `Visitor` with the hooks you need and pass it to `rewrite` along with a query.
+//! Nodes are visited parent first, so a hook sees changes made by the hooks of its parents.
+//! Keep in mind that `Subquery` keeps already generated SQL, so queries inside of it can't
+//! be visited - rewrite a `Select` before calling `as_subquery`. The same goes for anything
+//! passed as a string: `GROUP BY` expressions, conditions built with `Function::compare` and
+//! `CASE` used as a condition. `Function` and `Case` used as columns, sort keys or `UPDATE`
+//! and `MERGE` values are walked, including their `FILTER` clauses and conditions.
+//!
+//! # Example
+//!
+//! ```
+//! use lithium::{ToSQL, Select, Update};
+//! use lithium::visitor::{Visitor, rewrite};
+//!
+//! struct Sharding;
+//!
+//! impl<'a> Visitor<'a> for Sharding {
+//!     fn visit_table(&mut self, table: &mut &'a str) {
+//!         if *table == "users" {
+//!             *table = "users_07";
+//!         }
+//!     }
+//! }
+//!
+//! let mut query = Select::from("users").join("orders", "orders.user_id = users.id");
+//! rewrite(&mut query, &mut Sharding);
+//! let expected = "SELECT * FROM users_07 INNER JOIN orders ON orders.user_id = users.id";
+//! assert_eq!(query.to_sql(), expected);
+//!
+//! let mut update = Update::new("users").set("a = 1");
+//! rewrite(&mut update, &mut Sharding);
+//! assert_eq!(update.to_sql(), "UPDATE users_07 SET a = 1");
+//! ```
+
+use select::{Select, Join};
+use where_cl::{Where, WhereType};
+use insert::Insert;
+use update::Update;
+use merge::Merge;
+
+/// Hooks called for every node of a query. Every hook does nothing by default.
+pub trait Visitor<'a> {
+    /// Called for every `Select`, including ones nested into other queries.
+    fn visit_select(&mut self, _query: &mut Select<'a>) {}
+
+    /// Called for every `Insert`.
+    fn visit_insert(&mut self, _query: &mut Insert<'a>) {}
+
+    /// Called for every `Update`.
+    fn visit_update(&mut self, _query: &mut Update<'a>) {}
+
+    /// Called for every `Merge`.
+    fn visit_merge(&mut self, _query: &mut Merge<'a>) {}
+
+    /// Called for every join of a `Select`.
+    fn visit_join(&mut self, _join: &mut Join<'a>) {}
+
+    /// Called for every expression of `WHERE` and `HAVING` clauses, including nested ones.
+    fn visit_clause(&mut self, _clause: &mut WhereType<'a>) {}
+
+    /// Called for every group of expressions in `WHERE` and `HAVING` clauses.
+    fn visit_where(&mut self, _clause: &mut Where<'a>) {}
+
+    /// Called for every table a query refers to: `FROM` and join targets of `Select`,
+    /// tables of its locking clauses (`FOR UPDATE OF ...`), `INSERT` and `UPDATE` targets
+    /// and `UPDATE ... FROM`. Locking clauses name tables as `FROM` does, so a renamed table
+    /// has to be renamed there too, while an alias is passed as is.
+    fn visit_table(&mut self, _table: &mut &'a str) {}
+}
+
+/// Implemented by nodes which can be walked by a `Visitor`.
+pub trait Rewrite<'a> {
+    /// Calls hooks of the visitor for this node and then walks its children.
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V);
+}
+
+/// Walks the query calling hooks of the visitor for every node.
+pub fn rewrite<'a, Q: Rewrite<'a>, V: Visitor<'a>>(query: &mut Q, visitor: &mut V) {
+    query.walk(visitor);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Visitor, rewrite};
+    use common::ToSQL;
+    use select::{Select, Join, UnionMode, Union, Ordering, For};
+    use where_cl::{Where, WhereType};
+    use insert::Insert;
+    use update::Update;
+    use function::Function;
+    use case::Case;
+
+    struct Tenant;
+
+    impl<'a> Visitor<'a> for Tenant {
+        fn visit_select(&mut self, query: &mut Select<'a>) {
+            *query = query.clone().filter("tenant_id = $1");
+        }
+
+        fn visit_update(&mut self, query: &mut Update<'a>) {
+            *query = query.clone().filter("tenant_id = $1");
+        }
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        selects: usize,
+        inserts: usize,
+        updates: usize,
+        joins: usize,
+        clauses: usize,
+        groups: usize,
+        tables: Vec<String>
+    }
+
+    impl<'a> Visitor<'a> for Counter {
+        fn visit_select(&mut self, _query: &mut Select<'a>) {
+            self.selects += 1;
+        }
+
+        fn visit_insert(&mut self, _query: &mut Insert<'a>) {
+            self.inserts += 1;
+        }
+
+        fn visit_update(&mut self, _query: &mut Update<'a>) {
+            self.updates += 1;
+        }
+
+        fn visit_join(&mut self, _join: &mut Join<'a>) {
+            self.joins += 1;
+        }
+
+        fn visit_clause(&mut self, _clause: &mut WhereType<'a>) {
+            self.clauses += 1;
+        }
+
+        fn visit_where(&mut self, _clause: &mut Where<'a>) {
+            self.groups += 1;
+        }
+
+        fn visit_table(&mut self, table: &mut &'a str) {
+            self.tables.push(table.to_string());
+        }
+    }
+
+    #[test]
+    fn test_counter() {
+        let mut query = Select::from("foo")
+            .join("bar", "bar.a = foo.a")
+            .filter(Where::with_or().expr("a = 1").expr(Where::with_and().expr("b = 2")))
+            .having("COUNT(*) > 1");
+
+        let mut counter = Counter::default();
+        rewrite(&mut query, &mut counter);
+
+        assert_eq!(counter.selects, 1);
+        assert_eq!(counter.joins, 1);
+        assert_eq!(counter.clauses, 5);
+        assert_eq!(counter.groups, 2);
+        assert_eq!(counter.tables, vec!["foo", "bar"]);
+    }
+
+    #[test]
+    fn test_nested_queries() {
+        let mut query = Select::from("foo")
+            .filter(Where::not(Where::exists(Select::from("bar"))))
+            .filter(Where::in_query("a", Select::from("bazz").columns("a")));
+        rewrite(&mut query, &mut Tenant);
+
+        let expected = {
+            "SELECT * FROM foo WHERE \
+            NOT (EXISTS (SELECT * FROM bar WHERE tenant_id = $1)) AND \
+            a IN (SELECT a FROM bazz WHERE tenant_id = $1) AND \
+            tenant_id = $1"
+        };
+        assert_eq!(query.to_sql(), expected);
+    }
+
+    #[test]
+    fn test_expressions() {
+        let active = Function::count_all().filter(Where::exists(Select::from("bar"))).alias("n");
+        let rank = Case::new().when(Where::exists(Select::from("bazz")), "1").otherwise("2");
+        let mut query = Select::from("foo").column(active).order_by(rank, Ordering::Ascending);
+        rewrite(&mut query, &mut Tenant);
+
+        let expected = {
+            "SELECT COUNT(*) FILTER (WHERE EXISTS (SELECT * FROM bar WHERE tenant_id = $1)) AS n \
+            FROM foo WHERE tenant_id = $1 \
+            ORDER BY CASE WHEN EXISTS (SELECT * FROM bazz WHERE tenant_id = $1) THEN 1 ELSE 2 END ASC"
+        };
+        assert_eq!(query.to_sql(), expected);
+
+        let mut update = Update::new("foo").set_value("a", Case::new().when(Where::exists(Select::from("bar")), "1"));
+        let mut counter = Counter::default();
+        rewrite(&mut update, &mut counter);
+        assert_eq!(counter.selects, 1);
+        assert_eq!(counter.tables, vec!["foo", "bar"]);
+    }
+
+    #[test]
+    fn test_insert_with_query() {
+        let mut query = Insert::into("foo").query(Select::from("bar"));
+        let mut counter = Counter::default();
+        rewrite(&mut query, &mut counter);
+
+        assert_eq!(counter.inserts, 1);
+        assert_eq!(counter.selects, 1);
+        assert_eq!(counter.tables, vec!["foo", "bar"]);
+    }
+
+    #[test]
+    fn test_tenant() {
+        let mut query = Select::from("foo").filter("a = 1");
+        rewrite(&mut query, &mut Tenant);
+        assert_eq!(query.to_sql(), "SELECT * FROM foo WHERE a = 1 AND tenant_id = $1");
+
+        let mut update = Update::new("foo").set("a = 1").from("bar");
+        rewrite(&mut update, &mut Tenant);
+        assert_eq!(update.to_sql(), "UPDATE foo SET a = 1 FROM bar WHERE tenant_id = $1");
+
+        let mut counter = Counter::default();
+        rewrite(&mut update, &mut counter);
+        assert_eq!(counter.updates, 1);
+        assert_eq!(counter.tables, vec!["foo", "bar"]);
+    }
+
+    #[test]
+    fn test_locking_clauses() {
+        struct Shard;
+
+        impl<'a> Visitor<'a> for Shard {
+            fn visit_table(&mut self, table: &mut &'a str) {
+                if *table == "users" {
+                    *table = "users_07";
+                }
+            }
+        }
+
+        let mut query = Select::from("users")
+            .join("orders", "orders.user_id = users.id")
+            .add_for(For::update().table("users"))
+            .add_for(For::share().table(&["orders", "users"]));
+        rewrite(&mut query, &mut Shard);
+
+        let expected = {
+            "SELECT * FROM users_07 INNER JOIN orders ON orders.user_id = users.id \
+            FOR UPDATE OF users_07 FOR SHARE OF orders, users_07"
+        };
+        assert_eq!(query.to_sql(), expected);
+    }
+
+    #[test]
+    fn test_union() {
+        let mut union = Union::new(UnionMode::All, Select::from("foo"), Select::from("bar"));
+        rewrite(&mut union, &mut Tenant);
+
+        let expected = {
+            "SELECT * FROM foo WHERE tenant_id = $1 \
+            UNION ALL \
+            SELECT * FROM bar WHERE tenant_id = $1"
+        };
+        assert_eq!(union.to_sql(), expected);
+    }
+}
@@ -0,0 +1,681 @@
+//! Keeps `WHERE` related stuff.
+
+pub mod normalize;
+
+use common::{ToSQL, Subquery, Pusheable};
+use select::Select;
+use visitor::{Visitor, Rewrite};
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Operator {
+    And,
+    Or
+}
+
+impl Operator {
+    pub fn to_sql(&self) -> &str {
+        match *self {
+            Operator::And => "AND",
+            Operator::Or => "OR"
+        }
+    }
+
+    /// Value which doesn't change the result when joined with this operator:
+    /// `TRUE` for `AND` and `FALSE` for `OR`.
+    pub fn identity(&self) -> bool {
+        match *self {
+            Operator::And => true,
+            Operator::Or => false
+        }
+    }
+
+    fn constant(value: bool) -> &'static str {
+        if value { "TRUE" } else { "FALSE" }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum WhereType<'a> {
+    Simple(&'a str),
+    Extended(Where<'a>),
+    /// Clause generated by lithium itself (e.g. keyset pagination predicates).
+    Owned(String),
+    Predicate(Predicate<'a>),
+    /// `TRUE` or `FALSE`.
+    Constant(bool),
+}
+
+/// Query used inside of `IN (...)` and `EXISTS (...)`.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Nested<'a> {
+    Select(Box<Select<'a>>),
+    /// Already generated SQL of a `Subquery`, including parentheses.
+    Subquery(&'a str)
+}
+
+impl<'a> Nested<'a> {
+    fn to_sql(&self) -> String {
+        match *self {
+            Nested::Select(ref query) => format!("({})", query.to_sql()),
+            Nested::Subquery(query) => query.to_string()
+        }
+    }
+}
+
+/// Is used to receive either `Select` or `&Subquery` for `IN` and `EXISTS`.
+pub trait IntoNested<'a> {
+    fn into_nested(self) -> Nested<'a>;
+}
+
+impl<'a> IntoNested<'a> for Select<'a> {
+    fn into_nested(self) -> Nested<'a> {
+        Nested::Select(Box::new(self))
+    }
+}
+
+impl<'a> IntoNested<'a> for &'a Subquery<'a> {
+    fn into_nested(self) -> Nested<'a> {
+        Nested::Subquery(&self.query)
+    }
+}
+
+/// Predicates which are built with helpers of `Where`, e.g. `Where::not` or `Where::in_list`.
+/// Every one of them keeps `negated` flag which results in `NOT IN`, `IS NOT NULL` and so on.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub enum Predicate<'a> {
+    Not(Box<WhereType<'a>>),
+    In {
+        expression: &'a str,
+        values: Vec<&'a str>,
+        negated: bool
+    },
+    InQuery {
+        expression: &'a str,
+        query: Nested<'a>,
+        negated: bool
+    },
+    Exists {
+        query: Nested<'a>,
+        negated: bool
+    },
+    Between {
+        expression: &'a str,
+        low: &'a str,
+        high: &'a str,
+        negated: bool
+    },
+    IsNull {
+        expression: &'a str,
+        negated: bool
+    },
+    IsDistinctFrom {
+        left: &'a str,
+        right: &'a str,
+        negated: bool
+    }
+}
+
+impl<'a> ToSQL for Predicate<'a> {
+    fn to_sql(&self) -> String {
+        let not = |negated: bool| if negated { "NOT " } else { "" };
+        match *self {
+            Predicate::Not(ref clause) => match **clause {
+                WhereType::Extended(ref clause) => format!("NOT {}", clause.to_sql()),
+                ref clause => format!("NOT ({})", clause.to_sql())
+            },
+            Predicate::In { expression, ref values, negated } => {
+                if values.is_empty() {
+                    // `IN ()` is not valid SQL
+                    return Operator::constant(negated).to_string();
+                }
+                format!("{} {}IN ({})", expression, not(negated), values.join(", "))
+            },
+            Predicate::InQuery { expression, ref query, negated } => {
+                format!("{} {}IN {}", expression, not(negated), query.to_sql())
+            },
+            Predicate::Exists { ref query, negated } => {
+                format!("{}EXISTS {}", not(negated), query.to_sql())
+            },
+            Predicate::Between { expression, low, high, negated } => {
+                format!("{} {}BETWEEN {} AND {}", expression, not(negated), low, high)
+            },
+            Predicate::IsNull { expression, negated } => {
+                format!("{} IS {}NULL", expression, not(negated))
+            },
+            Predicate::IsDistinctFrom { left, right, negated } => {
+                format!("{} IS {}DISTINCT FROM {}", left, not(negated), right)
+            }
+        }
+    }
+}
+
+
+/// Represents `WHERE` clause which is widely used in different queries.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound(deserialize = "'de: 'a")))]
+pub struct Where<'a> {
+    /// Operator which will be used to join expressions
+    pub operator: Operator,
+    expressions: Vec<WhereType<'a>>,
+}
+
+impl<'a> Where<'a> {
+    /// Method to start with.
+    pub fn new(operator: Operator) -> Self {
+        Where {
+            operator: operator,
+            expressions: vec![]
+        }
+    }
+    
+    /// Just an alias for `new` with pre-defined `AND` operator.
+    pub fn with_and() -> Self {
+        Self::new(Operator::And)
+    }
+
+    /// Just an alias for `new` with pre-defined `OR` operator.
+    pub fn with_or() -> Self {
+        Self::new(Operator::Or)
+    }
+
+    /// Specifies clause. Can receive either `&str` or `Where`.
+    pub fn expr<T: IntoWhereType<'a>>(mut self, expression: T) -> Self {
+        self.expressions.push(expression.into_where_type());
+        self
+    }
+
+    /// Specifies clause only if `condition` is `true`.
+    pub fn expr_if<T: IntoWhereType<'a>>(self, condition: bool, expression: T) -> Self {
+        if condition {
+            self.expr(expression)
+        } else {
+            self
+        }
+    }
+
+    /// Specifies clause only if there is one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Where};
+    ///
+    /// let name: Option<&str> = None;
+    /// let clause = Where::with_or()
+    ///     .expr_opt(name.map(|_| "name = $1"))
+    ///     .expr_opt(Some("email = $2"));
+    /// assert_eq!(clause.to_sql(), "(email = $2)".to_string());
+    /// ```
+    pub fn expr_opt<T: IntoWhereType<'a>>(self, expression: Option<T>) -> Self {
+        match expression {
+            Some(expression) => self.expr(expression),
+            None => self
+        }
+    }
+
+    /// Specifies clause returned by the closure, if any.
+    pub fn expr_with<T: IntoWhereType<'a>, F: FnOnce() -> Option<T>>(self, f: F) -> Self {
+        self.expr_opt(f())
+    }
+
+    /// Negates the clause. Results in `NOT (...)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select, Where};
+    ///
+    /// let query = Select::from("test_table")
+    ///     .filter(Where::not(Where::with_or().expr("a = 1").expr("b = 2")))
+    ///     .filter(Where::not("c = 3"));
+    /// let expected = "SELECT * FROM test_table WHERE NOT (a = 1 OR b = 2) AND NOT (c = 3)".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn not<T: IntoWhereType<'a>>(clause: T) -> Predicate<'a> {
+        Predicate::Not(Box::new(clause.into_where_type()))
+    }
+
+    /// Results in `expression IN (...)`. Can receive either `&str` or `&[&str]`.
+    /// An empty list results in `FALSE`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select, Where};
+    ///
+    /// let query = Select::from("test_table").filter(Where::in_list("id", &["$1", "$2"]));
+    /// assert_eq!(query.to_sql(), "SELECT * FROM test_table WHERE id IN ($1, $2)".to_string());
+    /// ```
+    pub fn in_list<T: Pusheable<'a>>(expression: &'a str, values: T) -> Predicate<'a> {
+        let mut list = vec![];
+        values.push_to(&mut list);
+        Predicate::In {
+            expression: expression,
+            values: list,
+            negated: false
+        }
+    }
+
+    /// Results in `expression NOT IN (...)`. An empty list results in `TRUE`.
+    pub fn not_in_list<T: Pusheable<'a>>(expression: &'a str, values: T) -> Predicate<'a> {
+        let mut list = vec![];
+        values.push_to(&mut list);
+        Predicate::In {
+            expression: expression,
+            values: list,
+            negated: true
+        }
+    }
+
+    /// Results in `expression IN (SELECT ...)`. Can receive either `Select` or `&Subquery`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select, Where};
+    ///
+    /// let banned = Select::from("bans").columns("user_id");
+    /// let query = Select::from("users").filter(Where::not_in_query("id", banned));
+    /// let expected = "SELECT * FROM users WHERE id NOT IN (SELECT user_id FROM bans)".to_string();
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn in_query<T: IntoNested<'a>>(expression: &'a str, query: T) -> Predicate<'a> {
+        Predicate::InQuery {
+            expression: expression,
+            query: query.into_nested(),
+            negated: false
+        }
+    }
+
+    /// Results in `expression NOT IN (SELECT ...)`.
+    pub fn not_in_query<T: IntoNested<'a>>(expression: &'a str, query: T) -> Predicate<'a> {
+        Predicate::InQuery {
+            expression: expression,
+            query: query.into_nested(),
+            negated: true
+        }
+    }
+
+    /// Results in `EXISTS (SELECT ...)`. Can receive either `Select` or `&Subquery`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Select, Where};
+    ///
+    /// let orders = Select::from("orders").columns("1").filter("orders.user_id = users.id");
+    /// let query = Select::from("users").filter(Where::exists(orders));
+    /// let expected = {
+    ///     "SELECT * FROM users WHERE \
+    ///     EXISTS (SELECT 1 FROM orders WHERE orders.user_id = users.id)".to_string()
+    /// };
+    /// assert_eq!(query.to_sql(), expected);
+    /// ```
+    pub fn exists<T: IntoNested<'a>>(query: T) -> Predicate<'a> {
+        Predicate::Exists {
+            query: query.into_nested(),
+            negated: false
+        }
+    }
+
+    /// Results in `NOT EXISTS (SELECT ...)`.
+    pub fn not_exists<T: IntoNested<'a>>(query: T) -> Predicate<'a> {
+        Predicate::Exists {
+            query: query.into_nested(),
+            negated: true
+        }
+    }
+
+    /// Results in `expression BETWEEN low AND high`.
+    pub fn between(expression: &'a str, low: &'a str, high: &'a str) -> Predicate<'a> {
+        Predicate::Between {
+            expression: expression,
+            low: low,
+            high: high,
+            negated: false
+        }
+    }
+
+    /// Results in `expression NOT BETWEEN low AND high`.
+    pub fn not_between(expression: &'a str, low: &'a str, high: &'a str) -> Predicate<'a> {
+        Predicate::Between {
+            expression: expression,
+            low: low,
+            high: high,
+            negated: true
+        }
+    }
+
+    /// Results in `expression IS NULL`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Where};
+    ///
+    /// let clause = Where::with_or().expr(Where::is_null("deleted")).expr(Where::is_not_null("restored"));
+    /// assert_eq!(clause.to_sql(), "(deleted IS NULL OR restored IS NOT NULL)".to_string());
+    /// ```
+    pub fn is_null(expression: &'a str) -> Predicate<'a> {
+        Predicate::IsNull {
+            expression: expression,
+            negated: false
+        }
+    }
+
+    /// Results in `expression IS NOT NULL`.
+    pub fn is_not_null(expression: &'a str) -> Predicate<'a> {
+        Predicate::IsNull {
+            expression: expression,
+            negated: true
+        }
+    }
+
+    /// Results in `left IS DISTINCT FROM right`.
+    pub fn is_distinct_from(left: &'a str, right: &'a str) -> Predicate<'a> {
+        Predicate::IsDistinctFrom {
+            left: left,
+            right: right,
+            negated: false
+        }
+    }
+
+    /// Results in `left IS NOT DISTINCT FROM right`.
+    pub fn is_not_distinct_from(left: &'a str, right: &'a str) -> Predicate<'a> {
+        Predicate::IsDistinctFrom {
+            left: left,
+            right: right,
+            negated: true
+        }
+    }
+
+    /// Returns expressions joined by `operator`.
+    pub fn get_expressions(&self) -> &[WhereType<'a>] {
+        &self.expressions
+    }
+
+    /// Checks whether the group has no expressions except for empty groups with the same
+    /// operator and constants which don't change the result. Such group is always `TRUE`
+    /// for `AND` and always `FALSE` for `OR`.
+    pub fn is_empty(&self) -> bool {
+        self.expressions.iter().all(|x| x.is_neutral(&self.operator))
+    }
+}
+
+pub trait IntoWhereType<'a> {
+    fn into_where_type(self) -> WhereType<'a>;
+}
+
+impl<'a> IntoWhereType<'a> for WhereType<'a> {
+    fn into_where_type(self) -> WhereType<'a> {
+        self
+    }
+}
+
+impl<'a> IntoWhereType<'a> for &'a str {
+    fn into_where_type(self) -> WhereType<'a> {
+        WhereType::Simple(self)
+    }
+}
+
+impl<'a> IntoWhereType<'a> for String {
+    fn into_where_type(self) -> WhereType<'a> {
+        WhereType::Owned(self)
+    }
+}
+
+impl<'a> IntoWhereType<'a> for Predicate<'a> {
+    fn into_where_type(self) -> WhereType<'a> {
+        WhereType::Predicate(self)
+    }
+}
+
+impl<'a> IntoWhereType<'a> for bool {
+    fn into_where_type(self) -> WhereType<'a> {
+        WhereType::Constant(self)
+    }
+}
+
+impl<'a> IntoWhereType<'a> for Where<'a> {
+    fn into_where_type(self) -> WhereType<'a> {
+        WhereType::Extended(self)
+    }
+}
+
+impl<'a> WhereType<'a> {
+    /// Checks whether the expression doesn't change the result when joined by `operator`.
+    fn is_neutral(&self, operator: &Operator) -> bool {
+        match *self {
+            WhereType::Extended(ref clause) => clause.operator == *operator && clause.is_empty(),
+            WhereType::Constant(value) => value == operator.identity(),
+            _ => false
+        }
+    }
+}
+
+/// Joins clauses with `AND` as it's done for `WHERE` and `HAVING` of queries. Returns `None`
+/// when there is nothing to render, e.g. when there are only empty groups.
+pub fn conjunction(clauses: &[WhereType]) -> Option<String> {
+    let clauses = clauses.iter()
+        .filter(|x| !x.is_neutral(&Operator::And))
+        .map(|x| x.to_sql())
+        .collect::<Vec<_>>();
+
+    if clauses.is_empty() {
+        None
+    } else {
+        Some(clauses.join(" AND "))
+    }
+}
+
+impl<'a> ToSQL for WhereType<'a> {
+    fn to_sql(&self) -> String {
+        match *self {
+            WhereType::Simple(clause) => clause.to_string(),
+            WhereType::Extended(ref clause) => clause.to_sql(),
+            WhereType::Owned(ref clause) => clause.clone(),
+            WhereType::Predicate(ref clause) => clause.to_sql(),
+            WhereType::Constant(value) => Operator::constant(value).to_string()
+        }
+    }
+}
+
+impl<'a> ToSQL for &'a str {
+    fn to_sql(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl<'a> Rewrite<'a> for WhereType<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        visitor.visit_clause(self);
+        match *self {
+            WhereType::Extended(ref mut clause) => clause.walk(visitor),
+            WhereType::Predicate(ref mut clause) => clause.walk(visitor),
+            WhereType::Simple(_) | WhereType::Owned(_) | WhereType::Constant(_) => {}
+        }
+    }
+}
+
+impl<'a> Rewrite<'a> for Predicate<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        match *self {
+            Predicate::Not(ref mut clause) => clause.walk(visitor),
+            Predicate::InQuery { query: Nested::Select(ref mut query), .. } |
+            Predicate::Exists { query: Nested::Select(ref mut query), .. } => query.walk(visitor),
+            _ => {}
+        }
+    }
+}
+
+impl<'a> Rewrite<'a> for Where<'a> {
+    fn walk<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        visitor.visit_where(self);
+        for expression in &mut self.expressions {
+            expression.walk(visitor);
+        }
+    }
+}
+
+impl<'a> ToSQL for Where<'a> {
+    fn to_sql(&self) -> String {
+        if self.is_empty() {
+            return Operator::constant(self.operator.identity()).to_string();
+        }
+
+        let operator = format!(" {} ", self.operator.to_sql());
+        let mut rv = String::new();
+        rv.push('(');
+        rv.push_str(&self.expressions.iter()
+                    .filter(|x| !x.is_neutral(&self.operator))
+                    .map(|x| x.to_sql())
+                    .collect::<Vec<_>>()
+                    .join(&operator));
+
+        rv.push(')');
+        rv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Operator, Where, IntoWhereType, conjunction};
+    use common::ToSQL;
+    use select::Select;
+
+    #[test]
+    fn test_operator() {
+        let and = Operator::And;
+        let or = Operator::Or;
+
+        assert_eq!(and.to_sql(), "AND");
+        assert_eq!(or.to_sql(), "OR");
+    }
+
+    #[test]
+    fn test_alone_where() {
+        let foo = Where::new(Operator::And).expr("foo = bar").expr("fizz = bazz");
+        assert_eq!(foo.to_sql(), "(foo = bar AND fizz = bazz)".to_string());
+        assert!(foo.get_expressions() == ["foo = bar".into_where_type(), "fizz = bazz".into_where_type()]);
+    }
+
+    #[test]
+    fn test_empty_where() {
+        assert_eq!(Where::with_and().to_sql(), "TRUE".to_string());
+        assert_eq!(Where::with_or().to_sql(), "FALSE".to_string());
+        assert_eq!(Where::with_and().expr(Where::with_and()).to_sql(), "TRUE".to_string());
+
+        let clause = Where::with_or().expr("a = b").expr(Where::with_or()).expr(Where::with_and());
+        assert_eq!(clause.to_sql(), "(a = b OR TRUE)".to_string());
+
+        let clause = Where::with_and().expr(Where::with_and()).expr("a = b").expr(Where::with_or());
+        assert_eq!(clause.to_sql(), "(a = b AND FALSE)".to_string());
+    }
+
+    #[test]
+    fn test_conditional_expressions() {
+        let clause = Where::with_and()
+            .expr_if(true, "a = b")
+            .expr_if(false, "c = d")
+            .expr_opt(Some("e = f"))
+            .expr_opt(None::<&str>)
+            .expr_with(|| Some(Where::with_or().expr("g = h")))
+            .expr_with(|| None::<&str>);
+        assert_eq!(clause.to_sql(), "(a = b AND e = f AND (g = h))".to_string());
+    }
+
+    #[test]
+    fn test_conjunction() {
+        assert_eq!(conjunction(&[]), None);
+        assert_eq!(conjunction(&[Where::with_and().into_where_type()]), None);
+
+        let clauses = ["a = b".into_where_type(), Where::with_and().into_where_type()];
+        assert_eq!(conjunction(&clauses), Some("a = b".to_string()));
+
+        let clauses = ["a = b".into_where_type(), Where::with_or().into_where_type()];
+        assert_eq!(conjunction(&clauses), Some("a = b AND FALSE".to_string()));
+
+        let clauses = [true.into_where_type(), "a = b".into_where_type(), false.into_where_type()];
+        assert_eq!(conjunction(&clauses), Some("a = b AND FALSE".to_string()));
+    }
+
+    #[test]
+    fn test_not() {
+        let clause = Where::with_and().expr(Where::not("a = b")).expr(Where::not(Where::with_or().expr("c")));
+        assert_eq!(clause.to_sql(), "(NOT (a = b) AND NOT (c))".to_string());
+        assert_eq!(Where::not(Where::with_and()).to_sql(), "NOT TRUE".to_string());
+        assert_eq!(Where::not(Where::is_null("a")).to_sql(), "NOT (a IS NULL)".to_string());
+    }
+
+    #[test]
+    fn test_in() {
+        assert_eq!(Where::in_list("a", "1").to_sql(), "a IN (1)".to_string());
+        assert_eq!(Where::not_in_list("a", &["1", "2"]).to_sql(), "a NOT IN (1, 2)".to_string());
+        assert_eq!(Where::in_list("a", &[]).to_sql(), "FALSE".to_string());
+        assert_eq!(Where::not_in_list("a", &[]).to_sql(), "TRUE".to_string());
+
+        let subquery = Select::from("foo").columns("id").as_subquery();
+        assert_eq!(Where::in_query("a", &subquery).to_sql(), "a IN (SELECT id FROM foo)".to_string());
+        let query = Select::from("foo").columns("id");
+        assert_eq!(Where::not_in_query("a", query).to_sql(), "a NOT IN (SELECT id FROM foo)".to_string());
+    }
+
+    #[test]
+    fn test_exists() {
+        let query = Select::from("foo").filter("foo.a = bar.a");
+        assert_eq!(Where::exists(query.clone()).to_sql(), "EXISTS (SELECT * FROM foo WHERE foo.a = bar.a)".to_string());
+        assert_eq!(Where::not_exists(query).to_sql(), "NOT EXISTS (SELECT * FROM foo WHERE foo.a = bar.a)".to_string());
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(Where::between("a", "1", "2").to_sql(), "a BETWEEN 1 AND 2".to_string());
+        assert_eq!(Where::not_between("a", "1", "2").to_sql(), "a NOT BETWEEN 1 AND 2".to_string());
+        assert_eq!(Where::is_null("a").to_sql(), "a IS NULL".to_string());
+        assert_eq!(Where::is_not_null("a").to_sql(), "a IS NOT NULL".to_string());
+        assert_eq!(Where::is_distinct_from("a", "b").to_sql(), "a IS DISTINCT FROM b".to_string());
+        assert_eq!(Where::is_not_distinct_from("a", "b").to_sql(), "a IS NOT DISTINCT FROM b".to_string());
+    }
+
+    #[test]
+    fn test_nested_predicates() {
+        let clause = Where::with_or()
+            .expr(Where::with_and().expr(Where::is_null("a")).expr(Where::between("b", "1", "2")))
+            .expr(Where::in_list("c", &["3", "4"]));
+        assert_eq!(clause.to_sql(), "((a IS NULL AND b BETWEEN 1 AND 2) OR c IN (3, 4))".to_string());
+    }
+
+    #[test]
+    fn test_nested_where_clauses() {
+        let clause = Where::with_or()
+            .expr(Where::with_and().expr("foo = bar").expr("fizz = bazz"))
+            .expr(Where::with_and().expr("a = b").expr("c = d"));
+
+        let test_sql_string = {
+            "((foo = bar AND fizz = bazz) OR \
+            (a = b AND c = d))".to_string()
+        };
+        assert_eq!(clause.to_sql(), test_sql_string);
+    }
+
+    #[test]
+    fn test_really_nested_where_clauses() {
+        let foo = Where::with_and().expr("foo = bar").expr("fizz = bazz");
+        let bar = Where::with_and().expr("a = b").expr("c = d");
+        let bazz1 = Where::with_or().expr(foo.clone()).expr(bar.clone());
+        let bazz2 = Where::with_or().expr(bar.clone()).expr(foo.clone());
+        let fizz = Where::with_and().expr(bazz1).expr(bazz2);
+
+        let test_sql_string = {
+            "(((foo = bar AND fizz = bazz) OR \
+            (a = b AND c = d)) AND \
+            ((a = b AND c = d) OR \
+            (foo = bar AND fizz = bazz)))".to_string()
+        };
+        assert_eq!(fizz.to_sql(), test_sql_string);
+    }
+}
@@ -0,0 +1,402 @@
+//! Keeps simplification of `WHERE` trees.
+//!
+//! Every transformation here keeps the result the same under SQL's three-valued logic, so
+//! a normalized clause can replace the original one.
+
+use common::ToSQL;
+use super::{Where, WhereType, Predicate, Operator};
+
+impl<'a> Where<'a> {
+    /// Simplifies the group: nested groups with the same operator are flattened, duplicates
+    /// are removed, groups with a single expression lose their parentheses, `NOT` is applied
+    /// to constants and negatable predicates, and constants are short-circuited.
+    ///
+    /// String expressions with `OR` outside of parentheses keep them wherever they end up next
+    /// to `AND`, and an `AND` group of such an expression and something else is left as is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Where};
+    ///
+    /// let clause = Where::with_and()
+    ///     .expr(Where::with_and().expr("a = 1"))
+    ///     .expr(Where::with_and().expr("b = 2").expr(Where::with_and().expr("c = 3")))
+    ///     .expr("a = 1")
+    ///     .expr(true);
+    /// assert_eq!(clause.normalize().to_sql(), "(a = 1 AND b = 2 AND c = 3)".to_string());
+    ///
+    /// let clause = Where::with_or().expr("a = 1").expr(Where::not(false));
+    /// assert_eq!(clause.normalize().to_sql(), "TRUE".to_string());
+    /// ```
+    pub fn normalize(self) -> WhereType<'a> {
+        WhereType::Extended(self).normalize()
+    }
+
+    /// Converts the group into conjunctive normal form: `AND` of `OR`s of simple expressions.
+    /// Keep in mind that the result can grow exponentially.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Where};
+    ///
+    /// let clause = Where::with_or().expr("a").expr(Where::with_and().expr("b").expr("c"));
+    /// assert_eq!(clause.to_cnf().to_sql(), "((a OR b) AND (a OR c))".to_string());
+    /// ```
+    pub fn to_cnf(self) -> WhereType<'a> {
+        WhereType::Extended(self).to_cnf()
+    }
+
+    /// Converts the group into disjunctive normal form: `OR` of `AND`s of simple expressions.
+    /// Keep in mind that the result can grow exponentially.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lithium::{ToSQL, Where};
+    ///
+    /// let clause = Where::with_and().expr("a").expr(Where::with_or().expr("b").expr("c"));
+    /// assert_eq!(clause.to_dnf().to_sql(), "((a AND b) OR (a AND c))".to_string());
+    /// ```
+    pub fn to_dnf(self) -> WhereType<'a> {
+        WhereType::Extended(self).to_dnf()
+    }
+}
+
+impl<'a> WhereType<'a> {
+    /// Simplifies the expression. See `Where::normalize`.
+    pub fn normalize(self) -> WhereType<'a> {
+        match self {
+            WhereType::Simple(clause) => parse_constant(clause).unwrap_or(self),
+            WhereType::Owned(clause) => match parse_constant(&clause) {
+                Some(constant) => constant,
+                None => WhereType::Owned(clause)
+            },
+            WhereType::Predicate(Predicate::Not(clause)) => negate(clause.normalize()),
+            WhereType::Predicate(Predicate::In { ref values, negated, .. }) if values.is_empty() => {
+                WhereType::Constant(negated)
+            },
+            WhereType::Extended(ref clause) if is_opaque(clause) => WhereType::Owned(clause.to_sql()),
+            WhereType::Extended(clause) => {
+                let operator = clause.operator;
+                let expressions = clause.expressions.into_iter().map(|x| x.normalize()).collect();
+                group(operator, expressions)
+            },
+            other => other
+        }
+    }
+
+    /// Converts the expression into conjunctive normal form. See `Where::to_cnf`.
+    pub fn to_cnf(self) -> WhereType<'a> {
+        distribute(self.push_not(), Operator::And)
+    }
+
+    /// Converts the expression into disjunctive normal form. See `Where::to_dnf`.
+    pub fn to_dnf(self) -> WhereType<'a> {
+        distribute(self.push_not(), Operator::Or)
+    }
+
+    /// Normalizes the expression and moves `NOT` down to simple expressions using
+    /// De Morgan's laws.
+    fn push_not(self) -> WhereType<'a> {
+        match self.normalize() {
+            WhereType::Predicate(Predicate::Not(clause)) => match *clause {
+                WhereType::Extended(clause) => {
+                    let operator = opposite(&clause.operator);
+                    let expressions = clause.expressions.into_iter()
+                        .map(|x| negate(x).push_not())
+                        .collect();
+                    group(operator, expressions)
+                },
+                clause => negate(clause)
+            },
+            WhereType::Extended(clause) => {
+                let operator = clause.operator;
+                let expressions = clause.expressions.into_iter().map(|x| x.push_not()).collect();
+                group(operator, expressions)
+            },
+            other => other
+        }
+    }
+}
+
+fn parse_constant<'a>(clause: &str) -> Option<WhereType<'a>> {
+    let clause = clause.trim();
+    if clause.eq_ignore_ascii_case("TRUE") {
+        Some(WhereType::Constant(true))
+    } else if clause.eq_ignore_ascii_case("FALSE") {
+        Some(WhereType::Constant(false))
+    } else {
+        None
+    }
+}
+
+fn opposite(operator: &Operator) -> Operator {
+    match *operator {
+        Operator::And => Operator::Or,
+        Operator::Or => Operator::And
+    }
+}
+
+/// Negates already normalized expression.
+fn negate(clause: WhereType) -> WhereType {
+    match clause {
+        WhereType::Constant(value) => WhereType::Constant(!value),
+        WhereType::Predicate(predicate) => match predicate {
+            Predicate::Not(clause) => *clause,
+            Predicate::In { expression, values, negated } => {
+                WhereType::Predicate(Predicate::In { expression: expression, values: values, negated: !negated })
+            },
+            Predicate::InQuery { expression, query, negated } => {
+                WhereType::Predicate(Predicate::InQuery { expression: expression, query: query, negated: !negated })
+            },
+            Predicate::Exists { query, negated } => {
+                WhereType::Predicate(Predicate::Exists { query: query, negated: !negated })
+            },
+            Predicate::Between { expression, low, high, negated } => {
+                WhereType::Predicate(Predicate::Between { expression: expression, low: low, high: high, negated: !negated })
+            },
+            Predicate::IsNull { expression, negated } => {
+                WhereType::Predicate(Predicate::IsNull { expression: expression, negated: !negated })
+            },
+            Predicate::IsDistinctFrom { left, right, negated } => {
+                WhereType::Predicate(Predicate::IsDistinctFrom { left: left, right: right, negated: !negated })
+            }
+        },
+        other => WhereType::Predicate(Predicate::Not(Box::new(other)))
+    }
+}
+
+/// Builds a group out of already normalized expressions.
+fn group(operator: Operator, expressions: Vec<WhereType>) -> WhereType {
+    let mut flat = vec![];
+    for expression in expressions {
+        match expression {
+            WhereType::Extended(ref clause) if clause.operator == operator => {
+                flat.extend(clause.expressions.iter().cloned())
+            },
+            other => flat.push(other)
+        }
+    }
+
+    let mut rv: Vec<WhereType> = vec![];
+    let mut seen = vec![];
+    for expression in flat {
+        if let WhereType::Constant(value) = expression {
+            if value == operator.identity() {
+                continue;
+            }
+            return WhereType::Constant(value);
+        }
+
+        let sql = expression.to_sql();
+        if !seen.contains(&sql) {
+            seen.push(sql);
+            rv.push(expression);
+        }
+    }
+
+    match rv.len() {
+        0 => WhereType::Constant(operator.identity()),
+        1 => protect(rv.pop().unwrap()),
+        _ => {
+            if operator == Operator::And {
+                rv = rv.into_iter().map(protect).collect();
+            }
+            WhereType::Extended(Where {
+                operator: operator,
+                expressions: rv
+            })
+        }
+    }
+}
+
+/// Wraps a string expression with `OR` on the top level into parentheses, which it had as
+/// a part of a group, so it keeps its meaning next to `AND`.
+fn protect(clause: WhereType) -> WhereType {
+    match clause {
+        WhereType::Simple(ref raw) if has_top_level_or(raw) => WhereType::Owned(format!("({})", raw)),
+        WhereType::Owned(ref raw) if has_top_level_or(raw) => WhereType::Owned(format!("({})", raw)),
+        other => other
+    }
+}
+
+/// Checks whether the group can't be restructured: `AND` of a string expression with `OR` on
+/// the top level and something else, e.g. `(a OR b AND c)`, means `a OR (b AND c)`.
+fn is_opaque(clause: &Where) -> bool {
+    clause.operator == Operator::And && clause.expressions.len() > 1 && clause.expressions.iter().any(|x| match *x {
+        WhereType::Simple(raw) => has_top_level_or(raw),
+        WhereType::Owned(ref raw) => has_top_level_or(raw),
+        _ => false
+    })
+}
+
+/// Looks for `OR` outside of parentheses and quotes.
+fn has_top_level_or(clause: &str) -> bool {
+    let bytes = clause.as_bytes();
+    let mut depth = 0usize;
+    let mut quote = None;
+    for (idx, &byte) in bytes.iter().enumerate() {
+        match quote {
+            Some(q) => if byte == q { quote = None },
+            None => match byte {
+                b'\'' | b'"' => quote = Some(byte),
+                b'(' => depth += 1,
+                b')' => depth = depth.saturating_sub(1),
+                b'o' | b'O' if depth == 0 => {
+                    let is_word = |x: Option<&u8>| x.is_some_and(|x| x.is_ascii_alphanumeric() || *x == b'_');
+                    let next = bytes.get(idx + 1);
+                    if next.is_some_and(|x| x.eq_ignore_ascii_case(&b'r'))
+                        && !is_word(if idx == 0 { None } else { bytes.get(idx - 1) })
+                        && !is_word(bytes.get(idx + 2)) {
+                        return true;
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+    false
+}
+
+/// Distributes the opposite operator over `outer` one, e.g. builds `AND` of `OR`s for CNF.
+/// Expects an expression with `NOT` pushed down to simple expressions.
+fn distribute(clause: WhereType, outer: Operator) -> WhereType {
+    let terms = terms(clause, &outer);
+    let inner = opposite(&outer);
+    let groups = terms.into_iter().map(|x| group(inner.clone(), x)).collect();
+    group(outer, groups)
+}
+
+/// Returns a list of `outer`-joined terms, each of them being a list of expressions joined
+/// with the opposite operator.
+fn terms<'a>(clause: WhereType<'a>, outer: &Operator) -> Vec<Vec<WhereType<'a>>> {
+    match clause {
+        WhereType::Extended(clause) => {
+            if clause.operator == *outer {
+                clause.expressions.into_iter().flat_map(|x| terms(x, outer)).collect()
+            } else {
+                let mut rv = vec![vec![]];
+                for expression in clause.expressions {
+                    let expression = terms(expression, outer);
+                    rv = rv.iter()
+                        .flat_map(|left| expression.iter().map(move |right| {
+                            let mut term = left.clone();
+                            term.extend(right.iter().cloned());
+                            term
+                        }))
+                        .collect();
+                }
+                rv
+            }
+        },
+        other => vec![vec![other]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::has_top_level_or;
+    use common::ToSQL;
+    use select::Select;
+    use where_cl::{Where, WhereType, IntoWhereType};
+
+    #[test]
+    fn test_flatten() {
+        let clause = Where::with_or()
+            .expr(Where::with_or().expr("a").expr(Where::with_or().expr("b")))
+            .expr(Where::with_and().expr("c").expr(Where::with_and().expr("d")));
+        assert_eq!(clause.normalize().to_sql(), "(a OR b OR (c AND d))".to_string());
+    }
+
+    #[test]
+    fn test_string_with_or() {
+        let clause = Where::with_and().expr(Where::with_or().expr("x = 1 OR y = 1")).expr("z = 1");
+        assert_eq!(clause.normalize().to_sql(), "((x = 1 OR y = 1) AND z = 1)".to_string());
+
+        let clause = Where::with_and().expr(Where::with_and().expr("x = 1 or y = 1"));
+        assert_eq!(clause.normalize().to_sql(), "(x = 1 or y = 1)".to_string());
+
+        // means `x = 1 OR (y = 1 AND z = 1)`, so it's kept as is
+        let clause = Where::with_and().expr("x = 1 OR y = 1").expr(Where::with_and().expr("z = 1"));
+        assert_eq!(clause.normalize().to_sql(), "(x = 1 OR y = 1 AND (z = 1))".to_string());
+
+        let clause = Where::with_or().expr("a").expr("x = 1 OR y = 1");
+        assert_eq!(clause.to_cnf().to_sql(), "(a OR x = 1 OR y = 1)".to_string());
+        let clause = Where::with_or().expr(Where::with_and().expr("a").expr("b")).expr("x = 1 OR y = 1");
+        assert_eq!(clause.to_cnf().to_sql(), "((a OR x = 1 OR y = 1) AND (b OR x = 1 OR y = 1))".to_string());
+        let clause = Where::with_and().expr(Where::with_or().expr("a").expr("b")).expr(Where::with_or().expr("c OR d"));
+        assert_eq!(clause.to_dnf().to_sql(), "((a AND (c OR d)) OR (b AND (c OR d)))".to_string());
+    }
+
+    #[test]
+    fn test_has_top_level_or() {
+        assert!(has_top_level_or("a OR b"));
+        assert!(has_top_level_or("a=1 or(b)"));
+        assert!(!has_top_level_or("(a OR b) AND c"));
+        assert!(!has_top_level_or("name = 'x OR y' AND color = \"OR\""));
+        assert!(!has_top_level_or("orders.id = 1 AND color = 'red'"));
+    }
+
+    #[test]
+    fn test_single_expression() {
+        let clause = Where::with_and().expr(Where::with_or().expr("a = 1"));
+        assert!(clause.normalize() == WhereType::Simple("a = 1"));
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let clause = Where::with_and().expr("a").expr("b").expr("a".to_string()).expr("b");
+        assert_eq!(clause.normalize().to_sql(), "(a AND b)".to_string());
+    }
+
+    #[test]
+    fn test_constants() {
+        assert!(Where::with_and().normalize() == WhereType::Constant(true));
+        assert!(Where::with_or().normalize() == WhereType::Constant(false));
+        assert!(Where::with_and().expr("a").expr("false").normalize() == WhereType::Constant(false));
+        assert!(Where::with_or().expr("a").expr(" TRUE ").normalize() == WhereType::Constant(true));
+        assert!(Where::with_or().expr("a").expr(false).normalize() == WhereType::Simple("a"));
+        assert!(Where::with_and().expr("a").expr(Where::in_list("b", &[])).normalize() == WhereType::Constant(false));
+    }
+
+    #[test]
+    fn test_not() {
+        let clause = Where::not(Where::not("a")).into_where_type();
+        assert!(clause.normalize() == WhereType::Simple("a"));
+
+        let clause = Where::not(Where::is_null("a")).into_where_type();
+        assert_eq!(clause.normalize().to_sql(), "a IS NOT NULL".to_string());
+
+        let clause = Where::not(Where::exists(Select::from("foo"))).into_where_type();
+        assert_eq!(clause.normalize().to_sql(), "NOT EXISTS (SELECT * FROM foo)".to_string());
+
+        let clause = Where::not(Where::with_and().expr("a")).into_where_type();
+        assert_eq!(clause.normalize().to_sql(), "NOT (a)".to_string());
+    }
+
+    #[test]
+    fn test_cnf() {
+        let clause = Where::with_or()
+            .expr(Where::with_and().expr("a").expr("b"))
+            .expr(Where::with_and().expr("c").expr("d"));
+        let expected = "((a OR c) AND (a OR d) AND (b OR c) AND (b OR d))";
+        assert_eq!(clause.to_cnf().to_sql(), expected.to_string());
+    }
+
+    #[test]
+    fn test_dnf_with_not() {
+        let clause = Where::with_and()
+            .expr("a")
+            .expr(Where::not(Where::with_and().expr("b").expr(Where::is_null("c"))));
+        let expected = "((a AND NOT (b)) OR (a AND c IS NOT NULL))";
+        assert_eq!(clause.to_dnf().to_sql(), expected.to_string());
+    }
+
+    #[test]
+    fn test_normal_form_of_simple() {
+        assert!(Where::with_and().expr("a").to_cnf() == WhereType::Simple("a"));
+        assert!(Where::with_or().expr("a").expr(true).to_dnf() == WhereType::Constant(true));
+    }
+}